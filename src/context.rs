@@ -0,0 +1,256 @@
+use std::fmt::{Display, Formatter, Error as FmtError};
+use std::io::{BufRead, Read, Write, Result as IoResult};
+use std::ops::Range;
+use error::PositionalError;
+use spec::RecordSpec;
+
+const GUTTER_SEPARATOR: &'static str = " |";
+
+pub struct Capture<'a, T: 'a> {
+    inner: &'a mut T,
+    buffer: Option<&'a mut Vec<u8>>
+}
+
+impl<'a, T: 'a> Capture<'a, T> {
+    pub fn new(inner: &'a mut T, buffer: Option<&'a mut Vec<u8>>) -> Self {
+        Capture {
+            inner: inner,
+            buffer: buffer
+        }
+    }
+}
+
+impl<'a, T: Read + 'a> Read for Capture<'a, T> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        let amount = self.inner.read(buf)?;
+
+        if let Some(ref mut buffer) = self.buffer {
+            buffer.extend_from_slice(&buf[..amount]);
+        }
+
+        Ok(amount)
+    }
+}
+
+impl<'a, T: BufRead + 'a> BufRead for Capture<'a, T> {
+    fn fill_buf(&mut self) -> IoResult<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        if let Some(ref mut buffer) = self.buffer {
+            if let Ok(data) = self.inner.fill_buf() {
+                buffer.extend_from_slice(&data[..amount.min(data.len())]);
+            }
+        }
+
+        self.inner.consume(amount)
+    }
+}
+
+impl<'a, T: Write + 'a> Write for Capture<'a, T> {
+    fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
+        let amount = self.inner.write(buf)?;
+
+        if let Some(ref mut buffer) = self.buffer {
+            buffer.extend_from_slice(&buf[..amount]);
+        }
+
+        Ok(amount)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.inner.flush()
+    }
+}
+
+pub struct Counter<'a, T: 'a> {
+    inner: &'a mut T,
+    count: usize
+}
+
+impl<'a, T: 'a> Counter<'a, T> {
+    pub fn new(inner: &'a mut T) -> Self {
+        Counter {
+            inner: inner,
+            count: 0
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl<'a, T: Read + 'a> Read for Counter<'a, T> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        let amount = self.inner.read(buf)?;
+        self.count += amount;
+        Ok(amount)
+    }
+}
+
+impl<'a, T: BufRead + 'a> BufRead for Counter<'a, T> {
+    fn fill_buf(&mut self) -> IoResult<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.count += amount;
+        self.inner.consume(amount)
+    }
+}
+
+// fields holds the byte range of the record each field was read from, in the order they were read
+pub struct ErrorContext<'a> {
+    pub error: &'a PositionalError,
+    pub record: &'a [u8],
+    pub record_spec: &'a RecordSpec,
+    pub fields: &'a [(String, Range<usize>)]
+}
+
+impl<'a> ErrorContext<'a> {
+    pub fn new(error: &'a PositionalError, record: &'a [u8], record_spec: &'a RecordSpec, fields: &'a [(String, Range<usize>)]) -> Self {
+        ErrorContext {
+            error: error,
+            record: record,
+            record_spec: record_spec,
+            fields: fields
+        }
+    }
+
+    fn lines(&self) -> Vec<(Range<usize>, Vec<Range<usize>>)> {
+        let mut lines = Vec::new();
+        let mut start = 0;
+
+        for (_, line_ending) in self.record_spec.lines() {
+            let rest = &self.record[::std::cmp::min(start, self.record.len())..];
+            let length = if line_ending.is_empty() {
+                rest.len()
+            } else {
+                rest.windows(line_ending.len()).position(|window| window == line_ending).unwrap_or(rest.len())
+            };
+            let fields = self.fields.iter()
+                .map(|&(_, ref range)| range)
+                .filter(|range| range.start >= start && range.start <= start + length)
+                .map(|range| range.start - start..range.end - start)
+                .collect()
+            ;
+
+            lines.push((start..start + length, fields));
+            start += length + line_ending.len();
+        }
+
+        lines
+    }
+
+    fn failing_range(&self) -> Option<Range<usize>> {
+        self.error.position.as_ref()
+            .and_then(|position| position.field.as_ref())
+            .and_then(|field| self.fields.iter().rev().find(|&&(ref name, _)| name == field))
+            .map(|&(_, ref range)| range.clone())
+    }
+}
+
+impl<'a> Display for ErrorContext<'a> {
+    fn fmt(&self, f: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+        let lines = self.lines();
+        let failing = self.failing_range();
+        let index = failing.as_ref()
+            .and_then(|failing| lines.iter().position(|&(ref line, ref fields)| {
+                fields.iter().any(|field| field.start + line.start == failing.start)
+            }))
+            .unwrap_or(0)
+        ;
+        let (line, fields) = match lines.into_iter().nth(index) {
+            Some(line) => line,
+            None => return write!(f, "{}", self.error)
+        };
+        let width = fields.iter().map(|field| field.end.max(field.start + 1)).max().unwrap_or(0).max(line.end - line.start);
+        let number = self.error.line.map(|number| (number + index).to_string()).unwrap_or_default();
+        let gutter = number.len();
+
+        writeln!(f, "{}", self.error)?;
+        write!(f, "{:2$}{}", "", GUTTER_SEPARATOR, gutter)?;
+
+        for column in 1..width + 1 {
+            write!(f, "{}", if column % 10 == 0 { ((column / 10) % 10).to_string() } else { " ".to_string() })?;
+        }
+
+        write!(f, "\n{:2$}{}", "", GUTTER_SEPARATOR, gutter)?;
+
+        for column in 1..width + 1 {
+            write!(f, "{}", column % 10)?;
+        }
+
+        write!(f, "\n{:>2$}{}", number, GUTTER_SEPARATOR, gutter)?;
+
+        for byte in &self.record[line.start..::std::cmp::min(line.end, self.record.len())] {
+            write!(f, "{}", if *byte >= 0x20 && *byte < 0x7F { *byte as char } else { '.' })?;
+        }
+
+        write!(f, "\n{:2$}{}", "", GUTTER_SEPARATOR, gutter)?;
+
+        for column in 0..width {
+            write!(f, "{}", if fields.iter().any(|field| field.start == column) { '|' } else { ' ' })?;
+        }
+
+        if let Some(failing) = failing {
+            let start = failing.start - line.start;
+            write!(f, "\n{:2$}{}", "", GUTTER_SEPARATOR, gutter)?;
+            write!(f, "{:1$}", "", start)?;
+            write!(f, "{}", "^".repeat(::std::cmp::max(failing.end - failing.start, 1)))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use error::{Error, Position};
+    use spec::{Builder, FieldSpecBuilder, RecordSpecBuilder, SegmentSpecBuilder};
+    use std::io::Cursor;
+
+    #[test]
+    fn capture() {
+        let mut source = Cursor::new(b"abcdef".to_vec());
+        let mut buffer = Vec::new();
+        let mut data = Vec::new();
+        Read::take(Capture::new(&mut source, Some(&mut buffer)), 4).read_to_end(&mut data).unwrap();
+        assert_eq!(b"abcd".to_vec(), buffer);
+        let mut destination = Vec::new();
+        let mut buffer = Vec::new();
+        Capture::new(&mut destination, Some(&mut buffer)).write_all(b"xyz").unwrap();
+        Capture::new(&mut destination, None).write_all(b"123").unwrap();
+        assert_eq!(b"xyz".to_vec(), buffer);
+        assert_eq!(b"xyz123".to_vec(), destination);
+    }
+
+    #[test]
+    fn render_error_context() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("amount", FieldSpecBuilder::new().number().with_length(5))
+            .add_field("code", FieldSpecBuilder::new().string().with_length(3))
+            .add_segment(SegmentSpecBuilder::new()
+                .with_line_ending("\n")
+                .add_field("name", FieldSpecBuilder::new().string().with_length(4))
+            )
+            .build()
+            .unwrap()
+        ;
+        let fields = vec![("amount".to_string(), 0..5), ("code".to_string(), 5..8), ("name".to_string(), 9..13)];
+        let error = PositionalError::new(Error::CouldNotReadEnough(Vec::new()), Position::new("record1".to_string(), "code".to_string())).with_line(9);
+        assert_eq!(
+            "Could not read enough data. only got:  at field code of record record1 on line 9\n  |        \n  |12345678\n9 |00012x.c\n  ||    |  \n  |     ^^^",
+            ErrorContext::new(&error, b"00012x\tc\nabcd\n", &record_spec, &fields[..]).to_string()
+        );
+        let error = PositionalError::new(Error::CouldNotReadEnough(Vec::new()), Position::new("record1".to_string(), "name".to_string())).with_line(10);
+        assert_eq!(
+            "Could not read enough data. only got:  at field name of record record1 on line 10\n   |  \n   |12\n11 |ab\n   || \n   |^^",
+            ErrorContext::new(&error, b"00012x\tc\nab", &record_spec, &fields[..2].iter().cloned().chain(Some(("name".to_string(), 9..11))).collect::<Vec<_>>()[..]).to_string()
+        );
+    }
+}
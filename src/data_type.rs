@@ -1,6 +1,56 @@
+extern crate unicode_width;
+
 use std::ops::Range;
+use std::fmt::{Display, Formatter, Error as FmtError};
+use std::string::FromUtf8Error;
+use self::unicode_width::UnicodeWidthChar;
 use record::{DataRanges, Data};
-use super::BoxedErrorResult as Result;
+use error::BoxedError;
+
+type Result<T> = ::std::result::Result<T, DataHolderError>;
+
+#[derive(Debug)]
+pub enum DataHolderError {
+    InvalidUtf8(FromUtf8Error),
+    Other(BoxedError)
+}
+
+impl ::std::error::Error for DataHolderError {
+    fn description(&self) -> &str {
+        match *self {
+            DataHolderError::InvalidUtf8(_) => "The record data is not valid utf8",
+            DataHolderError::Other(_) => "There was an error creating the records data holder"
+        }
+    }
+
+    fn cause(&self) -> Option<&::std::error::Error> {
+        match *self {
+            DataHolderError::InvalidUtf8(ref e) => Some(e),
+            DataHolderError::Other(ref e) => Some(&**e)
+        }
+    }
+}
+
+impl Display for DataHolderError {
+    fn fmt(&self, f: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+        match *self {
+            DataHolderError::InvalidUtf8(ref e) => write!(f, "The record data is not valid utf8: {}", e),
+            DataHolderError::Other(ref e) => write!(f, "{}", e)
+        }
+    }
+}
+
+impl From<FromUtf8Error> for DataHolderError {
+    fn from(error: FromUtf8Error) -> Self {
+        DataHolderError::InvalidUtf8(error)
+    }
+}
+
+impl From<BoxedError> for DataHolderError {
+    fn from(error: BoxedError) -> Self {
+        DataHolderError::Other(error)
+    }
+}
 
 pub enum ShouldReadMore {
     More(usize),
@@ -36,6 +86,10 @@ pub trait FieldReadSupport: DataSupport {
             ShouldReadMore::NoMore
         }
     }
+
+    fn get_continuation_length(&self, _: &[u8]) -> usize {
+        0
+    }
 }
 
 pub trait RecordReadSupport: FieldReadSupport {
@@ -137,3 +191,141 @@ impl WriteSupport for StringSupport {
         Some(data[range].as_bytes())
     }
 }
+
+pub struct DisplayWidthSupport;
+
+impl DisplayWidthSupport {
+    fn get_string<'a>(&self, data: &'a [u8]) -> &'a str {
+        StringSupport.get_string(data)
+    }
+
+    pub fn get_char_width(&self, character: char) -> usize {
+        character.width().unwrap_or(0)
+    }
+
+    pub fn get_width<'a>(&self, string: &'a str) -> usize {
+        string.chars().fold(0, |width, character| width + self.get_char_width(character))
+    }
+
+    pub fn is_combining(&self, character: char) -> bool {
+        !character.is_control() && character.width() == Some(0)
+    }
+}
+
+impl DataSupport for DisplayWidthSupport {
+    type DataHolder = String;
+    fn get_length(&self, data: &[u8]) -> Length {
+        let string = self.get_string(data);
+
+        Length {
+            length: self.get_width(string),
+            remainder: data.len() - string.len()
+        }
+    }
+
+    fn get_byte_range(&self, data: &[u8], range: Range<usize>) -> Option<Range<usize>> {
+        let string = self.get_string(data);
+        let mut width = 0;
+        let mut start = None;
+
+        for (index, character) in string.char_indices() {
+            let character_width = self.get_char_width(character);
+
+            if width == range.start && start.is_none() && (character_width > 0 || range.start == range.end) {
+                start = Some(index);
+            }
+
+            if width == range.end && character_width > 0 {
+                return start.map(|start| start..index);
+            }
+
+            width += character_width;
+
+            if width > range.end || (start.is_none() && width > range.start) {
+                return None;
+            }
+        }
+
+        match (start, width == range.end) {
+            (Some(start), true) => Some(start..string.len()),
+            (None, true) if range.start == range.end => Some(string.len()..string.len()),
+            _ => None
+        }
+    }
+
+    fn get_size_hint(&self, length: usize) -> (usize, Option<usize>) {
+        (length / 2, Some(length * 4))
+    }
+}
+
+impl FieldReadSupport for DisplayWidthSupport {
+    fn should_read_more(&self, wanted_length: usize, data: &[u8]) -> ShouldReadMore {
+        let length = self.get_length(data);
+
+        if length.remainder > 0 {
+            ShouldReadMore::More(1)
+        } else if wanted_length > length.length {
+            ShouldReadMore::More(wanted_length - length.length)
+        } else {
+            ShouldReadMore::NoMore
+        }
+    }
+
+    fn get_continuation_length(&self, data: &[u8]) -> usize {
+        self.get_string(data).chars()
+            .take_while(|character| self.is_combining(*character))
+            .fold(0, |length, character| length + character.len_utf8())
+    }
+}
+
+impl RecordReadSupport for DisplayWidthSupport {
+    fn upcast_data(&self, data: Vec<u8>) -> Result<Self::DataHolder> {
+        Ok(String::from_utf8(data)?)
+    }
+}
+
+impl WriteSupport for DisplayWidthSupport {
+    fn get_data<'a>(&self, range: Range<usize>, data: &'a Self::DataHolder) -> Option<&'a [u8]> {
+        data.get(range).map(|data| data.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display_width_support() {
+        let support = DisplayWidthSupport;
+        let data = "ab\u{65e5}\u{672c}e\u{301}f".as_bytes();
+        let length = support.get_length(data);
+        assert_eq!(8, length.length);
+        assert_eq!(0, length.remainder);
+        let length = support.get_length(&data[..4]);
+        assert_eq!(2, length.length);
+        assert_eq!(2, length.remainder);
+        assert_eq!(Some(2..5), support.get_byte_range(data, 2..4));
+        assert_eq!(Some(5..8), support.get_byte_range(data, 4..6));
+        assert_eq!(Some(8..11), support.get_byte_range(data, 6..7));
+        assert_eq!(Some(11..12), support.get_byte_range(data, 7..8));
+        assert_eq!(None, support.get_byte_range(data, 3..4));
+        assert_eq!(None, support.get_byte_range(data, 7..9));
+        match support.should_read_more(4, &data[..4]) {
+            ShouldReadMore::More(1) => (),
+            _ => panic!("a partial character should request one more byte")
+        }
+        match support.should_read_more(6, &data[..5]) {
+            ShouldReadMore::More(2) => (),
+            _ => panic!("two columns should still be missing")
+        }
+        match support.should_read_more(6, &data[..8]) {
+            ShouldReadMore::NoMore => (),
+            _ => panic!("all six columns were read")
+        }
+        assert_eq!(2, support.get_continuation_length("\u{301}x".as_bytes()));
+        assert_eq!(5, support.get_continuation_length("\u{301}\u{200d}x".as_bytes()));
+        assert_eq!(0, support.get_continuation_length("\n\u{301}".as_bytes()));
+        assert_eq!(0, support.get_continuation_length("\0".as_bytes()));
+        assert_eq!(0, BinarySupport.get_continuation_length("\u{301}".as_bytes()));
+    }
+}
@@ -1,20 +1,44 @@
 use std::fmt::{Display, Formatter, Error as FmtError};
 use std::io::Error as IoError;
+use spec::validation::ValidationRule;
+use spec::stream::StreamError;
+use reader::parser::ParseError;
+use writer::formatter::FormatError;
+use data_type::DataHolderError;
 
 #[derive(Debug)]
 pub enum Error {
     SpecStreamReturnedNone,
-    SpecStreamError(BoxedError),
+    SpecStreamError(StreamError),
     RecordSpecNotFound(String),
-    ParserFailure(BoxedError),
-    FormatterFailure(BoxedError),
+    ParserFailure(ParseError),
+    FormatterFailure(FormatError),
     IoError(IoError),
     DataDoesNotMatchLineEnding(Vec<u8>, Vec<u8>),
+    DataDoesNotMatchSeparator(Vec<u8>, Vec<u8>),
     CouldNotReadEnough(Vec<u8>),
+    CharacterCrossesFieldBoundary(Vec<u8>),
+    EndOfFile,
+    UnexpectedEndOfFile(Vec<u8>),
     FormattedValueWrongLength(usize, Vec<u8>),
     FieldValueRequired,
-    DataHolderError(BoxedError),
-    FieldRequiredToBuild(&'static str)
+    DataHolderError(DataHolderError),
+    FieldRequiredToBuild(&'static str),
+    InvalidDescriptor(Vec<u8>),
+    DescriptorLengthMismatch(usize, usize),
+    RecordTooLong(usize, usize),
+    NoAlternativeForDiscriminator(Vec<u8>),
+    InvalidLengthPrefix(Vec<u8>),
+    InvalidBitmap(Vec<u8>),
+    BitmapFieldNotDefined(usize),
+    InvalidHeader(Vec<u8>),
+    ConstantMismatch(Vec<u8>, Vec<u8>),
+    ValidationFailed(ValidationRule, Option<Vec<u8>>),
+    RecordRuleFailed(String, Vec<String>),
+    FileRuleFailed(String, Vec<String>, Option<(String, usize)>),
+    FileRuleTargetFiltered(String, String),
+    BlankLineExceedsBuffer(usize),
+    SkipLineEndingAmbiguous(Vec<u8>, Vec<u8>)
 }
 
 impl ::std::error::Error for Error {
@@ -27,24 +51,61 @@ impl ::std::error::Error for Error {
             Error::FormatterFailure(_) => "The field formatter encountered an error",
             Error::IoError(_) => "An IO error occurred while trying to read",
             Error::CouldNotReadEnough(_) => "Could not read enough data",
+            Error::CharacterCrossesFieldBoundary(_) => "The last character of the field extends past the end of the field",
+            Error::EndOfFile => "The end of the data was reached at a record boundary",
+            Error::UnexpectedEndOfFile(_) => "The end of the data was reached in the middle of a record",
             Error::DataDoesNotMatchLineEnding(_, _) => "The encountered line ending doesn't match the expected one",
+            Error::DataDoesNotMatchSeparator(_, _) => "The encountered field separator doesn't match the expected one",
             Error::FormattedValueWrongLength(_, _) => "The value returned after padding is either longer or shorter than the length for the field",
             Error::FieldValueRequired => "The value for the given field is required since it has no default",
             Error::DataHolderError(_) => "There was an error creating the records data holder",
             Error::FieldRequiredToBuild(_) => "There is a required field missing",
+            Error::InvalidDescriptor(_) => "The record or block descriptor word is invalid",
+            Error::DescriptorLengthMismatch(_, _) => "The length given by the descriptor word doesn't match the data read",
+            Error::RecordTooLong(_, _) => "The record is too long to be described by a descriptor word",
+            Error::NoAlternativeForDiscriminator(_) => "There is no alternative layout for the discriminator value",
+            Error::InvalidLengthPrefix(_) => "The length prefix of the field is invalid",
+            Error::InvalidBitmap(_) => "The bitmap is invalid",
+            Error::BitmapFieldNotDefined(_) => "A bit is set in the bitmap for which no field is defined",
+            Error::InvalidHeader(_) => "The header line doesn't define any valid columns",
+            Error::ConstantMismatch(_, _) => "The value of a constant field doesn't match the constant",
+            Error::ValidationFailed(_, _) => "The value of the field failed a validation rule",
+            Error::RecordRuleFailed(_, _) => "The record failed a record level validation rule",
+            Error::FileRuleFailed(_, _, _) => "The record failed a file level integrity rule",
+            Error::FileRuleTargetFiltered(_, _) => "The record filter skips a record that a file rule depends on",
+            Error::BlankLineExceedsBuffer(_) => "A line starting with more whitespace than the buffer holds isn't blank",
+            Error::SkipLineEndingAmbiguous(_, _) => "The record specs disagree on the line ending skip rules should use",
         }
     }
 
     fn cause(&self) -> Option<&::std::error::Error> {
         match *self {
-            Error::SpecStreamError(ref e) => Some(&**e),
+            Error::SpecStreamError(ref e) => Some(e),
+            Error::ParserFailure(ref e) => Some(e),
+            Error::FormatterFailure(ref e) => Some(e),
             Error::IoError(ref e) => Some(e),
-            Error::DataHolderError(ref e) => Some(&**e),
+            Error::DataHolderError(ref e) => Some(e),
             _ => None
         }
     }
 }
 
+impl Error {
+    pub fn is_recoverable(&self) -> bool {
+        match *self {
+            Error::ParserFailure(_)
+            | Error::FormatterFailure(_)
+            | Error::FormattedValueWrongLength(_, _)
+            | Error::FieldValueRequired
+            | Error::ConstantMismatch(_, _)
+            | Error::ValidationFailed(_, _)
+            | Error::RecordRuleFailed(_, _)
+            | Error::FileRuleFailed(_, _, _) => true,
+            _ => false
+        }
+    }
+}
+
 macro_rules! write_with_data {
     ($f:expr, $m:expr, $($d:expr)*) => {
         write!($f, $m)?;
@@ -65,17 +126,34 @@ impl Display for Error {
             Error::ParserFailure(ref e) => write!(f, "The field parser encountered an error: {}", e),
             Error::FormatterFailure(ref e) => write!(f, "The field formatter encountered an error: {}", e),
             Error::IoError(ref e) => write!(f, "An IO error occurred while trying to read: {}", e),
+            Error::EndOfFile => write!(f, "The end of the data was reached at a record boundary"),
+            Error::UnexpectedEndOfFile(ref data) => write!(
+                f,
+                "The end of the data was reached in the middle of a record. only got: {}",
+                DataDisplayer(data)
+            ),
             Error::CouldNotReadEnough(ref data) => write!(
                 f,
                 "Could not read enough data. only got: {}",
                 DataDisplayer(data)
             ),
+            Error::CharacterCrossesFieldBoundary(ref data) => write!(
+                f,
+                "The last character of the field \"{}\" extends past the end of the field",
+                DataDisplayer(data)
+            ),
             Error::DataDoesNotMatchLineEnding(ref expected, ref actual) => write!(
                 f,
                 "The encountered line ending \"{}\" doesn't match the expected one \"{}\"",
                 DataDisplayer(actual),
                 DataDisplayer(expected)
             ),
+            Error::DataDoesNotMatchSeparator(ref expected, ref actual) => write!(
+                f,
+                "The encountered field separator \"{}\" doesn't match the expected one \"{}\"",
+                DataDisplayer(actual),
+                DataDisplayer(expected)
+            ),
             Error::FormattedValueWrongLength(ref expected_length, ref actual_value) => write!(
                 f,
                 "The value {} returned after padding is {} long and is required to be {} long for the given field",
@@ -86,6 +164,78 @@ impl Display for Error {
             Error::FieldValueRequired => write!(f, "The value for the field is required since it has no default"),
             Error::DataHolderError(ref e) => write!(f, "An error occurred while trying to create the record data holder: {}", e),
             Error::FieldRequiredToBuild(ref field) => write!(f, "{} must be set in order to build", field),
+            Error::InvalidDescriptor(ref descriptor) => write!(f, "The descriptor word {:?} is invalid", descriptor),
+            Error::DescriptorLengthMismatch(ref expected, ref actual) => write!(
+                f,
+                "The descriptor word gives a length of {} but {} was read",
+                expected,
+                actual
+            ),
+            Error::RecordTooLong(ref length, ref max_length) => write!(
+                f,
+                "The record is {} long but at most {} can be described by a descriptor word",
+                length,
+                max_length
+            ),
+            Error::NoAlternativeForDiscriminator(ref value) => write!(
+                f,
+                "There is no alternative layout for the discriminator value \"{}\"",
+                DataDisplayer(value)
+            ),
+            Error::InvalidLengthPrefix(ref prefix) => write!(f, "The length prefix \"{}\" of the field is invalid", DataDisplayer(prefix)),
+            Error::InvalidBitmap(ref bitmap) => write!(f, "The bitmap \"{}\" is invalid", DataDisplayer(bitmap)),
+            Error::BitmapFieldNotDefined(ref bit) => write!(f, "The bit {} is set in the bitmap but no field is defined for it", bit),
+            Error::InvalidHeader(ref line) => write!(f, "The header line \"{}\" doesn't define any valid columns", DataDisplayer(line)),
+            Error::ConstantMismatch(ref expected, ref actual) => write!(
+                f,
+                "The value \"{}\" doesn't match the constant \"{}\"",
+                DataDisplayer(actual),
+                DataDisplayer(expected)
+            ),
+            Error::ValidationFailed(ref rule, Some(ref value)) => write!(
+                f,
+                "The value \"{}\" failed the validation rule {}",
+                DataDisplayer(value),
+                rule
+            ),
+            Error::ValidationFailed(ref rule, None) => write!(f, "The missing value failed the validation rule {}", rule),
+            Error::RecordRuleFailed(ref rule, ref fields) => write!(
+                f,
+                "The record failed the rule {} involving the fields {}",
+                rule,
+                fields.join(", ")
+            ),
+            Error::FileRuleFailed(ref rule, ref fields, None) => write!(
+                f,
+                "The record failed the file rule {} involving the fields {}",
+                rule,
+                fields.join(", ")
+            ),
+            Error::FileRuleFailed(ref rule, ref fields, Some((ref record, ref line))) => write!(
+                f,
+                "The record failed the file rule {} involving the fields {} because of the {} record on line {}",
+                rule,
+                fields.join(", "),
+                record,
+                line
+            ),
+            Error::FileRuleTargetFiltered(ref rule, ref record) => write!(
+                f,
+                "The record filter skips the {} record which the file rule {} depends on",
+                record,
+                rule
+            ),
+            Error::BlankLineExceedsBuffer(ref consumed) => write!(
+                f,
+                "The line starts with {} bytes of whitespace, more than the buffer holds, but isn't blank, a larger buffer is needed to skip blank lines",
+                consumed
+            ),
+            Error::SkipLineEndingAmbiguous(ref first, ref second) => write!(
+                f,
+                "The record specs end lines with both \"{}\" and \"{}\", the line ending skip rules use must be set",
+                DataDisplayer(first),
+                DataDisplayer(second)
+            ),
         }
     }
 }
@@ -118,23 +268,31 @@ impl From<PositionalError> for Error {
 #[derive(Debug)]
 pub struct PositionalError {
     pub error: Error,
-    pub position: Option<Position>
+    pub position: Option<Position>,
+    pub line: Option<usize>
 }
 
 impl PositionalError {
     pub fn new(error: Error, position: Position) -> Self {
         PositionalError {
             error: error,
-            position: Some(position)
+            position: Some(position),
+            line: None
         }
     }
+
+    pub fn with_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
 }
 
 impl From<Error> for PositionalError {
     fn from(error: Error) -> Self {
         PositionalError {
             error: error,
-            position: None
+            position: None,
+            line: None
         }
     }
 }
@@ -147,7 +305,8 @@ impl<'a> From<(FieldError, &'a str)> for PositionalError {
                 Position::new(data.1.to_string(), field)
             } else {
                 Position::new_from_record(data.1.to_string())
-            })
+            }),
+            line: None
         }
     }
 }
@@ -168,6 +327,11 @@ impl Display for PositionalError {
             None => self.error.fmt(f),
             Some(Position { ref record, field: None }) => write!(f, "{} at record {}", self.error, record),
             Some(Position { ref record, field: Some(ref field) }) => write!(f, "{} at field {} of record {}", self.error, field, record)
+        }?;
+
+        match self.line {
+            None => Ok(()),
+            Some(line) => write!(f, " on line {}", line)
         }
     }
 }
@@ -185,6 +349,19 @@ impl FieldError {
             field: Some(field)
         }
     }
+
+    pub fn collect<'a, T: Default>(result: ::std::result::Result<T, FieldError>, errors: &'a mut Option<&mut Vec<FieldError>>) -> ::std::result::Result<T, FieldError> {
+        match result {
+            Err(e) => match *errors {
+                Some(ref mut errors) if e.error.is_recoverable() => {
+                    errors.push(e);
+                    Ok(T::default())
+                },
+                _ => Err(e)
+            },
+            result => result
+        }
+    }
 }
 
 impl From<IoError> for FieldError {
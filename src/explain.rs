@@ -0,0 +1,198 @@
+use std::fmt::{Display, Formatter, Error as FmtError};
+use std::ops::Range;
+use error::Error;
+use spec::RecordSpec;
+use reader::parser::FieldParser;
+use data_type::FieldReadSupport;
+
+// range is None when the field's position depends on the data of the line, such as fields behind a
+// length prefix or a bitmap, those fields are left unresolved rather than read from a guessed position
+#[derive(Debug)]
+pub struct FieldExplanation {
+    pub name: String,
+    pub range: Option<Range<usize>>,
+    pub raw: Vec<u8>,
+    pub value: Option<Vec<u8>>,
+    pub error: Option<Error>
+}
+
+#[derive(Debug)]
+pub struct Explanation {
+    pub fields: Vec<FieldExplanation>
+}
+
+pub fn explain<'a, T, U>(line: &'a [u8], record_spec: &'a RecordSpec, parser: &'a T, read_support: &'a U) -> Explanation
+    where T: FieldParser<U> + 'a,
+          U: FieldReadSupport + 'a
+{
+    let mut fields: Vec<FieldExplanation> = record_spec.lines()
+        .flat_map(|(field_specs, _)| field_specs.iter())
+        .map(|(name, field_spec)| {
+            let range = record_spec.field_range(name);
+            let mut explanation = FieldExplanation {
+                name: name.clone(),
+                range: range.clone(),
+                raw: Vec::new(),
+                value: None,
+                error: None
+            };
+            let range = match range {
+                Some(range) => range,
+                None => return explanation
+            };
+
+            match read_support.get_byte_range(line, range.clone()) {
+                Some(ref byte_range) if byte_range.end <= line.len() => {
+                    explanation.raw = line[byte_range.clone()].to_vec();
+                },
+                _ => {
+                    explanation.raw = line[::std::cmp::min(range.start, line.len())..::std::cmp::min(range.end, line.len())].to_vec();
+                    explanation.error = Some(Error::CouldNotReadEnough(explanation.raw.clone()));
+                    return explanation;
+                }
+            }
+
+            if field_spec.null.as_ref().map_or(false, |null| null.matches(&explanation.raw[..], field_spec)) {
+                return explanation;
+            }
+
+            let mut value = Vec::new();
+
+            match parser.parse(&explanation.raw[..], field_spec, &mut value, read_support) {
+                Ok(()) => explanation.value = Some(value),
+                Err(e) => explanation.error = Some(Error::ParserFailure(e))
+            }
+
+            explanation
+        })
+        .collect()
+    ;
+
+    fields.sort_by_key(|field| field.range.as_ref().map_or(usize::max_value(), |range| range.start));
+
+    Explanation { fields: fields }
+}
+
+struct BytesDisplayer<'a>(&'a [u8]);
+
+impl<'a> Display for BytesDisplayer<'a> {
+    fn fmt(&self, f: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+        for byte in self.0 {
+            if *byte >= 0x20 && *byte < 0x7F {
+                write!(f, "{}", *byte as char)?;
+            } else {
+                write!(f, "\\x{:02X}", byte)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+        let mut rows = vec![["field".to_string(), "columns".to_string(), "raw".to_string(), "value".to_string(), "error".to_string()]];
+
+        for field in &self.fields {
+            rows.push([
+                field.name.clone(),
+                field.range.as_ref().map_or("-".to_string(), |range| format!("{}-{}", range.start + 1, range.end)),
+                BytesDisplayer(&field.raw[..]).to_string(),
+                match (&field.value, &field.error) {
+                    (&Some(ref value), _) => BytesDisplayer(&value[..]).to_string(),
+                    (&None, &None) if field.range.is_some() => "(null)".to_string(),
+                    (&None, &None) => "(unresolved)".to_string(),
+                    _ => String::new()
+                },
+                field.error.as_ref().map_or(String::new(), |error| error.to_string())
+            ]);
+        }
+
+        let mut widths = [0; 5];
+
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row.iter()) {
+                *width = ::std::cmp::max(*width, cell.chars().count());
+            }
+        }
+
+        for (index, row) in rows.iter().enumerate() {
+            if index != 0 {
+                writeln!(f)?;
+            }
+
+            let length = row.iter().rposition(|cell| !cell.is_empty()).map_or(0, |position| position + 1);
+            let line = row[..length].iter()
+                .zip(widths.iter())
+                .enumerate()
+                .map(|(column, (cell, width))| if column + 1 == length { cell.clone() } else { format!("{:1$}", cell, width) })
+                .collect::<Vec<_>>()
+                .join(" | ")
+            ;
+            write!(f, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use spec::{Builder, FieldSpecBuilder, RecordSpecBuilder, NullValue};
+    use reader::parser::DefaultParser;
+    use data_type::BinarySupport;
+
+    #[test]
+    fn explain_line() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("amount", FieldSpecBuilder::new().number().with_length(5))
+            .add_field("code", FieldSpecBuilder::new().string().with_length(3).with_null(NullValue::Blanks))
+            .add_field("name", FieldSpecBuilder::new().string().with_length(4))
+            .build()
+            .unwrap()
+        ;
+        let explanation = explain(b"00012   a\tb", &record_spec, &DefaultParser, &BinarySupport);
+        assert_eq!(
+            vec!["amount".to_string(), "code".to_string(), "name".to_string()],
+            explanation.fields.iter().map(|field| field.name.clone()).collect::<Vec<_>>()
+        );
+        assert_eq!(Some(b"12".to_vec()), explanation.fields[0].value);
+        assert_eq!(None, explanation.fields[1].value);
+        assert_eq!(b"a\tb".to_vec(), explanation.fields[2].raw);
+        match explanation.fields[2].error {
+            Some(Error::CouldNotReadEnough(_)) => (),
+            ref e => panic!("unexpected error {:?}", e)
+        }
+        assert_eq!(
+            "field  | columns | raw    | value  | error\n\
+             amount | 1-5     | 00012  | 12\n\
+             code   | 6-8     |        | (null)\n\
+             name   | 9-12    | a\\x09b |        | Could not read enough data. only got: a\tb",
+            explanation.to_string()
+        );
+    }
+
+    #[test]
+    fn explain_line_with_unresolved_fields() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("field1", FieldSpecBuilder::new().string().with_length(2))
+            .add_field("field2", FieldSpecBuilder::new().string().with_length(5).with_length_prefix(1))
+            .add_field("field3", FieldSpecBuilder::new().string().with_length(2))
+            .build()
+            .unwrap()
+        ;
+        let explanation = explain(b"ab3xyzcd", &record_spec, &DefaultParser, &BinarySupport);
+        assert_eq!(Some(b"ab".to_vec()), explanation.fields[0].value);
+        assert!(explanation.fields[1..].iter().all(|field| field.range.is_none() && field.value.is_none() && field.raw.is_empty()));
+        assert_eq!(
+            "field  | columns | raw | value        | error\n\
+             field1 | 1-2     | ab  | ab\n\
+             field2 | -       |     | (unresolved)\n\
+             field3 | -       |     | (unresolved)",
+            explanation.to_string()
+        );
+    }
+}
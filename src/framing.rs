@@ -0,0 +1,116 @@
+use std::io::Read;
+use error::Error;
+use super::Result;
+
+pub const DESCRIPTOR_LENGTH: usize = 4;
+const MAX_DESCRIPTOR_VALUE: usize = 0xFFFF;
+const EXTENDED_BLOCK_FLAG: u8 = 0x80;
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Framing {
+    None,
+    Rdw,
+    Blocked(usize)
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Framing::None
+    }
+}
+
+pub fn read_record_descriptor<'a, T: Read + 'a>(reader: &'a mut T) -> Result<usize> {
+    let descriptor = read_descriptor(reader)?;
+
+    if descriptor[2] != 0 || descriptor[3] != 0 {
+        return Err(Error::InvalidDescriptor(descriptor.to_vec()));
+    }
+
+    content_length(((descriptor[0] as usize) << 8) | descriptor[1] as usize, &descriptor)
+}
+
+pub fn read_block_descriptor<'a, T: Read + 'a>(reader: &'a mut T) -> Result<usize> {
+    let descriptor = read_descriptor(reader)?;
+
+    if descriptor[0] & EXTENDED_BLOCK_FLAG != 0 {
+        return content_length(
+            ((descriptor[0] & !EXTENDED_BLOCK_FLAG) as usize) << 24
+                | (descriptor[1] as usize) << 16
+                | (descriptor[2] as usize) << 8
+                | descriptor[3] as usize,
+            &descriptor
+        );
+    }
+
+    if descriptor[2] != 0 || descriptor[3] != 0 {
+        return Err(Error::InvalidDescriptor(descriptor.to_vec()));
+    }
+
+    content_length(((descriptor[0] as usize) << 8) | descriptor[1] as usize, &descriptor)
+}
+
+pub fn write_descriptor(content_length: usize, destination: &mut Vec<u8>) -> Result<()> {
+    let length = content_length + DESCRIPTOR_LENGTH;
+
+    if length > MAX_DESCRIPTOR_VALUE {
+        return Err(Error::RecordTooLong(content_length, MAX_DESCRIPTOR_VALUE - DESCRIPTOR_LENGTH));
+    }
+
+    destination.extend_from_slice(&[(length >> 8) as u8, length as u8, 0, 0]);
+    Ok(())
+}
+
+fn read_descriptor<'a, T: Read + 'a>(reader: &'a mut T) -> Result<[u8; DESCRIPTOR_LENGTH]> {
+    let mut buffer = Vec::with_capacity(DESCRIPTOR_LENGTH);
+
+    match reader.take(DESCRIPTOR_LENGTH as u64).read_to_end(&mut buffer)? {
+        0 => return Err(Error::EndOfFile),
+        DESCRIPTOR_LENGTH => (),
+        _ => return Err(Error::CouldNotReadEnough(buffer))
+    }
+
+    Ok([buffer[0], buffer[1], buffer[2], buffer[3]])
+}
+
+fn content_length(length: usize, descriptor: &[u8]) -> Result<usize> {
+    if length < DESCRIPTOR_LENGTH {
+        return Err(Error::InvalidDescriptor(descriptor.to_vec()));
+    }
+
+    Ok(length - DESCRIPTOR_LENGTH)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use error::Error;
+    use std::io::Cursor;
+
+    #[test]
+    fn record_descriptor() {
+        let mut buf = Cursor::new(vec![0, 9, 0, 0, 0, 4, 0, 0, 0, 9, 1, 0, 0, 2, 0, 0, 0, 9]);
+        assert_result!(Ok(5), read_record_descriptor(&mut buf));
+        assert_result!(Ok(0), read_record_descriptor(&mut buf));
+        assert_result!(Err(Error::InvalidDescriptor(ref v)) if *v == vec![0, 9, 1, 0], read_record_descriptor(&mut buf));
+        assert_result!(Err(Error::InvalidDescriptor(ref v)) if *v == vec![0, 2, 0, 0], read_record_descriptor(&mut buf));
+        assert_result!(Err(Error::CouldNotReadEnough(ref v)) if *v == vec![0, 9], read_record_descriptor(&mut buf));
+        assert_result!(Err(Error::EndOfFile), read_record_descriptor(&mut buf));
+    }
+
+    #[test]
+    fn block_descriptor() {
+        let mut buf = Cursor::new(vec![1, 4, 0, 0, 0x80, 1, 0, 4, 0, 9, 0, 1]);
+        assert_result!(Ok(256), read_block_descriptor(&mut buf));
+        assert_result!(Ok(65536), read_block_descriptor(&mut buf));
+        assert_result!(Err(Error::InvalidDescriptor(ref v)) if *v == vec![0, 9, 0, 1], read_block_descriptor(&mut buf));
+    }
+
+    #[test]
+    fn descriptor_writing() {
+        let mut destination = Vec::new();
+        assert_result!(Ok(()), write_descriptor(5, &mut destination));
+        assert_result!(Ok(()), write_descriptor(300, &mut destination));
+        assert_eq!(vec![0, 9, 0, 0, 1, 48, 0, 0], destination);
+        assert_result!(Err(Error::RecordTooLong(65532, 65531)), write_descriptor(65532, &mut destination));
+    }
+}
@@ -0,0 +1,210 @@
+use std::io::BufRead;
+use std::iter::repeat;
+use error::Error;
+use spec::{Builder, FieldSpecBuilder, RecordSpec, RecordSpecBuilder};
+use super::Result;
+
+const UNDERLINE: u8 = b'-';
+const SPACE: u8 = b' ';
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Header {
+    pub name: String,
+    pub line_ending: Vec<u8>,
+    pub underline: bool
+}
+
+impl Header {
+    pub fn new<T: Into<String>, U: Into<Vec<u8>>>(name: T, line_ending: U, underline: bool) -> Self {
+        Header {
+            name: name.into(),
+            line_ending: line_ending.into(),
+            underline: underline
+        }
+    }
+}
+
+pub fn read_header<'a, T: BufRead + 'a>(reader: &'a mut T, header: &'a Header) -> Result<RecordSpec> {
+    let names = read_line(reader, &header.line_ending)?;
+    let columns = if header.underline {
+        let underline = read_line(reader, &header.line_ending)?;
+
+        if underline.iter().any(|byte| *byte != UNDERLINE && *byte != SPACE) {
+            return Err(Error::InvalidHeader(underline));
+        }
+
+        column_starts(&underline, UNDERLINE, underline.len())
+    } else {
+        column_starts(&names, SPACE, names.len())
+    };
+
+    if columns.is_empty() {
+        return Err(Error::InvalidHeader(names));
+    }
+
+    let mut builder = RecordSpecBuilder::new().with_line_ending(header.line_ending.clone());
+    let mut order = Vec::new();
+
+    for &(start, end) in &columns {
+        let name = names.get(start..::std::cmp::min(end, names.len()))
+            .and_then(|name| ::std::str::from_utf8(name).ok())
+            .map(|name| name.trim().to_string())
+            .unwrap_or_else(String::new)
+        ;
+
+        if name.is_empty() {
+            return Err(Error::InvalidHeader(names));
+        }
+
+        builder = builder.add_field(name.clone(), FieldSpecBuilder::new().string().with_length(end - start));
+        order.push(name);
+    }
+
+    builder.with_field_order(order).build().map_err(|_| Error::InvalidHeader(names))
+}
+
+pub fn write_header<'a>(record_spec: &'a RecordSpec, header: &'a Header, destination: &'a mut Vec<u8>) -> Result<()> {
+    let mut underline = Vec::new();
+
+    let (field_specs, _) = record_spec.lines().next().expect("a record spec always has at least one line");
+
+    for (name, field_spec) in field_specs {
+        if name.len() > field_spec.length {
+            return Err(Error::FormattedValueWrongLength(field_spec.length, name.as_bytes().to_vec()));
+        }
+
+        destination.extend_from_slice(name.as_bytes());
+        destination.extend(repeat(SPACE).take(field_spec.length - name.len()));
+
+        if field_spec.length > 0 {
+            underline.extend(repeat(UNDERLINE).take(field_spec.length - 1));
+            underline.push(SPACE);
+        }
+    }
+
+    destination.extend_from_slice(&header.line_ending[..]);
+
+    if header.underline {
+        destination.extend_from_slice(&underline[..]);
+        destination.extend_from_slice(&header.line_ending[..]);
+    }
+
+    Ok(())
+}
+
+fn read_line<'a, T: BufRead + 'a>(reader: &'a mut T, line_ending: &'a [u8]) -> Result<Vec<u8>> {
+    let mut line = Vec::new();
+    let last = *line_ending.last().unwrap_or(&b'\n');
+
+    reader.read_until(last, &mut line)?;
+
+    if line_ending.is_empty() || !line.ends_with(line_ending) {
+        return Err(Error::CouldNotReadEnough(line));
+    }
+
+    let length = line.len() - line_ending.len();
+    line.truncate(length);
+    Ok(line)
+}
+
+fn column_starts(line: &[u8], marker: u8, length: usize) -> Vec<(usize, usize)> {
+    let is_start = |index: usize| if marker == SPACE {
+        line[index] != SPACE && (index == 0 || line[index - 1] == SPACE)
+    } else {
+        line[index] == marker && (index == 0 || line[index - 1] != marker)
+    };
+    let starts: Vec<usize> = (0..line.len()).filter(|index| is_start(*index)).collect();
+
+    starts.iter().enumerate()
+        .map(|(index, start)| (*start, starts.get(index + 1).cloned().unwrap_or(length)))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use error::Error;
+    use spec::{Builder, FieldSpecBuilder, RecordSpecBuilder};
+    use std::io::Cursor;
+
+    #[test]
+    fn header_from_names() {
+        let header = Header::new("header", "\n", false);
+        let mut buf = Cursor::new("amount  code name  \n".as_bytes());
+        assert_result!(
+            Ok(ref spec) if *spec == RecordSpecBuilder::new()
+                .with_line_ending("\n")
+                .add_field("amount", FieldSpecBuilder::new().string().with_length(8))
+                .add_field("code", FieldSpecBuilder::new().string().with_length(5))
+                .add_field("name", FieldSpecBuilder::new().string().with_length(6))
+                .with_field_order(vec!["amount", "code", "name"])
+                .build()
+                .unwrap(),
+            read_header(&mut buf, &header)
+        );
+        let mut buf = Cursor::new("name  code\n".as_bytes());
+        let spec = read_header(&mut buf, &header).unwrap();
+        assert_eq!(Some(0..6), spec.field_range("name"));
+        assert_eq!(Some(6..10), spec.field_range("code"));
+        assert_eq!(vec!["name".to_string(), "code".to_string()], spec.field_order);
+        let mut buf = Cursor::new("code name code\n".as_bytes());
+        assert_result!(Err(Error::InvalidHeader(_)), read_header(&mut buf, &header));
+        let mut buf = Cursor::new("   \n".as_bytes());
+        assert_result!(Err(Error::InvalidHeader(_)), read_header(&mut buf, &header));
+        let mut buf = Cursor::new("amount".as_bytes());
+        assert_result!(Err(Error::CouldNotReadEnough(_)), read_header(&mut buf, &header));
+    }
+
+    #[test]
+    fn header_from_underline() {
+        let header = Header::new("header", "\r\n", true);
+        let mut buf = Cursor::new("a long b\r\n------ ---\r\n".as_bytes());
+        assert_result!(
+            Ok(ref spec) if *spec == RecordSpecBuilder::new()
+                .with_line_ending("\r\n")
+                .add_field("a long", FieldSpecBuilder::new().string().with_length(7))
+                .add_field("b", FieldSpecBuilder::new().string().with_length(3))
+                .with_field_order(vec!["a long", "b"])
+                .build()
+                .unwrap(),
+            read_header(&mut buf, &header)
+        );
+        let mut buf = Cursor::new("a b\r\n-+ -\r\n".as_bytes());
+        assert_result!(Err(Error::InvalidHeader(ref line)) if *line == b"-+ -".to_vec(), read_header(&mut buf, &header));
+    }
+
+    #[test]
+    fn header_writing() {
+        let record_spec = RecordSpecBuilder::new()
+            .add_field("amount", FieldSpecBuilder::new().number().with_length(8))
+            .add_field("code", FieldSpecBuilder::new().string().with_length(5))
+            .build()
+            .unwrap()
+        ;
+        let mut destination = Vec::new();
+        assert_result!(Ok(()), write_header(&record_spec, &Header::new("header", "\n", true), &mut destination));
+        assert_eq!("amount  code \n------- ---- \n".to_string(), String::from_utf8(destination.clone()).unwrap());
+        let spec = read_header(&mut Cursor::new(destination), &Header::new("header", "\n", true)).unwrap();
+        assert_eq!(Some(0..8), spec.field_range("amount"));
+        assert_eq!(Some(8..13), spec.field_range("code"));
+        let record_spec = RecordSpecBuilder::new()
+            .add_field("amount", FieldSpecBuilder::new().number().with_length(8))
+            .add_field("code", FieldSpecBuilder::new().string().with_length(5))
+            .with_field_order(vec!["code", "amount"])
+            .build()
+            .unwrap()
+        ;
+        let mut destination = Vec::new();
+        assert_result!(Ok(()), write_header(&record_spec, &Header::new("header", "\n", false), &mut destination));
+        assert_eq!("code amount  \n".to_string(), String::from_utf8(destination).unwrap());
+        let mut destination = Vec::new();
+        assert_result!(
+            Err(Error::FormattedValueWrongLength(2, ref name)) if *name == b"code".to_vec(),
+            write_header(
+                &RecordSpecBuilder::new().add_field("code", FieldSpecBuilder::new().string().with_length(2)).build().unwrap(),
+                &Header::new("header", "\n", false),
+                &mut destination
+            )
+        );
+    }
+}
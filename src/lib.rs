@@ -41,6 +41,10 @@ pub mod record;
 pub mod spec;
 pub mod writer;
 pub mod data_type;
+pub mod framing;
+pub mod header;
+pub mod context;
+pub mod explain;
 
 pub use self::error::{Error, FieldError, PositionalError, Position, BoxedError};
 pub use self::reader::{Reader, ReaderBuilder};
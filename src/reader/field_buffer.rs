@@ -1,4 +1,7 @@
 use std::collections::VecDeque;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+use record::{Data, DataRanges, Record};
 
 pub trait Source {
     fn get(&mut self) -> Option<Vec<u8>>;
@@ -32,4 +35,145 @@ impl Source for VecDeque<Vec<u8>> {
     fn get(&mut self) -> Option<Vec<u8>> {
         self.pop_front()
     }
-}
\ No newline at end of file
+}
+pub trait IntoBuffer {
+    fn into_buffer(self) -> Vec<u8>;
+}
+
+impl IntoBuffer for Vec<u8> {
+    fn into_buffer(self) -> Vec<u8> {
+        self
+    }
+}
+
+impl IntoBuffer for String {
+    fn into_buffer(self) -> Vec<u8> {
+        self.into_bytes()
+    }
+}
+
+impl<T: DataRanges, U: IntoBuffer> IntoBuffer for Data<T, U> {
+    fn into_buffer(self) -> Vec<u8> {
+        self.data.into_buffer()
+    }
+}
+
+impl<T: DataRanges, U: IntoBuffer> IntoBuffer for Record<T, U> {
+    fn into_buffer(self) -> Vec<u8> {
+        self.data.into_buffer()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Pool {
+    buffers: Arc<Mutex<Vec<Vec<u8>>>>
+}
+
+impl Pool {
+    pub fn new() -> Self {
+        Pool::default()
+    }
+
+    pub fn recycle<T: IntoBuffer>(&self, value: T) {
+        let mut buffer = value.into_buffer();
+        buffer.clear();
+
+        if let Ok(mut buffers) = self.buffers.lock() {
+            buffers.push(buffer);
+        }
+    }
+
+    pub fn guard<T: IntoBuffer>(&self, value: T) -> Recycle<T> {
+        Recycle {
+            value: Some(value),
+            pool: self.clone()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffers.lock().map(|buffers| buffers.len()).unwrap_or(0)
+    }
+}
+
+impl Source for Pool {
+    fn get(&mut self) -> Option<Vec<u8>> {
+        self.buffers.lock().ok().and_then(|mut buffers| buffers.pop())
+    }
+}
+
+#[derive(Debug)]
+pub struct Recycle<T: IntoBuffer> {
+    value: Option<T>,
+    pool: Pool
+}
+
+impl<T: IntoBuffer> Recycle<T> {
+    pub fn into_inner(mut self) -> T {
+        self.value.take().expect("the value is only taken on drop")
+    }
+}
+
+impl<T: IntoBuffer> Deref for Recycle<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_ref().expect("the value is only taken on drop")
+    }
+}
+
+impl<T: IntoBuffer> DerefMut for Recycle<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value.as_mut().expect("the value is only taken on drop")
+    }
+}
+
+impl<T: IntoBuffer> Drop for Recycle<T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.value.take() {
+            self.pool.recycle(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+    use std::ops::Range;
+
+    #[test]
+    fn pool() {
+        let mut pool = Pool::new();
+        assert_eq!(None, pool.get());
+        let mut buffer = Vec::with_capacity(16);
+        buffer.extend_from_slice(b"hello");
+        pool.recycle(buffer);
+        assert_eq!(1, pool.len());
+        let buffer = pool.get().unwrap();
+        assert!(buffer.is_empty());
+        assert!(buffer.capacity() >= 16);
+        assert_eq!(0, pool.len());
+        pool.recycle("hello".to_string());
+        assert_eq!(1, pool.len());
+    }
+
+    #[test]
+    fn recycle_guard() {
+        let pool = Pool::new();
+        let record: Record<HashMap<String, Range<usize>>, Vec<u8>> = Record {
+            data: Data { ranges: HashMap::new(), data: b"abc".to_vec(), nulls: Default::default() },
+            name: "a".to_string()
+        };
+
+        {
+            let mut guard = pool.guard(record);
+            guard.name = "b".to_string();
+            assert_eq!(b"abc".to_vec(), guard.data.data);
+        }
+
+        assert_eq!(1, pool.len());
+        let guard = pool.guard(b"def".to_vec());
+        assert_eq!(b"def".to_vec(), guard.into_inner());
+        assert_eq!(1, pool.len());
+    }
+}
@@ -1,18 +1,29 @@
 pub mod parser;
 pub mod spec;
 pub mod field_buffer;
+pub mod skip;
+pub mod slice;
 
-use spec::{RecordSpec, FieldSpec};
-use std::collections::{HashMap};
+use spec::{Spec, RecordSpec, FieldSpec, BitmapSpec, Fields};
+use spec::validation::{self, FileRule, FileValidator};
+use spec::compiled::{self, CompiledSpec, FieldTable, IdIndex};
+use std::collections::{HashMap, BTreeMap, BTreeSet};
+use std::ops::Range;
+use std::sync::Arc;
 use std::io::{Read, BufRead};
 use std::borrow::{Borrow, BorrowMut};
-use error::Error;
+use error::{Error, FieldError, PositionalError};
+use header::{self, Header};
+use context::{Capture, Counter, ErrorContext};
+use explain::{self, Explanation};
+use self::skip::{SkipRule, Skipper};
 use super::{Result, PositionalResult, FieldResult, Record};
 use record::{Data, BuildableDataRanges};
 use data_type::{FieldReadSupport, RecordReadSupport, ShouldReadMore};
 use reader::parser::{FieldParser, IdentityParser};
-use self::spec::Stream as SpecSource;
-use self::field_buffer::Source as FieldBufferSource;
+use self::spec::{Stream as SpecSource, ResolverSource};
+use self::field_buffer::{Source as FieldBufferSource, IntoBuffer};
+use framing::{self, Framing, DESCRIPTOR_LENGTH};
 
 pub struct FieldReader<'a, T: FieldParser<U> + 'a, U: FieldReadSupport> {
     parser: T,
@@ -32,14 +43,50 @@ impl<'a, T: FieldParser<U> + 'a, U: FieldReadSupport> FieldReader<'a, T, U> {
     pub fn read_support(&self) -> &U {
         &self.read_support
     }
+
+    pub fn explain<'b>(&self, line: &'b [u8], record_spec: &'b RecordSpec) -> Explanation {
+        explain::explain(line, record_spec, &self.parser, &self.read_support)
+    }
 }
 
 impl <'a, T: FieldParser<U> + 'a, U: FieldReadSupport> FieldReader<'a, T, U> {
     pub fn read<'b, V>(&self, reader: &'b mut V, field_spec: &'b FieldSpec, field_buffer: &'b mut Vec<u8>, buffer: &'b mut Vec<u8>) -> Result<()>
-        where V: Read + 'b
+        where V: BufRead + 'b
+    {
+        self.read_raw(reader, field_spec, buffer)?;
+        self.parse(&buffer[..], field_spec, field_buffer)
+    }
+
+    pub fn parse<'b>(&self, data: &'b [u8], field_spec: &'b FieldSpec, destination: &'b mut Vec<u8>) -> Result<()> {
+        self.parser.parse(data, field_spec, destination, &self.read_support).map_err(Error::ParserFailure)
+    }
+
+    pub fn read_raw<'b, V>(&self, reader: &'b mut V, field_spec: &'b FieldSpec, buffer: &'b mut Vec<u8>) -> Result<()>
+        where V: BufRead + 'b
     {
         buffer.clear();
-        while let ShouldReadMore::More(amount) = self.read_support.should_read_more(field_spec.length, &buffer[..]) {
+        let length = match field_spec.length_prefix {
+            None => field_spec.length,
+            Some(digits) => {
+                if reader.by_ref().take(digits as u64).read_to_end(buffer)? != digits {
+                    return Err(Error::CouldNotReadEnough(buffer.clone()));
+                }
+
+                let length = ::std::str::from_utf8(&buffer[..]).ok()
+                    .and_then(|prefix| prefix.parse::<usize>().ok())
+                    .ok_or_else(|| Error::InvalidLengthPrefix(buffer.clone()))?
+                ;
+
+                if length > field_spec.length {
+                    return Err(Error::InvalidLengthPrefix(buffer.clone()));
+                }
+
+                buffer.clear();
+                length
+            }
+        };
+
+        while let ShouldReadMore::More(amount) = self.read_support.should_read_more(length, &buffer[..]) {
             let amount_read = reader.by_ref().take(amount as u64).read_to_end(buffer)?;
 
             if amount_read != amount {
@@ -47,7 +94,24 @@ impl <'a, T: FieldParser<U> + 'a, U: FieldReadSupport> FieldReader<'a, T, U> {
             }
         }
 
-        self.parser.parse(&buffer[..], field_spec, field_buffer, &self.read_support).map_err(Error::ParserFailure)?;
+        if self.read_support.get_length(&buffer[..]).length > length {
+            return Err(Error::CharacterCrossesFieldBoundary(buffer.clone()));
+        }
+
+        loop {
+            let continuation_length = {
+                let data = reader.fill_buf()?;
+                let continuation_length = self.read_support.get_continuation_length(data);
+                buffer.extend_from_slice(&data[..continuation_length]);
+                continuation_length
+            };
+
+            if continuation_length == 0 {
+                break;
+            }
+
+            reader.consume(continuation_length);
+        }
 
         Ok(())
     }
@@ -68,39 +132,302 @@ impl<'a, T: FieldParser<U> + 'a, U: RecordReadSupport> RecordReader<'a, T, U> {
         self.field_reader.read_support()
     }
 
+    pub fn explain<'b>(&self, line: &'b [u8], record_spec: &'b RecordSpec) -> Explanation {
+        self.field_reader.explain(line, record_spec)
+    }
+
     pub fn into_inner(self) -> FieldReader<'a, T, U> {
         self.field_reader
     }
 }
 
 impl <'a, T: FieldParser<U> + 'a, U: RecordReadSupport> RecordReader<'a, T, U> {
-    pub fn read<'b, V, X>(&self, reader: &'b mut V, spec: &'b RecordSpec, mut field_buffer: Vec<u8>, buffer: &'b mut Vec<u8>) -> FieldResult<Data<X, U::DataHolder>>
-        where V: Read + 'b,
+    pub fn read<'b, V, X>(&self, reader: &'b mut V, spec: &'b RecordSpec, field_buffer: Vec<u8>, buffer: &'b mut Vec<u8>) -> FieldResult<Data<X, U::DataHolder>>
+        where V: BufRead + 'b,
+              X: BuildableDataRanges + 'b
+    {
+        self.read_owned(reader, spec, None, field_buffer, buffer, &mut None)
+    }
+
+    pub fn read_projected<'b, V, X>(&self, reader: &'b mut V, spec: &'b RecordSpec, projection: &'b BTreeSet<String>, field_buffer: Vec<u8>, buffer: &'b mut Vec<u8>) -> FieldResult<Data<X, U::DataHolder>>
+        where V: BufRead + 'b,
+              X: BuildableDataRanges + 'b
+    {
+        self.read_owned(reader, spec, Some(projection), field_buffer, buffer, &mut None)
+    }
+
+    pub fn read_collecting<'b, V, X>(&self, reader: &'b mut V, spec: &'b RecordSpec, field_buffer: Vec<u8>, buffer: &'b mut Vec<u8>) -> FieldResult<(Data<X, U::DataHolder>, Vec<FieldError>)>
+        where V: BufRead + 'b,
+              X: BuildableDataRanges + 'b
+    {
+        let mut errors = Vec::new();
+        let data = self.read_owned(reader, spec, None, field_buffer, buffer, &mut Some(&mut errors))?;
+        Ok((data, errors))
+    }
+
+    fn read_owned<'b, V, X>(&self, reader: &'b mut V, spec: &'b RecordSpec, projection: Option<&'b BTreeSet<String>>, mut field_buffer: Vec<u8>, buffer: &'b mut Vec<u8>, errors: &'b mut Option<&mut Vec<FieldError>>) -> FieldResult<Data<X, U::DataHolder>>
+        where V: BufRead + 'b,
               X: BuildableDataRanges + 'b
     {
         let mut ranges = X::new();
-        for (name, field_spec) in &spec.field_specs {
-            let old_length = field_buffer.len();
-            self.field_reader.read(reader, field_spec, &mut field_buffer, buffer).map_err(|e| (e, name))?;
+        let nulls = self.read_collecting_into(reader, spec, projection, &mut ranges, &mut field_buffer, buffer, errors, &mut None)?;
+
+        Ok(Data { ranges: ranges, data: self.field_reader.read_support().upcast_data(field_buffer).map_err(Error::DataHolderError)?, nulls: nulls })
+    }
+
+    // when given, read_fields gets the byte range of the record each field was read from
+    fn read_collecting_into<'b, V, X>(&self, reader: &'b mut V, spec: &'b RecordSpec, projection: Option<&'b BTreeSet<String>>, ranges: &'b mut X, field_buffer: &'b mut Vec<u8>, buffer: &'b mut Vec<u8>, errors: &'b mut Option<&mut Vec<FieldError>>, read_fields: &'b mut Option<&mut Vec<(String, Range<usize>)>>) -> FieldResult<BTreeSet<String>>
+        where V: BufRead + 'b,
+              X: BuildableDataRanges + 'b
+    {
+        let reader = &mut Counter::new(reader);
+        let mut nulls = BTreeSet::new();
+        let mut discriminators = BTreeMap::new();
+
+        for (field_specs, line_ending) in spec.lines() {
+            let mut first = true;
+
+            for (name, field_spec) in field_specs {
+                if spec.bitmap.as_ref().map_or(false, |bitmap| bitmap.controls(name)) {
+                    continue;
+                }
+
+                if !first && spec.separator.len() != 0 {
+                    buffer.clear();
+
+                    let start = reader.count();
+
+                    if reader.by_ref().take(spec.separator.len() as u64).read_to_end(buffer)? != spec.separator.len()
+                        || &buffer[..] != &spec.separator[..] {
+                        // point at the separator that was read in place of the field
+                        if let Some(ref mut read_fields) = *read_fields {
+                            read_fields.push((name.to_string(), start..reader.count()));
+                        }
+
+                        return Err((Error::DataDoesNotMatchSeparator(spec.separator.clone(), buffer[..].to_owned()), name))?;
+                    }
+                }
+
+                first = false;
+
+                if let Some(ref bitmap) = spec.bitmap {
+                    if bitmap.field == *name {
+                        let projected = is_projected(projection, name);
+
+                        for bit in track(reader, name, read_fields, |reader| self.read_bitmap(reader, name, field_spec, bitmap, projected, field_buffer, buffer, ranges))? {
+                            let name = bitmap.fields.get(&bit).ok_or_else(|| (Error::BitmapFieldNotDefined(bit), name))?;
+                            let field_spec = spec.field_spec(name).expect("bitmap fields are checked to exist when the spec is built");
+                            FieldError::collect(track(reader, name, read_fields, |reader| self.read_projected_field(reader, spec, projection, name, field_spec, field_buffer, buffer, ranges, &mut discriminators, &mut nulls)), errors)?;
+                        }
+
+                        continue;
+                    }
+                }
+
+                match spec.redefines.get(name) {
+                    None => FieldError::collect(track(reader, name, read_fields, |reader| self.read_projected_field(reader, spec, projection, name, field_spec, field_buffer, buffer, ranges, &mut discriminators, &mut nulls)), errors)?,
+                    Some(redefine) => {
+                        let alternative = {
+                            let value = ranges.get(&redefine.discriminator)
+                                .or_else(|| discriminators.get(&redefine.discriminator).cloned())
+                                .map(|range| &field_buffer[range])
+                                .unwrap_or(&[])
+                            ;
+                            redefine.alternative(value).ok_or_else(|| (Error::NoAlternativeForDiscriminator(value.to_owned()), name))?
+                        };
+
+                        for (name, field_spec) in alternative {
+                            FieldError::collect(track(reader, name, read_fields, |reader| self.read_projected_field(reader, spec, projection, name, field_spec, field_buffer, buffer, ranges, &mut discriminators, &mut nulls)), errors)?;
+                        }
+                    }
+                }
+            }
+
+            buffer.clear();
+
+            if reader.by_ref().take(line_ending.len() as u64).read_to_end(buffer)? != 0
+                && &buffer[..] != line_ending {
+                return Err(Error::DataDoesNotMatchLineEnding(
+                    line_ending.to_owned(),
+                    buffer[..].to_owned()
+                ))?;
+            }
+        }
+
+        let data = Data { ranges: &*ranges, data: &field_buffer[..], nulls: nulls };
+        let record_result = match projection {
+            None => validation::check_record(&spec.rules, &data),
+            Some(_) => validation::check_record(&spec.rules.iter()
+                .filter(|rule| rule.fields().iter().all(|field| is_projected(projection, field)))
+                .cloned()
+                .collect::<Vec<_>>(), &data)
+        };
+        FieldError::collect(record_result.map_err(FieldError::from), errors)?;
+
+        Ok(data.nulls)
+    }
+
+    pub fn skip<'b, V>(&self, reader: &'b mut V, spec: &'b RecordSpec, buffer: &'b mut Vec<u8>) -> FieldResult<()>
+        where V: BufRead + 'b
+    {
+        if spec.lines().any(|(_, line_ending)| line_ending.is_empty()) {
+            self.read_collecting_into::<_, HashMap<String, Range<usize>>>(reader, spec, Some(&BTreeSet::new()), &mut HashMap::new(), &mut Vec::new(), buffer, &mut None, &mut None)?;
+            return Ok(());
+        }
+
+        for (field_specs, line_ending) in spec.lines() {
+            buffer.clear();
+
+            while !buffer.ends_with(line_ending) {
+                if reader.read_until(line_ending[line_ending.len() - 1], buffer)? == 0 {
+                    return Ok(());
+                }
+            }
+
+            // when the layout fixes the line's length, hold the skipped line to it like a read would
+            if let Some(length) = line_length(spec, field_specs) {
+                let line = &buffer[..buffer.len() - line_ending.len()];
+                let mut end = 0;
 
-            ranges.insert(name, self.field_reader.read_support().get_range(
-                old_length,
-                &field_buffer[..]
-            ));
+                while let ShouldReadMore::More(amount) = self.field_reader.read_support().should_read_more(length, &line[..end]) {
+                    if end + amount > line.len() {
+                        return Err(Error::CouldNotReadEnough(line.to_owned()))?;
+                    }
+
+                    end += amount;
+                }
+
+                if end != line.len() {
+                    return Err(Error::DataDoesNotMatchLineEnding(
+                        line_ending.to_owned(),
+                        line[end..].iter().chain(line_ending).take(line_ending.len()).cloned().collect()
+                    ))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_projected_field<'b, V, X>(&self, reader: &'b mut V, spec: &'b RecordSpec, projection: Option<&'b BTreeSet<String>>, name: &'b str, field_spec: &'b FieldSpec, field_buffer: &'b mut Vec<u8>, buffer: &'b mut Vec<u8>, ranges: &'b mut X, discriminators: &'b mut BTreeMap<String, Range<usize>>, nulls: &'b mut BTreeSet<String>) -> FieldResult<()>
+        where V: BufRead + 'b,
+              X: BuildableDataRanges + 'b
+    {
+        match projection {
+            // discriminators are always read to pick an alternative, but only returned when projected
+            Some(projection) if !projection.contains(name) && spec.redefines.values().any(|redefine| redefine.discriminator == name) =>
+                self.read_field(reader, name, field_spec, true, field_buffer, buffer, discriminators, &mut BTreeSet::new()),
+            _ => self.read_field(reader, name, field_spec, is_projected(projection, name), field_buffer, buffer, ranges, nulls)
+        }
+    }
+
+    fn read_field<'b, V, X>(&self, reader: &'b mut V, name: &'b str, field_spec: &'b FieldSpec, projected: bool, field_buffer: &'b mut Vec<u8>, buffer: &'b mut Vec<u8>, ranges: &'b mut X, nulls: &'b mut BTreeSet<String>) -> FieldResult<()>
+        where V: BufRead + 'b,
+              X: BuildableDataRanges + 'b
+    {
+        if !projected {
+            return Ok(self.field_reader.read_raw(reader, field_spec, buffer).map_err(|e| (e, name))?);
+        }
+
+        let old_length = field_buffer.len();
+
+        if let Err(e) = self.field_reader.read(reader, field_spec, field_buffer, buffer) {
+            field_buffer.truncate(old_length);
+            return Err((e, name).into());
+        }
+
+        if field_spec.null.as_ref().map_or(false, |null| null.matches(&buffer[..], field_spec)) {
+            field_buffer.truncate(old_length);
+            nulls.insert(name.to_string());
+            return Ok(validation::validate(&field_spec.validations, None).map_err(|e| (e, name))?);
+        }
+
+        ranges.insert(name, self.field_reader.read_support().get_range(
+            old_length,
+            &field_buffer[..]
+        ));
+
+        validation::validate(&field_spec.validations, Some(&field_buffer[old_length..])).map_err(|e| (e, name))?;
+
+        if field_spec.constant {
+            let constant = field_spec.default.as_ref().expect("constant fields always have a default");
+            buffer.clear();
+            self.field_reader.parse(&constant[..], field_spec, buffer).map_err(|e| (e, name))?;
+
+            if field_buffer[old_length..] != buffer[..] {
+                return Err((Error::ConstantMismatch(constant.clone(), field_buffer[old_length..].to_vec()), name).into());
+            }
         }
 
+        Ok(())
+    }
+
+    fn read_bitmap<'b, V, X>(&self, reader: &'b mut V, name: &'b str, field_spec: &'b FieldSpec, bitmap: &'b BitmapSpec, projected: bool, field_buffer: &'b mut Vec<u8>, buffer: &'b mut Vec<u8>, ranges: &'b mut X) -> FieldResult<Vec<usize>>
+        where V: BufRead + 'b,
+              X: BuildableDataRanges + 'b
+    {
         buffer.clear();
 
-        if reader.by_ref().take(spec.line_ending.len() as u64).read_to_end(buffer)? != 0
-            && &buffer[..] != &spec.line_ending[..] {
-            return Err(Error::DataDoesNotMatchLineEnding(
-                spec.line_ending.clone(),
-                buffer[..].to_owned()
-            ))?;
+        if reader.by_ref().take(field_spec.length as u64).read_to_end(buffer)? != field_spec.length {
+            return Err((Error::CouldNotReadEnough(buffer.clone()), name))?;
+        }
+
+        if bitmap.has_secondary(&buffer[..]).ok_or_else(|| (Error::InvalidBitmap(buffer.clone()), name))? {
+            if reader.by_ref().take(field_spec.length as u64).read_to_end(buffer)? != field_spec.length {
+                return Err((Error::CouldNotReadEnough(buffer.clone()), name))?;
+            }
+        }
+
+        let bits = bitmap.decode(&buffer[..]).ok_or_else(|| (Error::InvalidBitmap(buffer.clone()), name))?;
+
+        if !projected {
+            return Ok(bits.into_iter().filter(|bit| *bit != 1).collect());
         }
 
-        Ok(Data { ranges: ranges, data: self.field_reader.read_support().upcast_data(field_buffer).map_err(Error::DataHolderError)? })
+        let old_length = field_buffer.len();
+        field_buffer.extend_from_slice(&buffer[..]);
+        ranges.insert(name, self.field_reader.read_support().get_range(
+            old_length,
+            &field_buffer[..]
+        ));
+
+        Ok(bits.into_iter().filter(|bit| *bit != 1).collect())
+    }
+}
+
+fn track<'a, 'b, R, T, F>(reader: &'b mut Counter<'a, R>, name: &'b str, read_fields: &'b mut Option<&mut Vec<(String, Range<usize>)>>, read: F) -> T
+    where R: BufRead + 'a,
+          F: FnOnce(&mut Counter<'a, R>) -> T
+{
+    let start = reader.count();
+    let result = read(reader);
+
+    if let Some(ref mut read_fields) = *read_fields {
+        read_fields.push((name.to_string(), start..reader.count()));
+    }
+
+    result
+}
+
+fn drain<'a, R: Read + 'a>(source: &'a mut R) -> ::std::io::Result<u64> {
+    ::std::io::copy(source, &mut ::std::io::sink())
+}
+
+fn line_length<'a>(spec: &'a RecordSpec, field_specs: Fields<'a>) -> Option<usize> {
+    if spec.bitmap.is_some() || spec.redefines.values()
+        .flat_map(|redefine| redefine.alternatives.values())
+        .flat_map(|alternative| alternative.values())
+        .chain(field_specs.into_iter().map(|(_, field_spec)| field_spec))
+        .any(|field_spec| field_spec.length_prefix.is_some()) {
+        return None;
     }
+
+    let count = field_specs.into_iter().count();
+    Some(field_specs.into_iter().map(|(_, field_spec)| field_spec.length).sum::<usize>() + count.saturating_sub(1) * spec.separator.len())
+}
+
+fn is_projected<'a>(projection: Option<&'a BTreeSet<String>>, name: &'a str) -> bool {
+    projection.map_or(true, |projection| projection.contains(name))
 }
 
 pub struct Reader<
@@ -120,6 +447,19 @@ pub struct Reader<
     record_specs: W,
     buffer: Y,
     field_buffer_source: Z,
+    framing: Framing,
+    block_remaining: usize,
+    header: Option<Header>,
+    header_spec: Option<RecordSpec>,
+    skipper: Skipper,
+    skip_line_ending: Option<Vec<u8>>,
+    file_validator: FileValidator,
+    tables: HashMap<String, Arc<FieldTable>>,
+    projection: HashMap<String, BTreeSet<String>>,
+    record_filter: Option<BTreeSet<String>>,
+    skipped: BTreeMap<String, usize>,
+    last_record: Option<Vec<u8>>,
+    last_fields: Vec<(String, Range<usize>)>,
     source_type: ::std::marker::PhantomData<&'a R>
 }
 
@@ -132,21 +472,274 @@ impl<'a, R, T, U, V, W, X, Y, Z> Reader<'a, R, T, U, V, W, X, Y, Z>
           X: BorrowMut<R> + 'a,
           Y: BorrowMut<Vec<u8>> + 'a,
           Z: FieldBufferSource + 'a {
-    pub fn read_record<'b, A: BuildableDataRanges + 'b>(&mut self) -> PositionalResult<Record<A, V::DataHolder>> {
-        let spec_name = self.spec_source.next(self.source.borrow_mut(), self.record_specs.borrow(), self.reader.read_support())
-            .map_err(Error::SpecStreamError)?
-            .ok_or(Error::SpecStreamReturnedNone)?
-        ;
-        self.reader
-            .read(
-                self.source.borrow_mut(),
-                self.record_specs.borrow().get(spec_name).ok_or_else(|| Error::RecordSpecNotFound(spec_name.to_string()))?,
+    pub fn read_record<'b, A: BuildableDataRanges + 'b>(&mut self) -> PositionalResult<Record<A, V::DataHolder>>
+        where V::DataHolder: AsRef<[u8]>
+    {
+        self.read_record_collecting_into(&mut None, &mut None)
+    }
+
+    pub fn read_record_into<'b, A: BuildableDataRanges + 'b>(&mut self, record: &'b mut Record<A, V::DataHolder>) -> PositionalResult<()>
+        where V::DataHolder: AsRef<[u8]> + IntoBuffer + Default
+    {
+        let empty = Record { data: Data { ranges: A::new(), data: V::DataHolder::default(), nulls: BTreeSet::new() }, name: String::new() };
+        let recycled = ::std::mem::replace(record, empty);
+        let mut recycled = Some(Record {
+            data: Data { ranges: recycled.data.ranges, data: recycled.data.data.into_buffer(), nulls: recycled.data.nulls },
+            name: recycled.name
+        });
+
+        match self.read_record_collecting_into(&mut None, &mut recycled) {
+            Ok(read) => {
+                *record = read;
+                Ok(())
+            },
+            Err(e) => {
+                // hand the buffers back so that their capacity survives the error
+                if let Some(recycled) = recycled {
+                    if let Ok(data) = self.reader.read_support().upcast_data(recycled.data.data) {
+                        *record = Record { data: Data { ranges: recycled.data.ranges, data: data, nulls: recycled.data.nulls }, name: recycled.name };
+                    }
+                }
+
+                Err(e)
+            }
+        }
+    }
+
+    pub fn read_record_collecting<'b, A: BuildableDataRanges + 'b>(&mut self) -> PositionalResult<(Record<A, V::DataHolder>, Vec<PositionalError>)>
+        where V::DataHolder: AsRef<[u8]>
+    {
+        let mut errors = Vec::new();
+        let record = self.read_record_collecting_into(&mut Some(&mut errors), &mut None)?;
+        Ok((record, errors))
+    }
+
+    fn read_record_collecting_into<'b, A: BuildableDataRanges + 'b>(&mut self, errors: &'b mut Option<&mut Vec<PositionalError>>, recycled: &'b mut Option<Record<A, Vec<u8>>>) -> PositionalResult<Record<A, V::DataHolder>>
+        where V::DataHolder: AsRef<[u8]>
+    {
+        let (line, framed, spec_name, record_spec) = loop {
+            self.skipper.skip(self.source.borrow_mut())?;
+
+            if let Some(ref header) = self.header {
+                if self.header_spec.is_none() {
+                    let line = self.skipper.line + 1;
+                    self.header_spec = Some(header::read_header(self.source.borrow_mut(), header)
+                        .map_err(|e| PositionalError::from((FieldError::from(e), &header.name[..])).with_line(line))?
+                    );
+                    self.skipper.advance(if header.underline { 2 } else { 1 });
+                    self.skipper.skip(self.source.borrow_mut())?;
+                }
+            }
+
+            let line = self.skipper.line + 1;
+            let record_length = self.read_descriptors().map_err(|e| PositionalError::from(e).with_line(line))?;
+            let mut framed = record_length.map(|record_length| (record_length, record_length));
+            let resolved = match self.header {
+                Some(ref header) => Ok((&header.name[..], self.header_spec.as_ref().expect("the header spec was read above"))),
+                None => {
+                    let spec_name = match framed {
+                        None => self.spec_source.next(self.source.borrow_mut(), self.record_specs.borrow(), self.reader.read_support()),
+                        Some((record_length, _)) => {
+                            let mut source = self.source.borrow_mut().take(record_length as u64);
+                            let spec_name = self.spec_source.next(&mut source, self.record_specs.borrow(), self.reader.read_support());
+                            framed = Some((record_length, source.limit() as usize));
+                            spec_name
+                        }
+                    };
+                    let record_specs = self.record_specs.borrow();
+
+                    spec_name.map_err(Error::SpecStreamError)
+                        .and_then(|spec_name| spec_name.ok_or(Error::SpecStreamReturnedNone))
+                        .and_then(|spec_name| record_specs.get(spec_name)
+                            .map(|record_spec| (spec_name, record_spec))
+                            .ok_or_else(|| Error::RecordSpecNotFound(spec_name.to_string()))
+                        )
+                }
+            };
+            let at_end = framed.is_none() && self.source.borrow_mut().fill_buf()
+                .map_err(|e| PositionalError::from(Error::from(e)).with_line(line))?
+                .is_empty()
+            ;
+            let (spec_name, record_spec) = match resolved {
+                Ok(_) | Err(Error::SpecStreamReturnedNone) if at_end => return Err(PositionalError::from(Error::EndOfFile).with_line(line)),
+                Ok(resolved) => resolved,
+                Err(e) => {
+                    if let Some((_, remaining)) = framed {
+                        drain(&mut self.source.borrow_mut().take(remaining as u64)).map_err(|e| PositionalError::from(Error::from(e)).with_line(line))?;
+                    }
+
+                    return Err(PositionalError::from(e).with_line(line));
+                }
+            };
+
+            if self.header.is_some() || self.record_filter.as_ref().map_or(true, |record_filter| record_filter.contains(spec_name)) {
+                break (line, framed, spec_name, record_spec);
+            }
+
+            let skipped = match framed {
+                None => self.reader.skip(self.source.borrow_mut(), record_spec, self.buffer.borrow_mut()),
+                Some((record_length, remaining)) => {
+                    let mut source = self.source.borrow_mut().take(remaining as u64);
+
+                    match drain(&mut source) {
+                        Ok(_) if source.limit() != 0 => Err(
+                            Error::DescriptorLengthMismatch(record_length, record_length - source.limit() as usize).into()
+                        ),
+                        Ok(_) => Ok(()),
+                        Err(e) => Err(e.into())
+                    }
+                }
+            };
+
+            self.skipper.advance(record_spec.lines().filter(|&(_, line_ending)| !line_ending.is_empty()).count());
+            skipped.map_err(|e| PositionalError::from((e, spec_name)).with_line(line))?;
+
+            if let Some(count) = self.skipped.get_mut(spec_name) {
+                *count += 1;
+                continue;
+            }
+
+            self.skipped.insert(spec_name.to_string(), 1);
+        };
+        let table = match self.header {
+            Some(_) => None,
+            None => self.tables.get(spec_name)
+        };
+        let (mut ranges, mut field_buffer, mut name) = match recycled.take() {
+            Some(Record { data: Data { mut ranges, data: mut field_buffer, .. }, mut name }) => {
+                ranges.reset(table);
+                field_buffer.clear();
+                name.clear();
+                (ranges, field_buffer, name)
+            },
+            None => (
+                table.map_or_else(A::new, A::new_with_table),
                 self.field_buffer_source.get().unwrap_or_else(|| Vec::new()),
-                self.buffer.borrow_mut()
+                String::new()
             )
-            .map(|data| Record { data: data, name: spec_name.to_string() })
-            .map_err(|e| (e, spec_name).into())
+        };
+        let mut field_errors = Vec::new();
+        let mut collected = errors.as_ref().map(|_| &mut field_errors);
+
+        let last_record = self.last_record.as_mut().map(|last_record| {
+            last_record.clear();
+            last_record
+        });
+        self.last_fields.clear();
+        let mut last_fields = if last_record.is_some() { Some(&mut self.last_fields) } else { None };
+
+        let data = match framed {
+            None => {
+                let mut source = Capture::new(self.source.borrow_mut(), last_record);
+                self.reader.read_collecting_into(&mut source, record_spec, self.projection.get(spec_name), &mut ranges, &mut field_buffer, self.buffer.borrow_mut(), &mut collected, &mut last_fields)
+                    .map_err(|e| match e.error {
+                        Error::CouldNotReadEnough(data) => FieldError { error: Error::UnexpectedEndOfFile(data), field: e.field },
+                        error => FieldError { error: error, field: e.field }
+                    })
+            },
+            Some((record_length, remaining)) => {
+                let mut source = self.source.borrow_mut().take(remaining as u64);
+                let data = self.reader.read_collecting_into(&mut Capture::new(&mut source, last_record), record_spec, self.projection.get(spec_name), &mut ranges, &mut field_buffer, self.buffer.borrow_mut(), &mut collected, &mut last_fields);
+                let unread = source.limit() as usize;
+                let drained = drain(&mut source).map_err(|e| PositionalError::from(Error::from(e)).with_line(line));
+
+                match data {
+                    Ok(_) if unread != 0 => {
+                        drained?;
+                        Err(Error::DescriptorLengthMismatch(record_length, record_length - unread).into())
+                    },
+                    data => {
+                        drained?;
+                        data
+                    }
+                }
+            }
+        };
+
+        self.skipper.advance(record_spec.lines().filter(|&(_, line_ending)| !line_ending.is_empty()).count());
+
+        let file_validator = &mut self.file_validator;
+        let checked = data.map_err(|e| PositionalError::from((e, spec_name)).with_line(line)).and_then(|nulls| {
+            let data = Data { ranges: &ranges, data: &field_buffer[..], nulls: nulls };
+            if let Err(file_errors) = file_validator.check(spec_name, &data, line) {
+                for e in file_errors {
+                    FieldError::collect(Err::<(), _>(FieldError::from(e)), &mut collected).map_err(|e| PositionalError::from((e, spec_name)).with_line(line))?;
+                }
+            }
+            Ok(data.nulls)
+        });
+        let nulls = match checked {
+            Ok(nulls) => nulls,
+            Err(e) => {
+                ranges.reset(table);
+                field_buffer.clear();
+                *recycled = Some(Record { data: Data { ranges: ranges, data: field_buffer, nulls: BTreeSet::new() }, name: name });
+                return Err(e);
+            }
+        };
+        let data = Data {
+            ranges: ranges,
+            data: self.reader.read_support().upcast_data(field_buffer).map_err(|e| PositionalError::from((FieldError::from(Error::DataHolderError(e)), spec_name)).with_line(line))?,
+            nulls: nulls
+        };
+
+        if let Some(ref mut errors) = *errors {
+            errors.extend(field_errors.into_iter().map(|e| PositionalError::from((e, spec_name)).with_line(line)));
+        }
+
+        name.push_str(spec_name);
+
+        Ok(Record { data: data, name: name })
+    }
+
+    pub fn line(&self) -> usize {
+        self.skipper.line
+    }
+
+    pub fn skipped_records(&self) -> &BTreeMap<String, usize> {
+        &self.skipped
+    }
+
+    pub fn last_record(&self) -> Option<&[u8]> {
+        self.last_record.as_ref().map(|last_record| &last_record[..])
+    }
+
+    pub fn explain<'b>(&self, line: &'b [u8], name: &'b str) -> Option<Explanation> {
+        self.record_specs.borrow().get(name).map(|record_spec| self.reader.explain(line, record_spec))
+    }
+
+    pub fn error_context<'b>(&'b self, error: &'b PositionalError) -> Option<ErrorContext<'b>> {
+        let name = &error.position.as_ref()?.record;
+        let record_spec = match self.header {
+            Some(ref header) if header.name == *name => self.header_spec.as_ref(),
+            _ => self.record_specs.borrow().get(name)
+        }?;
+
+        Some(ErrorContext::new(error, self.last_record()?, record_spec, &self.last_fields[..]))
+    }
+
+    fn read_descriptors(&mut self) -> Result<Option<usize>> {
+        if let Framing::None = self.framing {
+            return Ok(None);
+        }
+
+        if let Framing::Blocked(_) = self.framing {
+            if self.block_remaining == 0 {
+                self.block_remaining = framing::read_block_descriptor(self.source.borrow_mut())?;
+            }
+        }
+
+        let record_length = framing::read_record_descriptor(self.source.borrow_mut())?;
+
+        if let Framing::Blocked(_) = self.framing {
+            if record_length + DESCRIPTOR_LENGTH > self.block_remaining {
+                return Err(Error::DescriptorLengthMismatch(self.block_remaining, record_length + DESCRIPTOR_LENGTH));
+            }
 
+            self.block_remaining -= record_length + DESCRIPTOR_LENGTH;
+        }
+
+        Ok(Some(record_length))
     }
 
     pub fn into_inner(self) -> RecordReader<'a, T, V> {
@@ -172,6 +765,15 @@ pub struct ReaderBuilder<
     record_specs: Option<W>,
     buffer: Y,
     field_buffer_source: Z,
+    framing: Framing,
+    header: Option<Header>,
+    skip_rules: Vec<SkipRule>,
+    skip_line_ending: Option<Vec<u8>>,
+    file_rules: Vec<FileRule>,
+    projection: HashMap<String, BTreeSet<String>>,
+    record_filter: Option<BTreeSet<String>>,
+    tables: Option<HashMap<String, Arc<FieldTable>>>,
+    error_context: bool,
     source_type: ::std::marker::PhantomData<&'a R>
 }
 
@@ -187,6 +789,48 @@ impl<'a, V> ReaderBuilder<'a, &'a [u8], IdentityParser, (), V, HashMap<String, R
             record_specs: None,
             buffer: Vec::new(),
             field_buffer_source: None,
+            framing: Framing::None,
+            header: None,
+            skip_rules: Vec::new(),
+            skip_line_ending: None,
+            file_rules: Vec::new(),
+            projection: HashMap::new(),
+            record_filter: None,
+            tables: None,
+            error_context: false,
+            source_type: ::std::marker::PhantomData
+        }
+    }
+}
+
+impl<'a, R, T, V, W, X, Y, Z> ReaderBuilder<'a, R, T, (), V, W, X, Y, Z>
+    where R: BufRead + 'a,
+          T: FieldParser<V> + 'a,
+          V: RecordReadSupport,
+          W: Borrow<HashMap<String, RecordSpec>> + 'a,
+          X: BorrowMut<R> + 'a,
+          Y: BorrowMut<Vec<u8>> + 'a,
+          Z: FieldBufferSource + 'a {
+    // records are resolved through the compiled id index; without one the spec source is left
+    // unset for with_spec_source to fill in
+    pub fn with_compiled_spec(self, compiled: &'a CompiledSpec) -> ReaderBuilder<'a, R, T, ResolverSource<'a, &'a IdIndex, V>, V, &'a CompiledSpec, X, Y, Z> {
+        ReaderBuilder {
+            read_support: self.read_support,
+            source: self.source,
+            field_parser: self.field_parser,
+            spec_source: compiled.id_index().map(ResolverSource::new),
+            record_specs: Some(compiled),
+            buffer: self.buffer,
+            field_buffer_source: self.field_buffer_source,
+            framing: self.framing,
+            header: self.header,
+            skip_rules: self.skip_rules,
+            skip_line_ending: self.skip_line_ending,
+            file_rules: self.file_rules.into_iter().chain(compiled.rules.iter().cloned()).collect(),
+            projection: self.projection,
+            record_filter: self.record_filter,
+            tables: Some(compiled.tables().clone()),
+            error_context: self.error_context,
             source_type: ::std::marker::PhantomData
         }
     }
@@ -210,6 +854,15 @@ impl<'a, R, T, U, V, W, X, Y, Z> ReaderBuilder<'a, R, T, U, V, W, X, Y, Z>
             record_specs: self.record_specs,
             buffer: self.buffer,
             field_buffer_source: self.field_buffer_source,
+            framing: self.framing,
+            header: self.header,
+            skip_rules: self.skip_rules,
+            skip_line_ending: self.skip_line_ending,
+            file_rules: self.file_rules,
+            projection: self.projection,
+            record_filter: self.record_filter,
+            tables: self.tables,
+            error_context: self.error_context,
             source_type: ::std::marker::PhantomData
         }
     }
@@ -223,6 +876,15 @@ impl<'a, R, T, U, V, W, X, Y, Z> ReaderBuilder<'a, R, T, U, V, W, X, Y, Z>
             record_specs: self.record_specs,
             buffer: self.buffer,
             field_buffer_source: self.field_buffer_source,
+            framing: self.framing,
+            header: self.header,
+            skip_rules: self.skip_rules,
+            skip_line_ending: self.skip_line_ending,
+            file_rules: self.file_rules,
+            projection: self.projection,
+            record_filter: self.record_filter,
+            tables: self.tables,
+            error_context: self.error_context,
             source_type: ::std::marker::PhantomData
         }
     }
@@ -236,6 +898,15 @@ impl<'a, R, T, U, V, W, X, Y, Z> ReaderBuilder<'a, R, T, U, V, W, X, Y, Z>
             record_specs: self.record_specs,
             buffer: self.buffer,
             field_buffer_source: self.field_buffer_source,
+            framing: self.framing,
+            header: self.header,
+            skip_rules: self.skip_rules,
+            skip_line_ending: self.skip_line_ending,
+            file_rules: self.file_rules,
+            projection: self.projection,
+            record_filter: self.record_filter,
+            tables: self.tables,
+            error_context: self.error_context,
             source_type: ::std::marker::PhantomData
         }
     }
@@ -249,10 +920,23 @@ impl<'a, R, T, U, V, W, X, Y, Z> ReaderBuilder<'a, R, T, U, V, W, X, Y, Z>
             record_specs: Some(record_specs),
             buffer: self.buffer,
             field_buffer_source: self.field_buffer_source,
+            framing: self.framing,
+            header: self.header,
+            skip_rules: self.skip_rules,
+            skip_line_ending: self.skip_line_ending,
+            file_rules: self.file_rules,
+            projection: self.projection,
+            record_filter: self.record_filter,
+            tables: None,
+            error_context: self.error_context,
             source_type: ::std::marker::PhantomData
         }
     }
 
+    pub fn with_spec(self, spec: &'a Spec) -> ReaderBuilder<'a, R, T, U, V, &'a HashMap<String, RecordSpec>, X, Y, Z> {
+        self.with_file_rules(spec.rules.iter().cloned()).with_record_specs(&spec.record_specs)
+    }
+
     pub fn with_buffer<A: BorrowMut<Vec<u8>> + 'a>(self, buffer: A) -> ReaderBuilder<'a, R, T, U, V, W, X, A, Z> {
         ReaderBuilder {
             read_support: self.read_support,
@@ -262,6 +946,15 @@ impl<'a, R, T, U, V, W, X, Y, Z> ReaderBuilder<'a, R, T, U, V, W, X, Y, Z>
             record_specs: self.record_specs,
             buffer: buffer,
             field_buffer_source: self.field_buffer_source,
+            framing: self.framing,
+            header: self.header,
+            skip_rules: self.skip_rules,
+            skip_line_ending: self.skip_line_ending,
+            file_rules: self.file_rules,
+            projection: self.projection,
+            record_filter: self.record_filter,
+            tables: self.tables,
+            error_context: self.error_context,
             source_type: ::std::marker::PhantomData
         }
     }
@@ -275,21 +968,158 @@ impl<'a, R, T, U, V, W, X, Y, Z> ReaderBuilder<'a, R, T, U, V, W, X, Y, Z>
             record_specs: self.record_specs,
             buffer: self.buffer,
             field_buffer_source: field_buffer_source,
+            framing: self.framing,
+            header: self.header,
+            skip_rules: self.skip_rules,
+            skip_line_ending: self.skip_line_ending,
+            file_rules: self.file_rules,
+            projection: self.projection,
+            record_filter: self.record_filter,
+            tables: self.tables,
+            error_context: self.error_context,
+            source_type: ::std::marker::PhantomData
+        }
+    }
+
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    pub fn with_skip_rule(mut self, skip_rule: SkipRule) -> Self {
+        self.skip_rules.push(skip_rule);
+        self
+    }
+
+    // the line ending skip rules split lines on, needed when the record specs disagree on theirs
+    pub fn with_skip_line_ending<A: Into<Vec<u8>>>(mut self, line_ending: A) -> Self {
+        self.skip_line_ending = Some(line_ending.into());
+        self
+    }
+
+    pub fn with_file_rule(mut self, file_rule: FileRule) -> Self {
+        self.file_rules.push(file_rule);
+        self
+    }
+
+    pub fn with_file_rules<I: IntoIterator<Item=FileRule>>(mut self, file_rules: I) -> Self {
+        self.file_rules.extend(file_rules);
+        self
+    }
+
+    pub fn with_projection<A, B, C>(mut self, record: A, fields: B) -> Self
+        where A: Into<String>,
+              B: IntoIterator<Item=C>,
+              C: Into<String>
+    {
+        self.projection.entry(record.into()).or_insert_with(BTreeSet::new).extend(fields.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_record_filter<A, B>(mut self, records: A) -> Self
+        where A: IntoIterator<Item=B>,
+              B: Into<String>
+    {
+        self.record_filter.get_or_insert_with(BTreeSet::new).extend(records.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_error_context(mut self) -> Self {
+        self.error_context = true;
+        self
+    }
+
+    pub fn with_header(self, header: Header) -> ReaderBuilder<'a, R, T, (), V, HashMap<String, RecordSpec>, X, Y, Z> {
+        ReaderBuilder {
+            read_support: self.read_support,
+            source: self.source,
+            field_parser: self.field_parser,
+            spec_source: Some(()),
+            record_specs: Some(HashMap::new()),
+            buffer: self.buffer,
+            field_buffer_source: self.field_buffer_source,
+            framing: self.framing,
+            header: Some(header),
+            skip_rules: self.skip_rules,
+            skip_line_ending: self.skip_line_ending,
+            file_rules: self.file_rules,
+            projection: self.projection,
+            record_filter: self.record_filter,
+            tables: None,
+            error_context: self.error_context,
             source_type: ::std::marker::PhantomData
         }
     }
 
     pub fn build(self) -> Result<Reader<'a, R, T, U, V, W, X, Y, Z>> {
+        let record_specs = self.record_specs.ok_or(Error::FieldRequiredToBuild("record_specs"))?;
+        let tables = match self.tables {
+            Some(tables) => tables,
+            None => compiled::compile_tables(record_specs.borrow())
+        };
+        let line_ending = match (&self.skip_line_ending, &self.header) {
+            (&Some(ref line_ending), _) => line_ending.clone(),
+            (&None, &Some(ref header)) => header.line_ending.clone(),
+            (&None, &None) => {
+                let line_endings: BTreeSet<&Vec<u8>> = record_specs.borrow().values()
+                    .map(|record_spec| &record_spec.line_ending)
+                    .filter(|line_ending| !line_ending.is_empty())
+                    .collect();
+                let mut line_endings = line_endings.into_iter();
+
+                match (line_endings.next(), line_endings.next()) {
+                    (Some(first), Some(second)) if !self.skip_rules.is_empty() => {
+                        return Err(Error::SkipLineEndingAmbiguous(first.clone(), second.clone()));
+                    },
+                    (Some(first), _) => first.clone(),
+                    (None, _) => b"\n".to_vec()
+                }
+            }
+        };
+        let spec_source = self.spec_source.ok_or(Error::FieldRequiredToBuild("spec_source"))?;
+        spec_source.check_record_specs(record_specs.borrow()).map_err(Error::SpecStreamError)?;
+        let mut projection = self.projection;
+
+        if let Some(ref record_filter) = self.record_filter {
+            for rule in self.file_rules.iter() {
+                if let FileRule::References { ref record, ref target_record, .. } = *rule {
+                    if record_filter.contains(record) && !record_filter.contains(target_record) {
+                        return Err(Error::FileRuleTargetFiltered(rule.name().to_string(), target_record.clone()));
+                    }
+                }
+            }
+        }
+
+        // file rules need their fields even when a projection leaves them out
+        for (record, field) in self.file_rules.iter().flat_map(FileRule::record_fields) {
+            if let Some(fields) = projection.get_mut(&record) {
+                fields.insert(field);
+            }
+        }
+
         Ok(Reader {
             source: self.source.ok_or(Error::FieldRequiredToBuild("source"))?,
             reader: RecordReader::new(FieldReader::new(
                 self.field_parser.ok_or(Error::FieldRequiredToBuild("field_parser"))?,
                 self.read_support
             )),
-            spec_source: self.spec_source.ok_or(Error::FieldRequiredToBuild("spec_source"))?,
-            record_specs: self.record_specs.ok_or(Error::FieldRequiredToBuild("record_specs"))?,
+            spec_source: spec_source,
+            record_specs: record_specs,
             buffer: self.buffer,
             field_buffer_source: self.field_buffer_source,
+            framing: self.framing,
+            block_remaining: 0,
+            header: self.header,
+            header_spec: None,
+            skipper: Skipper::new(self.skip_rules).with_line_ending(line_ending),
+            skip_line_ending: self.skip_line_ending,
+            file_validator: FileValidator::new(self.file_rules),
+            tables: tables,
+            projection: projection,
+            record_filter: self.record_filter,
+            skipped: BTreeMap::new(),
+            last_record: if self.error_context { Some(Vec::new()) } else { None },
+            last_fields: Vec::new(),
             source_type: ::std::marker::PhantomData
         })
     }
@@ -329,6 +1159,15 @@ impl<'a, R, T, U, V, W, X, Y, Z> From<Reader<'a, R, T, U, V, W, X, Y, Z>> for Re
             record_specs: Some(reader.record_specs),
             buffer: reader.buffer,
             field_buffer_source: reader.field_buffer_source,
+            framing: reader.framing,
+            header: reader.header,
+            skip_rules: reader.skipper.rules,
+            skip_line_ending: reader.skip_line_ending,
+            file_rules: reader.file_validator.rules,
+            projection: reader.projection,
+            record_filter: reader.record_filter,
+            tables: Some(reader.tables),
+            error_context: reader.last_record.is_some(),
             source_type: ::std::marker::PhantomData
         }
     }
@@ -338,12 +1177,19 @@ impl<'a, R, T, U, V, W, X, Y, Z> From<Reader<'a, R, T, U, V, W, X, Y, Z>> for Re
 mod test {
 
     use super::*;
-    use error::{Error, FieldError};
+    use error::{Error, FieldError, PositionalError};
     use test::*;
+    use spec::{SpecBuilder, RecordSpecBuilder, FieldSpecBuilder, GroupSpecBuilder, BitmapEncoding, NullValue, Builder};
+    use reader::skip::SkipRule;
+    use spec::validation::{CharacterClass, FileRule, RecordRule, RecordValues, ValidationRule};
+    use reader::parser::DefaultParser;
+    use spec::stream::StreamError;
+    use data_type::DisplayWidthSupport;
     use std::io::Cursor;
-    use std::collections::{HashMap, BTreeMap};
+    use std::collections::{HashMap, BTreeMap, BTreeSet};
     use std::ops::Range;
     use data_type::BinarySupport;
+    use record::{IterableDataRanges, Node};
 
     #[test]
     fn read_record() {
@@ -365,7 +1211,8 @@ mod test {
                 ranges: [("field1".to_owned(), 0..5),
                     ("field2".to_owned(), 5..11),
                     ("field3".to_owned(), 11..17)]
-                    .iter().cloned().collect::<HashMap<String, Range<usize>>>()
+                    .iter().cloned().collect::<HashMap<String, Range<usize>>>(),
+                nulls: BTreeSet::new()
             }),
             reader.read(&mut buf, record_spec, Vec::new(), &mut Vec::new())
         );
@@ -374,7 +1221,8 @@ mod test {
                 ranges: [("field1".to_owned(), 0..6),
                     ("field2".to_owned(), 6..12),
                     ("field3".to_owned(), 12..18)]
-                    .iter().cloned().collect::<BTreeMap<String, Range<usize>>>()
+                    .iter().cloned().collect::<BTreeMap<String, Range<usize>>>(),
+                nulls: BTreeSet::new()
             }),
             reader.read(&mut buf, record_spec, Vec::new(), &mut Vec::new())
         );
@@ -517,7 +1365,8 @@ mod test {
                 ranges: [("field1".to_owned(), 0..5),
                     ("field2".to_owned(), 5..11),
                     ("field3".to_owned(), 11..17)]
-                    .iter().cloned().collect::<HashMap<String, Range<usize>>>()
+                    .iter().cloned().collect::<HashMap<String, Range<usize>>>(),
+                nulls: BTreeSet::new()
             },
             name: "record1".to_string()
         }), reader.read_record());
@@ -527,10 +1376,1296 @@ mod test {
                 ranges: [("field1".to_owned(), 0..6),
                     ("field2".to_owned(), 6..12),
                     ("field3".to_owned(), 12..18)]
-                    .iter().cloned().collect::<BTreeMap<String, Range<usize>>>()
+                    .iter().cloned().collect::<BTreeMap<String, Range<usize>>>(),
+                nulls: BTreeSet::new()
             },
             name: "record1".to_string()
         }), reader.read_record());
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn read_with_skip_rules() {
+        let spec = SpecBuilder::new()
+            .with_record("record1")
+                .with_line_ending("\n")
+                .with_field("field1")
+                    .string()
+                    .with_length(3)
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let data = "BANNER\n# comment\nabc\n\n\x0cPAGE 2\nheader\ndef\ntoolong\n";
+        let mut buf = Cursor::new(data.as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(IdentityParser)
+            .with_spec_source(::spec::stream::VecStream::from(vec!["record1", "record1", "record1"]))
+            .with_record_specs(&spec.record_specs)
+            .with_skip_rule(SkipRule::FirstLines(1))
+            .with_skip_rule(SkipRule::Prefix(b"#".to_vec()))
+            .with_skip_rule(SkipRule::BlankLines)
+            .with_skip_rule(SkipRule::FormFeedHeader(2))
+            .build()
+            .unwrap()
+        ;
+        assert_result!(Ok(ref record) if record.data.data == b"abc".to_vec(), reader.read_record::<BTreeMap<_, _>>());
+        assert_eq!(3, reader.line());
+        assert_result!(Ok(ref record) if record.data.data == b"def".to_vec(), reader.read_record::<BTreeMap<_, _>>());
+        assert_eq!(7, reader.line());
+        assert_result!(
+            Err(PositionalError { error: Error::DataDoesNotMatchLineEnding(_, _), line: Some(8), .. }),
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+    }
+
+    #[test]
+    fn build_with_disagreeing_line_endings() {
+        let spec = SpecBuilder::new()
+            .with_record("record1")
+                .with_line_ending("\n")
+                .with_field("field1")
+                    .string()
+                    .with_length(3)
+                .end()
+            .end()
+            .with_record("record2")
+                .with_line_ending("\r\n")
+                .with_field("field1")
+                    .string()
+                    .with_length(3)
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new("#\r\nabc\r\n".as_bytes());
+        assert_result!(
+            Some(Error::SkipLineEndingAmbiguous(ref first, ref second)) if *first == b"\n".to_vec() && *second == b"\r\n".to_vec(),
+            ReaderBuilder::new(BinarySupport)
+                .with_source::<Cursor<_>, _>(&mut buf)
+                .with_field_parser(IdentityParser)
+                .with_spec_source(::spec::stream::VecStream::from(vec!["record2"]))
+                .with_record_specs(&spec.record_specs)
+                .with_skip_rule(SkipRule::Prefix(b"#".to_vec()))
+                .build()
+                .err()
+        );
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(IdentityParser)
+            .with_spec_source(::spec::stream::VecStream::from(vec!["record2"]))
+            .with_record_specs(&spec.record_specs)
+            .with_skip_rule(SkipRule::Prefix(b"#".to_vec()))
+            .with_skip_line_ending("\r\n")
+            .build()
+            .unwrap()
+        ;
+        assert_result!(Ok(ref record) if record.data.data == b"abc".to_vec(), reader.read_record::<BTreeMap<_, _>>());
+    }
+
+    #[test]
+    fn read_with_header() {
+        let data = "amount code\n------ ----\n12.50  ab  \n3      cd  \n";
+        let mut buf = Cursor::new(data.as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(IdentityParser)
+            .with_header(Header::new("header", "\n", true))
+            .build()
+            .unwrap()
+        ;
+        assert_result!(Ok(Record {
+            data: Data {
+                data: "12.50  ab  ".as_bytes().to_owned(),
+                ranges: [("amount".to_owned(), 0..7), ("code".to_owned(), 7..11)]
+                    .iter().cloned().collect::<BTreeMap<String, Range<usize>>>(),
+                nulls: BTreeSet::new()
+            },
+            name: "header".to_string()
+        }), reader.read_record());
+        assert_result!(Ok(Record {
+            data: Data {
+                data: "3      cd  ".as_bytes().to_owned(),
+                ranges: [("amount".to_owned(), 0..7), ("code".to_owned(), 7..11)]
+                    .iter().cloned().collect::<BTreeMap<String, Range<usize>>>(),
+                nulls: BTreeSet::new()
+            },
+            name: "header".to_string()
+        }), reader.read_record());
+
+        let mut buf = Cursor::new("code amount\nab   12    \n".as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(IdentityParser)
+            .with_header(Header::new("header", "\n", false))
+            .build()
+            .unwrap()
+        ;
+        let record = reader.read_record::<BTreeMap<_, _>>().unwrap();
+        assert_eq!(Some(&b"ab   "[..]), record.data.get("code").map(|v| &v[..]));
+        assert_eq!(Some(&b"12    "[..]), record.data.get("amount").map(|v| &v[..]));
+    }
+
+    #[test]
+    fn read_with_framing() {
+        let spec = SpecBuilder::new()
+            .with_record("record1")
+                .with_field("field1")
+                    .string()
+                    .with_length(3)
+                .end()
+                .with_field("field2")
+                    .string()
+                    .with_length(2)
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut data = vec![0, 9, 0, 0];
+        data.extend("abcde".as_bytes());
+        data.extend(&[0, 9, 0, 0]);
+        data.extend("fghij".as_bytes());
+        data.extend(&[0, 10, 0, 0]);
+        data.extend("klmnop".as_bytes());
+        data.extend(&[0, 9, 0, 0]);
+        data.extend("qrstu".as_bytes());
+        let mut buf = Cursor::new(&data[..]);
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(IdentityParser)
+            .with_spec_source(::spec::stream::VecStream::from(vec!["record1", "record1", "record1", "record1"]))
+            .with_record_specs(&spec.record_specs)
+            .with_framing(Framing::Rdw)
+            .build()
+            .unwrap()
+        ;
+        assert_result!(Ok(Record {
+            data: Data {
+                data: "abcde".as_bytes().to_owned(),
+                ranges: [("field1".to_owned(), 0..3), ("field2".to_owned(), 3..5)]
+                    .iter().cloned().collect::<BTreeMap<String, Range<usize>>>(),
+                nulls: BTreeSet::new()
+            },
+            name: "record1".to_string()
+        }), reader.read_record());
+        assert_result!(Ok(Record {
+            data: Data {
+                data: "fghij".as_bytes().to_owned(),
+                ranges: [("field1".to_owned(), 0..3), ("field2".to_owned(), 3..5)]
+                    .iter().cloned().collect::<BTreeMap<String, Range<usize>>>(),
+                nulls: BTreeSet::new()
+            },
+            name: "record1".to_string()
+        }), reader.read_record());
+        assert_result!(
+            Err(PositionalError { error: Error::DescriptorLengthMismatch(6, 5), .. }),
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+        assert_result!(Ok(ref record) if record.data.data == b"qrstu".to_vec(), reader.read_record::<BTreeMap<_, _>>());
+        assert_result!(Err(PositionalError { error: Error::EndOfFile, .. }), reader.read_record::<BTreeMap<_, _>>());
+
+        let mut data = vec![0, 22, 0, 0, 0, 9, 0, 0];
+        data.extend("abcde".as_bytes());
+        data.extend(&[0, 9, 0, 0]);
+        data.extend("fghij".as_bytes());
+        data.extend(&[0, 13, 0, 0, 0, 9, 0, 0]);
+        data.extend("klmno".as_bytes());
+        let mut buf = Cursor::new(&data[..]);
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(IdentityParser)
+            .with_spec_source(::spec::stream::VecStream::from(vec!["record1", "record1", "record1"]))
+            .with_record_specs(&spec.record_specs)
+            .with_framing(Framing::Blocked(100))
+            .build()
+            .unwrap()
+        ;
+        assert_eq!(
+            vec!["abcde".as_bytes().to_owned(), "fghij".as_bytes().to_owned(), "klmno".as_bytes().to_owned()],
+            (0..3).map(|_| reader.read_record::<BTreeMap<_, _>>().unwrap().data.data).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn read_record_with_segments() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("field1", FieldSpecBuilder::new().string().with_length(3))
+            .with_segment()
+                .with_line_ending("\n")
+                .add_field("field2", FieldSpecBuilder::new().string().with_length(2))
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new("abc\nde\nfgh\nij".as_bytes());
+        let reader = RecordReader::new(FieldReader::new(IdentityParser, BinarySupport));
+        assert_result!(
+            Ok(Data {
+                data: "abcde".as_bytes().to_owned(),
+                ranges: [("field1".to_owned(), 0..3), ("field2".to_owned(), 3..5)]
+                    .iter().cloned().collect::<BTreeMap<String, Range<usize>>>(),
+                nulls: BTreeSet::new()
+            }),
+            reader.read(&mut buf, &record_spec, Vec::new(), &mut Vec::new())
+        );
+        assert_result!(
+            Ok(Data {
+                data: "fghij".as_bytes().to_owned(),
+                ranges: [("field1".to_owned(), 0..3), ("field2".to_owned(), 3..5)]
+                    .iter().cloned().collect::<BTreeMap<String, Range<usize>>>(),
+                nulls: BTreeSet::new()
+            }),
+            reader.read(&mut buf, &record_spec, Vec::new(), &mut Vec::new())
+        );
+        let mut buf = Cursor::new("abc\ndef\n".as_bytes());
+        assert_result!(
+            Err(FieldError { error: Error::DataDoesNotMatchLineEnding(_, _), field: None }),
+            reader.read::<_, BTreeMap<_, _>>(&mut buf, &record_spec, Vec::new(), &mut Vec::new())
+        );
+    }
+
+    #[test]
+    fn read_record_with_separator() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .with_separator("|")
+            .add_field("field1", FieldSpecBuilder::new().string().with_length(3))
+            .add_field("field2", FieldSpecBuilder::new().string().with_length(2))
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new("abc|de\nfgh ij\n".as_bytes());
+        let reader = RecordReader::new(FieldReader::new(IdentityParser, BinarySupport));
+        assert_result!(
+            Ok(Data {
+                data: "abcde".as_bytes().to_owned(),
+                ranges: [("field1".to_owned(), 0..3), ("field2".to_owned(), 3..5)]
+                    .iter().cloned().collect::<BTreeMap<String, Range<usize>>>(),
+                nulls: BTreeSet::new()
+            }),
+            reader.read(&mut buf, &record_spec, Vec::new(), &mut Vec::new())
+        );
+        assert_result!(
+            Err(FieldError {
+                error: Error::DataDoesNotMatchSeparator(ref expected, ref actual),
+                field: Some(ref field)
+            }) if *expected == b"|".to_vec() && *actual == b" ".to_vec() && field == "field2",
+            reader.read::<_, BTreeMap<_, _>>(&mut buf, &record_spec, Vec::new(), &mut Vec::new())
+        );
+    }
+
+    #[test]
+    fn read_record_with_redefine() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("field1", FieldSpecBuilder::new().string().with_length(1))
+            .add_field("field2", FieldSpecBuilder::new().string().with_length(5))
+            .with_redefine("field2", "field1")
+                .add_field("A", "field3", FieldSpecBuilder::new().string().with_length(5))
+                .add_field("B", "field4", FieldSpecBuilder::new().string().with_length(2))
+                .add_field("B", "field5", FieldSpecBuilder::new().string().with_length(3))
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new("Aabcde\nBfghij\nCklmno\n".as_bytes());
+        let reader = RecordReader::new(FieldReader::new(IdentityParser, BinarySupport));
+        assert_result!(
+            Ok(Data {
+                data: "Aabcde".as_bytes().to_owned(),
+                ranges: [("field1".to_owned(), 0..1), ("field3".to_owned(), 1..6)]
+                    .iter().cloned().collect::<BTreeMap<String, Range<usize>>>(),
+                nulls: BTreeSet::new()
+            }),
+            reader.read(&mut buf, &record_spec, Vec::new(), &mut Vec::new())
+        );
+        assert_result!(
+            Ok(Data {
+                data: "Bfghij".as_bytes().to_owned(),
+                ranges: [("field1".to_owned(), 0..1), ("field4".to_owned(), 1..3), ("field5".to_owned(), 3..6)]
+                    .iter().cloned().collect::<BTreeMap<String, Range<usize>>>(),
+                nulls: BTreeSet::new()
+            }),
+            reader.read(&mut buf, &record_spec, Vec::new(), &mut Vec::new())
+        );
+        assert_result!(
+            Err(FieldError {
+                error: Error::NoAlternativeForDiscriminator(ref value),
+                field: Some(ref field)
+            }) if *value == b"C".to_vec() && field == "field2",
+            reader.read::<_, BTreeMap<_, _>>(&mut buf, &record_spec, Vec::new(), &mut Vec::new())
+        );
+    }
+
+    #[test]
+    fn read_record_with_bitmap() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("bitmap", FieldSpecBuilder::new().string().with_length(2))
+            .add_field("field1", FieldSpecBuilder::new().string().with_length(2))
+            .add_field("field2", FieldSpecBuilder::new().llvar(10))
+            .add_field("field3", FieldSpecBuilder::new().string().with_length(3))
+            .add_field("mti", FieldSpecBuilder::new().string().with_length(1))
+            .with_bitmap("bitmap", BitmapEncoding::Hex)
+                .add_field(2, "field1")
+                .add_field(3, "field2")
+                .add_field(12, "field3")
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new("C010ab1231\n60ab04cdef2\n2011abcdefghijk3\n".as_bytes());
+        let reader = RecordReader::new(FieldReader::new(IdentityParser, BinarySupport));
+        assert_result!(
+            Ok(Data {
+                data: "C010ab1231".as_bytes().to_owned(),
+                ranges: [("bitmap".to_owned(), 0..4), ("field1".to_owned(), 4..6), ("field3".to_owned(), 6..9), ("mti".to_owned(), 9..10)]
+                    .iter().cloned().collect::<BTreeMap<String, Range<usize>>>(),
+                nulls: BTreeSet::new()
+            }),
+            reader.read(&mut buf, &record_spec, Vec::new(), &mut Vec::new())
+        );
+        assert_result!(
+            Ok(Data {
+                data: "60abcdef2".as_bytes().to_owned(),
+                ranges: [("bitmap".to_owned(), 0..2), ("field1".to_owned(), 2..4), ("field2".to_owned(), 4..8), ("mti".to_owned(), 8..9)]
+                    .iter().cloned().collect::<BTreeMap<String, Range<usize>>>(),
+                nulls: BTreeSet::new()
+            }),
+            reader.read(&mut buf, &record_spec, Vec::new(), &mut Vec::new())
+        );
+        assert_result!(
+            Err(FieldError {
+                error: Error::InvalidLengthPrefix(ref prefix),
+                field: Some(ref field)
+            }) if *prefix == b"11".to_vec() && field == "field2",
+            reader.read::<_, BTreeMap<_, _>>(&mut buf, &record_spec, Vec::new(), &mut Vec::new())
+        );
+        let mut buf = Cursor::new("04\n".as_bytes());
+        assert_result!(
+            Err(FieldError {
+                error: Error::BitmapFieldNotDefined(6),
+                field: Some(ref field)
+            }) if field == "bitmap",
+            reader.read::<_, BTreeMap<_, _>>(&mut buf, &record_spec, Vec::new(), &mut Vec::new())
+        );
+    }
+
+    #[test]
+    fn read_record_with_display_width() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("code", FieldSpecBuilder::new().string().with_length(2))
+            .add_field("name", FieldSpecBuilder::new().string().with_length(7))
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new("ab\u{65e5}\u{672c}\u{8a9e} \n".as_bytes());
+        let reader = RecordReader::new(FieldReader::new(DefaultParser, DisplayWidthSupport));
+        assert_result!(
+            Ok(Data {
+                data: "ab\u{65e5}\u{672c}\u{8a9e}".to_string(),
+                ranges: [("code".to_owned(), 0..2), ("name".to_owned(), 2..11)]
+                    .iter().cloned().collect::<BTreeMap<String, Range<usize>>>(),
+                nulls: BTreeSet::new()
+            }),
+            reader.read(&mut buf, &record_spec, Vec::new(), &mut Vec::new())
+        );
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("a", FieldSpecBuilder::new().string().with_length(1))
+            .add_field("b", FieldSpecBuilder::new().string().with_length(1))
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new("e\u{301}x\n".as_bytes());
+        assert_result!(
+            Ok(Data {
+                data: "e\u{301}x".to_string(),
+                ranges: [("a".to_owned(), 0..3), ("b".to_owned(), 3..4)]
+                    .iter().cloned().collect::<BTreeMap<String, Range<usize>>>(),
+                nulls: BTreeSet::new()
+            }),
+            reader.read(&mut buf, &record_spec, Vec::new(), &mut Vec::new())
+        );
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("a", FieldSpecBuilder::new().string().with_length(2))
+            .add_field("b", FieldSpecBuilder::new().string().with_length(1))
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new("a\u{65e5}\n".as_bytes());
+        assert_result!(
+            Err(FieldError {
+                error: Error::CharacterCrossesFieldBoundary(ref value),
+                field: Some(ref field)
+            }) if *value == "a\u{65e5}".as_bytes().to_vec() && field == "a",
+            reader.read::<_, BTreeMap<String, Range<usize>>>(&mut buf, &record_spec, Vec::new(), &mut Vec::new())
+        );
+    }
+
+    #[test]
+    fn read_record_with_groups() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("id", FieldSpecBuilder::new().string().with_length(2))
+            .with_group("address")
+                .add_field("street", FieldSpecBuilder::new().string().with_length(4))
+                .add_group("geo", GroupSpecBuilder::new()
+                    .add_field("lat", FieldSpecBuilder::new().string().with_length(2))
+                    .add_field("lng", FieldSpecBuilder::new().string().with_length(2))
+                )
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new("1234567890\n".as_bytes());
+        let reader = RecordReader::new(FieldReader::new(IdentityParser, BinarySupport));
+        let data: Data<BTreeMap<String, Range<usize>>, Vec<u8>> = reader.read(&mut buf, &record_spec, Vec::new(), &mut Vec::new()).unwrap();
+        assert_eq!(Some(&b"12"[..]), data.get("address.geo.lat").map(|v| &v[..]));
+        assert_eq!(None, data.get("address"));
+        assert_eq!(None, data.get("address.geo"));
+        let tree = data.to_tree(&record_spec.groups);
+        assert_eq!(Some(&b"12"[..]), tree.get("address.geo.lat").and_then(|n| n.value()).map(|v| &v[..]));
+        assert_eq!(Some(&b"5678"[..]), tree.get("address").and_then(|n| n.get("street")).and_then(|n| n.value()).map(|v| &v[..]));
+        assert_eq!(Some(&b"90"[..]), tree.get("id").and_then(|n| n.value()).map(|v| &v[..]));
+        assert_eq!(None, tree.get("address.geo").and_then(|n| n.value()));
+        assert_result!(Some(&Node::Group(ref fields)) if fields.len() == 2, tree.get("address.geo"));
+        assert_eq!(None, tree.get("geo"));
+    }
+
+    #[test]
+    fn read_record_with_nulls() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("amount", FieldSpecBuilder::new().number().with_length(3).with_null(NullValue::Zeros))
+            .add_field("code", FieldSpecBuilder::new().string().with_length(3).with_null(NullValue::Sentinel(b"NA".to_vec())))
+            .add_field("high", FieldSpecBuilder::new().string().with_length(2).with_null(NullValue::HighValues))
+            .add_field("low", FieldSpecBuilder::new().string().with_length(2).with_null(NullValue::LowValues))
+            .add_field("name", FieldSpecBuilder::new().string().with_length(3).with_null(NullValue::Blanks))
+            .build()
+            .unwrap()
+        ;
+        let reader = RecordReader::new(FieldReader::new(DefaultParser, BinarySupport));
+        let mut buf = Cursor::new(b"000NA \xff\xff\x00\x00   \n".to_vec());
+        let data: Data<BTreeMap<String, Range<usize>>, Vec<u8>> = reader.read(&mut buf, &record_spec, Vec::new(), &mut Vec::new()).unwrap();
+        for name in &["amount", "code", "high", "low", "name"] {
+            assert!(data.is_null(name));
+            assert_eq!(None, data.get(name));
+        }
+        let mut buf = Cursor::new(b"010N  \xff\x00\x00\xffab \n".to_vec());
+        let data: Data<BTreeMap<String, Range<usize>>, Vec<u8>> = reader.read(&mut buf, &record_spec, Vec::new(), &mut Vec::new()).unwrap();
+        assert!(data.nulls.is_empty());
+        assert_eq!(Some(&b"10"[..]), data.get("amount").map(|v| &v[..]));
+        assert_eq!(Some(&b"N"[..]), data.get("code").map(|v| &v[..]));
+        assert_eq!(Some(&b"ab"[..]), data.get("name").map(|v| &v[..]));
+    }
+
+    #[test]
+    fn read_record_with_constants() {
+        let spec = SpecBuilder::new()
+            .with_record("record1")
+                .with_line_ending("\n")
+                .with_field("$type")
+                    .string()
+                    .with_length(4)
+                    .with_default("HDR")
+                    .constant()
+                .end()
+                .with_field("version")
+                    .number()
+                    .with_length(2)
+                    .with_default("02")
+                    .constant()
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new("HDR 02\nHDX 02\n".as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(DefaultParser)
+            .with_spec_source(::spec::stream::VecStream::from(vec!["record1", "record1", "record1"]))
+            .with_record_specs(&spec.record_specs)
+            .build()
+            .unwrap()
+        ;
+        assert_result!(Ok(ref record) if record.data.data == b"HDR2".to_vec(), reader.read_record::<BTreeMap<_, _>>());
+        assert_result!(
+            Err(PositionalError { error: Error::ConstantMismatch(ref expected, ref actual), position: Some(ref position), line: Some(2) })
+                if *expected == b"HDR".to_vec() && *actual == b"HDX".to_vec() && position.field == Some("$type".to_string()),
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+        let mut buf = Cursor::new("HDR 03\n".as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(DefaultParser)
+            .with_spec_source(::spec::stream::VecStream::from(vec!["record1"]))
+            .with_record_specs(&spec.record_specs)
+            .build()
+            .unwrap()
+        ;
+        assert_result!(
+            Err(PositionalError { error: Error::ConstantMismatch(ref expected, ref actual), position: Some(ref position), line: Some(1) })
+                if *expected == b"02".to_vec() && *actual == b"3".to_vec() && position.field == Some("version".to_string()),
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+    }
+
+    #[test]
+    fn read_record_with_validations() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("code", FieldSpecBuilder::new().string().with_length(3).with_validation(ValidationRule::CharacterClass(CharacterClass::Alphanumeric)))
+            .add_field("name", FieldSpecBuilder::new().string().with_length(3).with_null(NullValue::Blanks).with_validation(ValidationRule::Required))
+            .build()
+            .unwrap()
+        ;
+        let reader = RecordReader::new(FieldReader::new(DefaultParser, BinarySupport));
+        let mut buf = Cursor::new("ab1cd \na-1cd \nab1   \n".as_bytes());
+        let data: Data<BTreeMap<String, Range<usize>>, Vec<u8>> = reader.read(&mut buf, &record_spec, Vec::new(), &mut Vec::new()).unwrap();
+        assert_eq!(b"ab1cd".to_vec(), data.data);
+        assert_result!(
+            Err(FieldError { error: Error::ValidationFailed(ValidationRule::CharacterClass(CharacterClass::Alphanumeric), Some(ref value)), field: Some(ref field) })
+                if *value == b"a-1".to_vec() && field == "code",
+            reader.read::<_, BTreeMap<String, Range<usize>>>(&mut buf, &record_spec, Vec::new(), &mut Vec::new())
+        );
+        let mut buf = Cursor::new("ab1   \n".as_bytes());
+        assert_result!(
+            Err(FieldError { error: Error::ValidationFailed(ValidationRule::Required, None), field: Some(ref field) }) if field == "name",
+            reader.read::<_, BTreeMap<String, Range<usize>>>(&mut buf, &record_spec, Vec::new(), &mut Vec::new())
+        );
+    }
+
+    #[test]
+    fn read_record_with_record_rules() {
+        let spec = SpecBuilder::new()
+            .with_record("record1")
+                .with_line_ending("\n")
+                .with_field("end")
+                    .number()
+                    .with_length(2)
+                .end()
+                .with_field("start")
+                    .number()
+                    .with_length(2)
+                .end()
+                .add_rule(RecordRule::Ordered { first: "start".to_string(), second: "end".to_string() })
+                .add_rule_fn("not_equal", vec!["end", "start"], |values: &RecordValues| values.get("start") != values.get("end"))
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new("0501\n0105\n0505\n".as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(DefaultParser)
+            .with_spec_source(::spec::stream::VecStream::from(vec!["record1", "record1", "record1"]))
+            .with_record_specs(&spec.record_specs)
+            .build()
+            .unwrap()
+        ;
+        assert_result!(Ok(ref record) if record.data.data == b"51".to_vec(), reader.read_record::<BTreeMap<_, _>>());
+        assert_result!(
+            Err(PositionalError { error: Error::RecordRuleFailed(ref rule, ref fields), position: Some(ref position), line: Some(2) })
+                if rule == "ordered" && *fields == vec!["start".to_string(), "end".to_string()] && position.record == "record1" && position.field == None,
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+        assert_result!(
+            Err(PositionalError { error: Error::RecordRuleFailed(ref rule, _), .. }) if rule == "not_equal",
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+    }
+
+    #[test]
+    fn read_record_with_projection() {
+        let spec = SpecBuilder::new()
+            .with_record("record1")
+                .with_line_ending("\n")
+                .with_field("end")
+                    .number()
+                    .with_length(2)
+                .end()
+                .with_field("name")
+                    .string()
+                    .with_length(3)
+                .end()
+                .with_field("start")
+                    .number()
+                    .with_length(2)
+                .end()
+                .add_rule(RecordRule::Ordered { first: "start".to_string(), second: "end".to_string() })
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut parser = MockParser::new();
+        let name_spec = spec.record_specs["record1"].field_specs["name"].clone();
+        parser.add_parse_call(b"abc".to_vec(), name_spec.clone(), Ok(b"abc".to_vec()));
+        parser.add_parse_call(b"xyz".to_vec(), name_spec, Ok(b"xyz".to_vec()));
+        let mut buf = Cursor::new("01abc05\n05xyz01\n".as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(parser)
+            .with_spec_source(::spec::stream::VecStream::from(vec!["record1", "record1"]))
+            .with_record_specs(&spec.record_specs)
+            .with_projection("record1", vec!["name"])
+            .build()
+            .unwrap()
+        ;
+        let record = reader.read_record::<BTreeMap<_, _>>().unwrap();
+        assert_eq!(b"abc".to_vec(), record.data.data);
+        assert_eq!(
+            [("name".to_string(), 0..3)].iter().cloned().collect::<BTreeMap<String, Range<usize>>>(),
+            record.data.ranges
+        );
+        let record = reader.read_record::<BTreeMap<_, _>>().unwrap();
+        assert_eq!(Some(&b"xyz"[..]), record.data.get("name").map(|v| &v[..]));
+        assert_eq!(None, record.data.get("start"));
+        assert_result!(Err(PositionalError { error: Error::EndOfFile, line: Some(3), .. }), reader.read_record::<BTreeMap<_, _>>());
+    }
+
+    #[test]
+    fn read_record_with_record_filter() {
+        let spec = SpecBuilder::new()
+            .with_record("detail")
+                .with_line_ending("\n")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                    .with_default("D")
+                .end()
+                .with_field("amount")
+                    .number()
+                    .with_length(4)
+                .end()
+            .end()
+            .with_record("header")
+                .with_line_ending("\n")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                    .with_default("H")
+                .end()
+                .with_field("name")
+                    .string()
+                    .with_length(6)
+                .end()
+            .end()
+            .with_record("trailer")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                    .with_default("T")
+                .end()
+                .with_field("count")
+                    .number()
+                    .with_length(3)
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new("Hbatch1\nD0012\nD0034\nHbatch2 and more\nD0056\nT002".as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(MockParser::new())
+            .with_spec_source(::reader::spec::ResolverSource::new(::spec::resolver::IdFieldResolver::new()))
+            .with_record_specs(&spec.record_specs)
+            .with_record_filter(vec!["detail"])
+            .with_projection("detail", Vec::<String>::new())
+            .build()
+            .unwrap()
+        ;
+        assert_result!(Ok(ref record) if record.name == "detail", reader.read_record::<BTreeMap<_, _>>());
+        assert_eq!(2, reader.line());
+        assert_eq!(Some(&1), reader.skipped_records().get("header"));
+        assert_result!(Ok(ref record) if record.name == "detail", reader.read_record::<BTreeMap<_, _>>());
+        assert_result!(
+            Err(PositionalError { error: Error::DataDoesNotMatchLineEnding(_, ref actual), line: Some(4), .. }) if *actual == b" ".to_vec(),
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+        assert_result!(Ok(ref record) if record.name == "detail", reader.read_record::<BTreeMap<_, _>>());
+        assert_eq!(5, reader.line());
+        assert_result!(Err(PositionalError { error: Error::EndOfFile, line: Some(6), .. }), reader.read_record::<BTreeMap<_, _>>());
+        assert_eq!(
+            vec![("header".to_string(), 1), ("trailer".to_string(), 1)],
+            reader.skipped_records().iter().map(|(name, count)| (name.clone(), *count)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn read_projected_with_redefine() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("field1", FieldSpecBuilder::new().string().with_length(1))
+            .add_field("field2", FieldSpecBuilder::new().string().with_length(5))
+            .with_redefine("field2", "field1")
+                .add_field("A", "field3", FieldSpecBuilder::new().string().with_length(5))
+                .add_field("B", "field4", FieldSpecBuilder::new().string().with_length(2))
+                .add_field("B", "field5", FieldSpecBuilder::new().string().with_length(3))
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let projection = vec!["field5".to_string()].into_iter().collect::<BTreeSet<_>>();
+        let mut buf = Cursor::new("Aabcde\nBfghij\n".as_bytes());
+        let reader = RecordReader::new(FieldReader::new(IdentityParser, BinarySupport));
+        assert_result!(
+            Ok(Data {
+                data: "A".as_bytes().to_owned(),
+                ranges: BTreeMap::<String, Range<usize>>::new(),
+                nulls: BTreeSet::new()
+            }),
+            reader.read_projected(&mut buf, &record_spec, &projection, Vec::new(), &mut Vec::new())
+        );
+        assert_result!(
+            Ok(Data {
+                data: "Bhij".as_bytes().to_owned(),
+                ranges: [("field5".to_owned(), 1..4)].iter().cloned().collect::<BTreeMap<String, Range<usize>>>(),
+                nulls: BTreeSet::new()
+            }),
+            reader.read_projected(&mut buf, &record_spec, &projection, Vec::new(), &mut Vec::new())
+        );
+    }
+
+    #[test]
+    fn read_projected_with_file_rules() {
+        let spec = SpecBuilder::new()
+            .with_record("a")
+                .with_line_ending("\n")
+                .with_field("key")
+                    .string()
+                    .with_length(2)
+                .end()
+                .with_field("name")
+                    .string()
+                    .with_length(3)
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new("01abc\n01xyz\n".as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(DefaultParser)
+            .with_spec_source(::spec::stream::VecStream::from(vec!["a", "a"]))
+            .with_record_specs(&spec.record_specs)
+            .with_file_rules(vec![FileRule::UniqueKey { record: "a".to_string(), fields: vec!["key".to_string()] }])
+            .with_projection("a", vec!["name"])
+            .build()
+            .unwrap()
+        ;
+        let record = reader.read_record::<BTreeMap<_, _>>().unwrap();
+        assert_eq!(Some(&b"01"[..]), record.data.get("key").map(|v| &v[..]));
+        assert_result!(
+            Err(PositionalError { error: Error::FileRuleFailed(_, _, Some((_, 1))), .. }),
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+    }
+
+    #[test]
+    fn read_with_file_rules() {
+        let spec = SpecBuilder::new()
+            .with_record("batch")
+                .with_line_ending("\n")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                .end()
+                .with_field("batch")
+                    .string()
+                    .with_length(2)
+                .end()
+            .end()
+            .with_record("detail")
+                .with_line_ending("\n")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                .end()
+                .with_field("batch")
+                    .string()
+                    .with_length(2)
+                .end()
+                .with_field("key")
+                    .string()
+                    .with_length(2)
+                    .with_null(NullValue::Blanks)
+                .end()
+            .end()
+            .add_rule(FileRule::UniqueKey { record: "detail".to_string(), fields: vec!["key".to_string()] })
+            .add_rule(FileRule::References { record: "detail".to_string(), field: "batch".to_string(), target_record: "batch".to_string(), target_field: "batch".to_string() })
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new("B01\nD01k1\nD01k1\nD02k2\nD01  \nD01  \n".as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(DefaultParser)
+            .with_spec_source(::spec::stream::VecStream::from(vec!["batch", "detail", "detail", "detail", "detail", "detail"]))
+            .with_spec(&spec)
+            .build()
+            .unwrap()
+        ;
+        assert_result!(Ok(ref record) if record.name == "batch", reader.read_record::<BTreeMap<_, _>>());
+        assert_result!(Ok(ref record) if record.name == "detail", reader.read_record::<BTreeMap<_, _>>());
+        assert_result!(
+            Err(PositionalError { error: Error::FileRuleFailed(ref rule, _, Some((ref record, 2))), position: Some(ref position), line: Some(3) })
+                if rule == "unique_key" && record == "detail" && position.record == "detail",
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+        assert_result!(
+            Err(PositionalError { error: Error::FileRuleFailed(ref rule, ref fields, Some((ref record, 1))), line: Some(4), .. })
+                if rule == "references" && *fields == vec!["batch".to_string()] && record == "batch",
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+        assert_result!(Ok(_), reader.read_record::<BTreeMap<_, _>>());
+        assert_result!(Ok(_), reader.read_record::<BTreeMap<_, _>>());
+        let mut buf = Cursor::new("".as_bytes());
+        assert_result!(
+            Some(Error::FileRuleTargetFiltered(ref rule, ref record)) if rule == "references" && record == "batch",
+            ReaderBuilder::new(BinarySupport)
+                .with_source::<Cursor<_>, _>(&mut buf)
+                .with_field_parser(DefaultParser)
+                .with_spec_source(::spec::stream::VecStream::from(vec!["detail"]))
+                .with_spec(&spec)
+                .with_record_filter(vec!["detail"])
+                .build()
+                .err()
+        );
+    }
+
+    #[test]
+    fn read_record_collecting_errors() {
+        let spec = SpecBuilder::new()
+            .with_record("record1")
+                .with_line_ending("\n")
+                .with_field("code")
+                    .string()
+                    .with_length(2)
+                    .with_validation(ValidationRule::CharacterClass(CharacterClass::Digits))
+                .end()
+                .with_field("name")
+                    .string()
+                    .with_length(3)
+                    .with_validation(ValidationRule::MinLength(2))
+                .end()
+                .with_field("type")
+                    .string()
+                    .with_length(1)
+                    .with_default("A")
+                    .constant()
+                .end()
+                .add_rule(RecordRule::AnyPopulated(vec!["name".to_string()]))
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let record_spec = &spec.record_specs["record1"];
+        let reader = RecordReader::new(FieldReader::new(DefaultParser, BinarySupport));
+        let mut buf = Cursor::new("1a   B\n12abcA\n12ab".as_bytes());
+        let (data, errors) = reader.read_collecting::<_, BTreeMap<String, Range<usize>>>(&mut buf, record_spec, Vec::new(), &mut Vec::new()).unwrap();
+        assert_eq!(Some(&b"1a"[..]), data.get("code").map(|v| &v[..]));
+        assert_eq!(Some(&b"B"[..]), data.get("type").map(|v| &v[..]));
+        assert_eq!(
+            vec![Some("code".to_string()), Some("name".to_string()), Some("type".to_string()), None],
+            errors.iter().map(|e| e.field.clone()).collect::<Vec<_>>()
+        );
+        match errors[3].error {
+            Error::RecordRuleFailed(ref rule, _) if rule == "any_populated" => (),
+            ref e => panic!("unexpected error {:?}", e)
+        }
+        let (data, errors) = reader.read_collecting::<_, BTreeMap<String, Range<usize>>>(&mut buf, record_spec, Vec::new(), &mut Vec::new()).unwrap();
+        assert_eq!(b"12abcA".to_vec(), data.data);
+        assert!(errors.is_empty());
+        assert_result!(
+            Err(FieldError { error: Error::CouldNotReadEnough(_), .. }),
+            reader.read_collecting::<_, BTreeMap<String, Range<usize>>>(&mut buf, record_spec, Vec::new(), &mut Vec::new())
+        );
+
+        let mut buf = Cursor::new("1a   B\n1aabcA\n".as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(DefaultParser)
+            .with_spec_source(::spec::stream::VecStream::from(vec!["record1", "record1"]))
+            .with_record_specs(&spec.record_specs)
+            .with_file_rule(FileRule::UniqueKey { record: "record1".to_string(), fields: vec!["code".to_string()] })
+            .with_file_rule(FileRule::Sequence { record: "record1".to_string(), field: "code".to_string() })
+            .build()
+            .unwrap()
+        ;
+        let (_, errors) = reader.read_record_collecting::<BTreeMap<_, _>>().unwrap();
+        assert_eq!(5, errors.len());
+        assert!(errors.iter().all(|e| e.line == Some(1) && e.position.as_ref().map_or(false, |position| position.record == "record1")));
+        let (_, errors) = reader.read_record_collecting::<BTreeMap<_, _>>().unwrap();
+        assert_eq!(3, errors.len());
+        match errors[1] {
+            PositionalError { error: Error::FileRuleFailed(ref rule, _, Some((_, 1))), line: Some(2), .. } if rule == "unique_key" => (),
+            ref e => panic!("unexpected error {:?}", e)
+        }
+        match errors[2] {
+            PositionalError { error: Error::FileRuleFailed(ref rule, _, None), line: Some(2), .. } if rule == "sequence" => (),
+            ref e => panic!("unexpected error {:?}", e)
+        }
+    }
+
+    #[test]
+    fn read_record_with_error_context() {
+        let spec = SpecBuilder::new()
+            .with_record("record1")
+                .with_line_ending("\n")
+                .with_field("code")
+                    .string()
+                    .with_length(2)
+                    .with_validation(ValidationRule::CharacterClass(CharacterClass::Digits))
+                .end()
+                .with_field("name")
+                    .string()
+                    .with_length(3)
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new("12abc\n1xabc\n".as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(DefaultParser)
+            .with_spec_source(::spec::stream::VecStream::from(vec!["record1", "record1"]))
+            .with_record_specs(&spec.record_specs)
+            .with_error_context()
+            .build()
+            .unwrap()
+        ;
+        assert_result!(Ok(_), reader.read_record::<BTreeMap<_, _>>());
+        assert_eq!(Some(&b"12abc\n"[..]), reader.last_record());
+        let error = reader.read_record::<BTreeMap<_, _>>().unwrap_err();
+        assert_eq!(
+            format!("{}\n  |  \n  |12\n2 |1x\n  || \n  |^^", error),
+            reader.error_context(&error).unwrap().to_string()
+        );
+        let explanation = reader.explain(b"1xabc", "record1").unwrap();
+        assert_eq!(Some(b"abc".to_vec()), explanation.fields[1].value);
+        assert!(reader.explain(b"1xabc", "record2").is_none());
+
+        let mut buf = Cursor::new("1xabc\n".as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(DefaultParser)
+            .with_spec_source(::spec::stream::VecStream::from(vec!["record1"]))
+            .with_record_specs(&spec.record_specs)
+            .build()
+            .unwrap()
+        ;
+        let error = reader.read_record::<BTreeMap<_, _>>().unwrap_err();
+        assert!(reader.error_context(&error).is_none());
+
+        let spec = SpecBuilder::new()
+            .with_record("record1")
+                .with_line_ending("\n")
+                .with_field("field1")
+                    .string()
+                    .with_length(5)
+                    .with_length_prefix(1)
+                .end()
+                .with_field("field2")
+                    .string()
+                    .with_length(2)
+                    .with_validation(ValidationRule::CharacterClass(CharacterClass::Digits))
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new("3abc1x\n".as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(DefaultParser)
+            .with_spec_source(::spec::stream::VecStream::from(vec!["record1"]))
+            .with_record_specs(&spec.record_specs)
+            .with_error_context()
+            .build()
+            .unwrap()
+        ;
+        let error = reader.read_record::<BTreeMap<_, _>>().unwrap_err();
+        assert_eq!(
+            format!("{}\n  |      \n  |123456\n1 |3abc1x\n  ||   | \n  |    ^^", error),
+            reader.error_context(&error).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn read_record_with_typed_errors() {
+        let spec = SpecBuilder::new()
+            .with_record("a")
+                .with_line_ending("\n")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                    .with_default("A")
+                .end()
+                .with_field("value")
+                    .string()
+                    .with_length(2)
+                .end()
+            .end()
+            .with_record("b")
+                .with_line_ending("\n")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                    .with_default("B")
+                .end()
+                .with_field("value")
+                    .string()
+                    .with_length(2)
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new("A12\nB3".as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(DefaultParser)
+            .with_spec_source(::reader::spec::ResolverSource::new(::spec::resolver::IdFieldResolver::new()))
+            .with_record_specs(&spec.record_specs)
+            .build()
+            .unwrap()
+        ;
+        assert_result!(Ok(ref record) if record.name == "a", reader.read_record::<BTreeMap<_, _>>());
+        assert_result!(
+            Err(PositionalError { error: Error::UnexpectedEndOfFile(ref data), line: Some(2), .. }) if *data == b"3".to_vec(),
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+        assert_result!(Err(PositionalError { error: Error::EndOfFile, line: Some(3), .. }), reader.read_record::<BTreeMap<_, _>>());
+
+        let mut record_specs = spec.record_specs.clone();
+        record_specs.insert("c".to_string(), spec.record_specs["a"].clone());
+        let mut buf = Cursor::new("A12\n".as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(DefaultParser)
+            .with_spec_source(::reader::spec::ResolverSource::new(::spec::resolver::IdFieldResolver::new()))
+            .with_record_specs(&record_specs)
+            .build()
+            .unwrap()
+        ;
+        assert_result!(
+            Err(PositionalError { error: Error::SpecStreamError(StreamError::AmbiguousRecord(ref names)), .. }) if *names == vec!["a".to_string(), "c".to_string()],
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+
+        let mut buf = Cursor::new("A12\n".as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(DefaultParser)
+            .with_spec_source(::spec::stream::VecStream::from(vec!["z"]))
+            .with_record_specs(&spec.record_specs)
+            .build()
+            .unwrap()
+        ;
+        assert_result!(
+            Err(PositionalError { error: Error::SpecStreamError(StreamError::UnknownRecord(ref name)), line: Some(1), .. }) if name == "z",
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+
+        let mut buf = Cursor::new("".as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(DefaultParser)
+            .with_spec_source(::spec::stream::VecStream::from(vec!["z"]))
+            .with_record_specs(&spec.record_specs)
+            .build()
+            .unwrap()
+        ;
+        assert_result!(
+            Err(PositionalError { error: Error::SpecStreamError(StreamError::UnknownRecord(ref name)), line: Some(1), .. }) if name == "z",
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+
+        let mut stream = ::spec::stream::VecStream::from(vec!["a"]);
+        {
+            let mut buf = Cursor::new("".as_bytes());
+            let mut reader = ReaderBuilder::new(BinarySupport)
+                .with_source::<Cursor<_>, _>(&mut buf)
+                .with_field_parser(DefaultParser)
+                .with_spec_source(&mut stream)
+                .with_record_specs(&spec.record_specs)
+                .build()
+                .unwrap()
+            ;
+            assert_result!(Err(PositionalError { error: Error::EndOfFile, line: Some(1), .. }), reader.read_record::<BTreeMap<_, _>>());
+        }
+        assert_eq!(1, stream.position);
+    }
+
+
+    #[test]
+    fn build_with_unlocatable_id_field() {
+        let spec = SpecBuilder::new()
+            .with_record("a")
+                .with_line_ending("\n")
+                .with_field_order(vec!["name", "$id"])
+                .with_field("name")
+                    .string()
+                    .llvar(10)
+                .end()
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                    .with_default("A")
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let resolver = ::spec::resolver::IdFieldResolver::new();
+        assert_result!(
+            Err(StreamError::IdFieldNotLocatable(ref record, ref field)) if record == "a" && field == "$id",
+            ::reader::spec::Resolver::resolve(&resolver, &mut Cursor::new("03abcA\n".as_bytes()), &spec.record_specs, &BinarySupport)
+        );
+        let mut buf = Cursor::new("03abcA\n".as_bytes());
+        assert_result!(
+            Some(Error::SpecStreamError(StreamError::IdFieldNotLocatable(ref record, ref field))) if record == "a" && field == "$id",
+            ReaderBuilder::new(BinarySupport)
+                .with_source::<Cursor<_>, _>(&mut buf)
+                .with_field_parser(DefaultParser)
+                .with_spec_source(::reader::spec::ResolverSource::new(resolver))
+                .with_record_specs(&spec.record_specs)
+                .build()
+                .err()
+        );
+    }
+
+    #[test]
+    fn read_record_with_compiled_spec() {
+        let spec = SpecBuilder::new()
+            .with_record("a")
+                .with_line_ending("\n")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                    .with_default("A")
+                .end()
+                .with_field("value")
+                    .string()
+                    .with_length(3)
+                .end()
+            .end()
+            .with_record("b")
+                .with_line_ending("\n")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                    .with_default("B")
+                .end()
+                .with_field("other")
+                    .string()
+                    .with_length(2)
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let compiled = ::spec::compiled::CompiledSpec::new(spec).with_id_field("$id");
+        let mut buf = Cursor::new("A12 \nBxy\nA3  \n".as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(DefaultParser)
+            .with_compiled_spec(&compiled)
+            .build()
+            .unwrap()
+        ;
+        let first = reader.read_record::<::record::TableRanges>().unwrap();
+        assert_eq!("a", first.name);
+        assert_eq!(Some(&b"12"[..]), first.data.get("value").map(|v| &v[..]));
+        let second = reader.read_record::<::record::TableRanges>().unwrap();
+        assert_eq!("b", second.name);
+        assert_eq!(
+            vec![("$id".to_string(), 0..1), ("other".to_string(), 1..3)],
+            second.data.ranges.range_iter().map(|(name, range)| (name.clone(), range.clone())).collect::<Vec<_>>()
+        );
+        let third = reader.read_record::<::record::TableRanges>().unwrap();
+        assert_eq!(Some(&b"3"[..]), third.data.get("value").map(|v| &v[..]));
+        assert!(Arc::ptr_eq(first.data.ranges.table().unwrap(), third.data.ranges.table().unwrap()));
+        assert!(Arc::ptr_eq(compiled.table("a").unwrap(), third.data.ranges.table().unwrap()));
+        assert_result!(Err(PositionalError { error: Error::EndOfFile, .. }), reader.read_record::<::record::TableRanges>());
+    }
+
+    #[test]
+    fn read_record_into_recycled_record() {
+        let spec = SpecBuilder::new()
+            .with_record("a")
+                .with_line_ending("\n")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                    .with_default("A")
+                .end()
+                .with_field("value")
+                    .string()
+                    .with_length(3)
+                .end()
+            .end()
+            .with_record("b")
+                .with_line_ending("\n")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                    .with_default("B")
+                .end()
+                .with_field("other")
+                    .string()
+                    .with_length(2)
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut pool = ::reader::field_buffer::Pool::new();
+        pool.recycle(Vec::with_capacity(64));
+        let mut buf = Cursor::new("A12 \nBxy\nA3  \nAx".as_bytes());
+        let mut reader = ReaderBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_parser(DefaultParser)
+            .with_spec_source(::reader::spec::ResolverSource::new(::spec::resolver::IdFieldResolver::new()))
+            .with_record_specs(spec.record_specs)
+            .with_field_buffer_source(pool.clone())
+            .build()
+            .unwrap()
+        ;
+        let mut record = reader.read_record::<::record::TableRanges>().unwrap();
+        assert_eq!(0, pool.len());
+        assert_eq!("a", record.name);
+        let buffer = record.data.data.as_ptr();
+        let table = record.data.ranges.table().unwrap().clone();
+        reader.read_record_into(&mut record).unwrap();
+        assert_eq!("b", record.name);
+        assert_eq!(Some(&b"xy"[..]), record.data.get("other").map(|v| &v[..]));
+        assert_eq!(None, record.data.get("value"));
+        assert_eq!(buffer, record.data.data.as_ptr());
+        reader.read_record_into(&mut record).unwrap();
+        assert_eq!("a", record.name);
+        assert_eq!(Some(&b"3"[..]), record.data.get("value").map(|v| &v[..]));
+        assert_eq!(None, record.data.get("other"));
+        assert_eq!(buffer, record.data.data.as_ptr());
+        assert!(Arc::ptr_eq(&table, record.data.ranges.table().unwrap()));
+        let capacity = record.data.data.capacity();
+        assert_result!(Err(PositionalError { error: Error::UnexpectedEndOfFile(_), .. }), reader.read_record_into(&mut record));
+        assert_eq!(buffer, record.data.data.as_ptr());
+        assert_eq!(capacity, record.data.data.capacity());
+        assert_eq!(None, record.data.get("value"));
+        assert_result!(Err(PositionalError { error: Error::EndOfFile, .. }), reader.read_record_into(&mut record));
+        assert_eq!(buffer, record.data.data.as_ptr());
+        assert_eq!(capacity, record.data.data.capacity());
+    }
+}
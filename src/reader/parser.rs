@@ -1,8 +1,11 @@
-use spec::PaddingDirection;
+use spec::TrimDirection;
+use std::ops::Range;
 use std::fmt::{Display, Formatter, Error as FmtError};
-use data_type::{FieldReadSupport, BinarySupport};
+use data_type::{FieldReadSupport, BinarySupport, DisplayWidthSupport};
 use spec::FieldSpec;
-use super::super::BoxedErrorResult as Result;
+use error::BoxedError;
+
+type Result<T> = ::std::result::Result<T, ParseError>;
 
 pub trait FieldParser<T: FieldReadSupport> {
     fn parse<'a>(&self, data: &[u8], field_spec: &'a FieldSpec, destination: &'a mut Vec<u8>, read_support: &'a T) -> Result<()>;
@@ -17,14 +20,23 @@ impl<'a, T, U: FieldReadSupport> FieldParser<U> for &'a T where T: FieldParser<U
 #[derive(Debug)]
 pub enum ParseError {
     DataSplitNotOnCharBoundary(usize),
-    PaddingSplitNotOnCharBoundary(usize)
+    PaddingSplitNotOnCharBoundary(usize),
+    Other(BoxedError)
 }
 
 impl ::std::error::Error for ParseError {
     fn description(&self) -> &str {
         match *self {
             ParseError::DataSplitNotOnCharBoundary(_) => "The index needed for splitting the data is not on a char boundary",
-            ParseError::PaddingSplitNotOnCharBoundary(_) => "The index needed for splitting the padding is not on a char boundary"
+            ParseError::PaddingSplitNotOnCharBoundary(_) => "The index needed for splitting the padding is not on a char boundary",
+            ParseError::Other(_) => "The field parser encountered an error"
+        }
+    }
+
+    fn cause(&self) -> Option<&::std::error::Error> {
+        match *self {
+            ParseError::Other(ref e) => Some(&**e),
+            _ => None
         }
     }
 }
@@ -41,35 +53,72 @@ impl Display for ParseError {
                 f,
                 "The index {} needed for splitting the padding is not on a char boundary",
                 index
-            )
+            ),
+            ParseError::Other(ref e) => write!(f, "{}", e)
         }
     }
 }
 
+impl From<BoxedError> for ParseError {
+    fn from(error: BoxedError) -> Self {
+        ParseError::Other(error)
+    }
+}
+
 pub struct DefaultParser;
 
 impl FieldParser<BinarySupport> for DefaultParser {
     fn parse<'a>(&self, data: &'a [u8], field_spec: &'a FieldSpec, destination: &'a mut Vec<u8>, _: &'a BinarySupport) -> Result<()> {
-        let mut index = 0;
-        let mut iter = data.chunks(field_spec.padding.len());
-
-        while let Some(chunk) = match field_spec.padding_direction {
-            PaddingDirection::Left => iter.next(),
-            PaddingDirection::Right => iter.next_back(),
-        } {
-            if chunk != &field_spec.padding[..] {
-                break;
+        trim(data, field_spec, destination)
+    }
+}
+
+impl FieldParser<DisplayWidthSupport> for DefaultParser {
+    fn parse<'a>(&self, data: &'a [u8], field_spec: &'a FieldSpec, destination: &'a mut Vec<u8>, _: &'a DisplayWidthSupport) -> Result<()> {
+        trim(data, field_spec, destination)
+    }
+}
+
+fn trim<'a>(data: &'a [u8], field_spec: &'a FieldSpec, destination: &'a mut Vec<u8>) -> Result<()> {
+    destination.extend_from_slice(&data[trim_range(data, field_spec)]);
+    Ok(())
+}
+
+pub fn trim_range<'a>(data: &'a [u8], field_spec: &'a FieldSpec) -> Range<usize> {
+    let (left, right) = match field_spec.trim_direction {
+        TrimDirection::Left => (true, false),
+        TrimDirection::Right => (false, true),
+        TrimDirection::Both => (true, true),
+        TrimDirection::None => (false, false)
+    };
+    let mut start = 0;
+    let mut end = data.len();
+
+    match field_spec.trim_set {
+        Some(ref trim_set) => {
+            while left && start < end && trim_set.contains(&data[start]) {
+                start += 1;
             }
 
-            index += chunk.len();
-        }
+            while right && end > start && trim_set.contains(&data[end - 1]) {
+                end -= 1;
+            }
+        },
+        None if !field_spec.padding.is_empty() => {
+            let padding = &field_spec.padding[..];
 
-        destination.extend_from_slice(match field_spec.padding_direction {
-            PaddingDirection::Left => &data[index..],
-            PaddingDirection::Right => &data[..data.len() - index],
-        });
-        Ok(())
+            while left && data[start..end].starts_with(padding) {
+                start += padding.len();
+            }
+
+            while right && data[start..end].ends_with(padding) {
+                end -= padding.len();
+            }
+        },
+        None => ()
     }
+
+    start..end
 }
 
 pub struct IdentityParser;
@@ -116,6 +165,45 @@ mod test {
         assert_eq!("qwer".as_bytes().to_owned(), destination);
     }
 
+    #[test]
+    fn default_parser_trimming() {
+        let padder = DefaultParser;
+        let mut destination = Vec::new();
+        let data_type = BinarySupport;
+        let data = " \0qw er\0 ".as_bytes();
+        assert_result!(Ok(()), padder.parse(data, &FieldSpecBuilder::new().string().with_length(8).build().unwrap(), &mut destination, &data_type));
+        assert_eq!(" \0qw er\0".as_bytes().to_owned(), destination);
+        destination.clear();
+        let field_spec_builder = FieldSpecBuilder::new().string().with_trim_set(" \0").with_length(8);
+        assert_result!(Ok(()), padder.parse(data, &field_spec_builder.clone().build().unwrap(), &mut destination, &data_type));
+        assert_eq!(" \0qw er".as_bytes().to_owned(), destination);
+        destination.clear();
+        assert_result!(Ok(()), padder.parse(data, &field_spec_builder.clone().with_trim_direction(TrimDirection::Both).build().unwrap(), &mut destination, &data_type));
+        assert_eq!("qw er".as_bytes().to_owned(), destination);
+        destination.clear();
+        assert_result!(Ok(()), padder.parse(data, &field_spec_builder.clone().with_trim_direction(TrimDirection::None).build().unwrap(), &mut destination, &data_type));
+        assert_eq!(data.to_owned(), destination);
+        destination.clear();
+        assert_result!(Ok(()), padder.parse("  qw er   ".as_bytes(), &FieldSpecBuilder::new().centered_string().with_length(10).build().unwrap(), &mut destination, &data_type));
+        assert_eq!("qw er".as_bytes().to_owned(), destination);
+        destination.clear();
+        assert_result!(Ok(()), padder.parse(" 00120".as_bytes(), &FieldSpecBuilder::new().number().with_length(6).build().unwrap(), &mut destination, &data_type));
+        assert_eq!(" 00120".as_bytes().to_owned(), destination);
+        destination.clear();
+        assert_result!(Ok(()), padder.parse(" 00120".as_bytes(), &FieldSpecBuilder::new().number().with_trim_set("0 ").with_length(6).build().unwrap(), &mut destination, &data_type));
+        assert_eq!("120".as_bytes().to_owned(), destination);
+        destination.clear();
+        let field_spec = FieldSpecBuilder::new()
+            .with_padding("ab")
+            .with_padding_direction(PaddingDirection::Center)
+            .with_length(9)
+            .build()
+            .unwrap()
+        ;
+        assert_result!(Ok(()), padder.parse("ababqwaab".as_bytes(), &field_spec, &mut destination, &data_type));
+        assert_eq!("qwa".as_bytes().to_owned(), destination);
+    }
+
     #[test]
     fn identity_parser() {
         let padder = IdentityParser;
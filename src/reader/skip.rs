@@ -0,0 +1,284 @@
+extern crate regex;
+
+use std::io::BufRead;
+use super::super::{Error, Result};
+pub use self::regex::bytes::Regex;
+
+const FORM_FEED: u8 = 0x0C;
+
+#[derive(Debug, Clone)]
+pub enum SkipRule {
+    FirstLines(usize),
+    Prefix(Vec<u8>),
+    Pattern(Regex),
+    BlankLines,
+    FormFeedHeader(usize),
+    Page { length: usize, header: usize, footer: usize }
+}
+
+impl SkipRule {
+    fn matches<'a>(&self, line: &'a [u8], skipper: &'a Skipper) -> bool {
+        let page_line = skipper.line - skipper.page_start;
+
+        match *self {
+            SkipRule::FirstLines(lines) => skipper.line < lines,
+            SkipRule::Prefix(ref prefix) => line.starts_with(&prefix[..]),
+            SkipRule::Pattern(ref regex) => regex.is_match(line),
+            SkipRule::BlankLines => line.iter().all(u8::is_ascii_whitespace),
+            SkipRule::FormFeedHeader(lines) => skipper.form_feed_seen && page_line < lines,
+            SkipRule::Page { length, header, footer } => length > 0 && (page_line % length < header || page_line % length >= length.saturating_sub(footer))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Skipper {
+    pub rules: Vec<SkipRule>,
+    pub line_ending: Vec<u8>,
+    pub line: usize,
+    page_start: usize,
+    form_feed_seen: bool
+}
+
+impl Skipper {
+    pub fn new(rules: Vec<SkipRule>) -> Self {
+        Skipper {
+            rules: rules,
+            line_ending: b"\n".to_vec(),
+            line: 0,
+            page_start: 0,
+            form_feed_seen: false
+        }
+    }
+
+    pub fn with_line_ending<T: Into<Vec<u8>>>(mut self, line_ending: T) -> Self {
+        self.line_ending = line_ending.into();
+        self
+    }
+
+    // Rules are matched against the buffered part of the line, a line longer than the buffer is
+    // matched on its first chunk but is always consumed up to and including its line ending. The
+    // exception is a line only the blank line rule matches, it's walked to its end first and errors
+    // if data follows the whitespace that filled the buffer.
+    pub fn skip<'a, T: BufRead + 'a>(&mut self, reader: &'a mut T) -> Result<usize> {
+        let mut skipped = 0;
+
+        while !self.rules.is_empty() {
+            let blank_only = {
+                let buffer = reader.fill_buf()?;
+
+                if buffer.is_empty() {
+                    break;
+                }
+
+                let found = find(buffer, &self.line_ending[..]);
+                let line = found.map_or(buffer, |index| &buffer[..index]);
+
+                if line.first() == Some(&FORM_FEED) {
+                    self.page_start = self.line;
+                    self.form_feed_seen = true;
+                }
+
+                let mut matching = self.rules.iter().filter(|rule| rule.matches(line, self));
+                let first = match matching.next() {
+                    Some(rule) => rule,
+                    None => break
+                };
+
+                found.is_none() && match *first {
+                    SkipRule::BlankLines => matching.next().is_none(),
+                    _ => false
+                }
+            };
+
+            if blank_only {
+                consume_blank_line(reader, &self.line_ending[..])?;
+            } else {
+                consume_line(reader, &self.line_ending[..])?;
+            }
+            self.line += 1;
+            skipped += 1;
+        }
+
+        Ok(skipped)
+    }
+
+    pub fn advance(&mut self, lines: usize) {
+        self.line += lines;
+    }
+}
+
+fn find(buffer: &[u8], line_ending: &[u8]) -> Option<usize> {
+    if line_ending.is_empty() {
+        return None;
+    }
+
+    buffer.windows(line_ending.len()).position(|window| window == line_ending)
+}
+
+fn consume_line<T: BufRead>(reader: &mut T, line_ending: &[u8]) -> Result<()> {
+    let mut matched = 0;
+
+    loop {
+        let (length, found) = {
+            let buffer = reader.fill_buf()?;
+
+            if buffer.is_empty() {
+                return Ok(());
+            }
+
+            let mut end = None;
+
+            for (index, byte) in buffer.iter().enumerate() {
+                matched = if line_ending.get(matched) == Some(byte) {
+                    matched + 1
+                } else if line_ending.first() == Some(byte) {
+                    1
+                } else {
+                    0
+                };
+
+                if matched > 0 && matched == line_ending.len() {
+                    end = Some(index + 1);
+                    break;
+                }
+            }
+
+            end.map_or((buffer.len(), false), |end| (end, true))
+        };
+
+        reader.consume(length);
+
+        if found {
+            return Ok(());
+        }
+    }
+}
+
+fn consume_blank_line<T: BufRead>(reader: &mut T, line_ending: &[u8]) -> Result<()> {
+    let mut matched = 0;
+    let mut consumed = 0;
+
+    loop {
+        let (length, found, blank) = {
+            let buffer = reader.fill_buf()?;
+
+            if buffer.is_empty() {
+                return Ok(());
+            }
+
+            let mut end = None;
+            let mut blank = true;
+
+            for (index, byte) in buffer.iter().enumerate() {
+                matched = if line_ending.get(matched) == Some(byte) {
+                    matched + 1
+                } else if line_ending.first() == Some(byte) {
+                    1
+                } else {
+                    0
+                };
+
+                if matched > 0 && matched == line_ending.len() {
+                    end = Some(index + 1);
+                    break;
+                }
+
+                if matched == 0 && !byte.is_ascii_whitespace() {
+                    end = Some(index);
+                    blank = false;
+                    break;
+                }
+            }
+
+            end.map_or((buffer.len(), false, true), |end| (end, blank, blank))
+        };
+
+        reader.consume(length);
+        consumed += length;
+
+        if !blank {
+            return Err(Error::BlankLineExceedsBuffer(consumed));
+        }
+
+        if found {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::{Cursor, BufRead, BufReader};
+
+    fn rest<T: BufRead>(reader: &mut T) -> String {
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        rest
+    }
+
+    #[test]
+    fn skip_lines() {
+        let mut skipper = Skipper::new(vec![
+            SkipRule::FirstLines(2),
+            SkipRule::Prefix(b"#".to_vec()),
+            SkipRule::BlankLines,
+            SkipRule::Pattern(Regex::new("^-+$").unwrap())
+        ]);
+        let mut buf = Cursor::new("REPORT\n# comment\n# more\n\r\n  \n-----\nrecord\n#after\n".as_bytes());
+        assert_result!(Ok(6), skipper.skip(&mut buf));
+        assert_eq!(6, skipper.line);
+        let mut line = String::new();
+        buf.read_line(&mut line).unwrap();
+        skipper.advance(1);
+        assert_eq!("record\n", line);
+        assert_result!(Ok(1), skipper.skip(&mut buf));
+        assert_eq!(8, skipper.line);
+        assert_eq!("", rest(&mut buf));
+        assert_result!(Ok(0), Skipper::new(vec![SkipRule::BlankLines]).skip(&mut Cursor::new("".as_bytes())));
+    }
+
+    #[test]
+    fn skip_long_lines() {
+        let mut skipper = Skipper::new(vec![SkipRule::Prefix(b"#".to_vec())]).with_line_ending("\r\n");
+        let mut buf = BufReader::with_capacity(4, Cursor::new("# a long comment\r\n#\nstill\r\nrecord\r\n".as_bytes()));
+        assert_result!(Ok(2), skipper.skip(&mut buf));
+        assert_eq!("record\r\n", rest(&mut buf));
+
+        let mut skipper = Skipper::new(vec![SkipRule::BlankLines]);
+        let mut buf = BufReader::with_capacity(2, Cursor::new("      \nrecord\n".as_bytes()));
+        assert_result!(Ok(1), skipper.skip(&mut buf));
+        assert_eq!("record\n", rest(&mut buf));
+
+        let mut skipper = Skipper::new(vec![SkipRule::BlankLines]);
+        let mut buf = BufReader::with_capacity(2, Cursor::new("    data\n".as_bytes()));
+        assert_result!(Err(Error::BlankLineExceedsBuffer(4)), skipper.skip(&mut buf));
+        assert_eq!("data\n", rest(&mut buf));
+        let mut buf = BufReader::with_capacity(4, Cursor::new("\u{a0}\n".as_bytes()));
+        assert_result!(Ok(0), skipper.skip(&mut buf));
+    }
+
+    #[test]
+    fn skip_pages() {
+        let mut skipper = Skipper::new(vec![SkipRule::FormFeedHeader(2)]);
+        let mut buf = Cursor::new("record1\n\x0cPAGE 2\nheader\nrecord2\n".as_bytes());
+        assert_result!(Ok(0), skipper.skip(&mut buf));
+        let mut line = String::new();
+        buf.read_line(&mut line).unwrap();
+        skipper.advance(1);
+        assert_result!(Ok(2), skipper.skip(&mut buf));
+        assert_eq!("record2\n", rest(&mut buf));
+
+        let mut skipper = Skipper::new(vec![SkipRule::Page { length: 4, header: 1, footer: 1 }]);
+        let mut buf = Cursor::new("head\nrecord1\nrecord2\nfoot\nhead\nrecord3\n".as_bytes());
+        assert_result!(Ok(1), skipper.skip(&mut buf));
+        buf.read_line(&mut String::new()).unwrap();
+        skipper.advance(1);
+        assert_result!(Ok(0), skipper.skip(&mut buf));
+        buf.read_line(&mut String::new()).unwrap();
+        skipper.advance(1);
+        assert_result!(Ok(2), skipper.skip(&mut buf));
+        assert_eq!("record3\n", rest(&mut buf));
+    }
+}
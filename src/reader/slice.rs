@@ -0,0 +1,412 @@
+use spec::{RecordSpec, FieldSpec, BitmapSpec};
+use spec::validation;
+use std::collections::{HashMap, BTreeSet};
+use std::borrow::Borrow;
+use std::ops::Range;
+use error::{Error, FieldError, PositionalError};
+use super::{PositionalResult, FieldResult, Record};
+use record::{Data, SliceData, BuildableDataRanges};
+use data_type::{FieldReadSupport, ShouldReadMore};
+use reader::parser::trim_range;
+use reader::spec::Stream as SpecSource;
+
+/// Reads records straight out of an in-memory slice, returning ranges into it instead of copying
+/// each field. Fields are trimmed the way `DefaultParser` trims them; there is no `FieldParser`,
+/// and skip rules, framing, headers and file rules are not supported, so use a `Reader` for those.
+pub struct SliceReader<'a, T: SpecSource<U> + 'a, U: FieldReadSupport, V: Borrow<HashMap<String, RecordSpec>> + 'a> {
+    source: &'a [u8],
+    position: usize,
+    line: usize,
+    read_support: U,
+    spec_source: T,
+    record_specs: V
+}
+
+impl<'a, T, U, V> SliceReader<'a, T, U, V>
+    where T: SpecSource<U> + 'a,
+          U: FieldReadSupport,
+          V: Borrow<HashMap<String, RecordSpec>> + 'a {
+    pub fn new(source: &'a [u8], read_support: U, spec_source: T, record_specs: V) -> Self {
+        SliceReader {
+            source: source,
+            position: 0,
+            line: 0,
+            read_support: read_support,
+            spec_source: spec_source,
+            record_specs: record_specs
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn read_record<X: BuildableDataRanges>(&mut self) -> PositionalResult<Record<X, SliceData<'a>>> {
+        let line = self.line + 1;
+
+        if self.position == self.source.len() {
+            return Err(PositionalError::from(Error::EndOfFile).with_line(line));
+        }
+
+        let (name, result, end, lines) = {
+            let record_specs = self.record_specs.borrow();
+            let name = self.spec_source.next(&mut &self.source[self.position..], record_specs, &self.read_support)
+                .map_err(|e| PositionalError::from(Error::SpecStreamError(e)).with_line(line))?
+                .ok_or_else(|| PositionalError::from(Error::SpecStreamReturnedNone).with_line(line))?
+            ;
+            let record_spec = record_specs.get(name)
+                .ok_or_else(|| PositionalError::from(Error::RecordSpecNotFound(name.to_string())).with_line(line))?
+            ;
+            let mut position = self.position;
+            let result = self.read_fields(record_spec, &mut position).and_then(|(ranges, nulls)| {
+                let data = Data { ranges: ranges, data: SliceData(&self.source[self.position..position]), nulls: nulls };
+                validation::check_record(&record_spec.rules, &data).map_err(FieldError::from)?;
+                Ok(data)
+            });
+
+            (name.to_string(), result, position, record_spec.lines().filter(|&(_, line_ending)| !line_ending.is_empty()).count())
+        };
+
+        self.position = end;
+        self.line += lines;
+
+        let data = result.map_err(|e| PositionalError::from((e, &name[..])).with_line(line))?;
+
+        Ok(Record { data: data, name: name })
+    }
+
+    fn read_fields<X: BuildableDataRanges>(&self, spec: &RecordSpec, position: &mut usize) -> FieldResult<(X, BTreeSet<String>)> {
+        let start = *position;
+        let mut ranges = X::new();
+        let mut nulls = BTreeSet::new();
+
+        let mut lines = spec.lines().peekable();
+
+        while let Some((field_specs, line_ending)) = lines.next() {
+            let mut first = true;
+
+            for (name, field_spec) in field_specs {
+                if spec.bitmap.as_ref().map_or(false, |bitmap| bitmap.controls(name)) {
+                    continue;
+                }
+
+                if !first && spec.separator.len() != 0 {
+                    let separator = self.take(position, spec.separator.len());
+
+                    if separator != &spec.separator[..] {
+                        return Err((Error::DataDoesNotMatchSeparator(spec.separator.clone(), separator.to_owned()), name))?;
+                    }
+                }
+
+                first = false;
+
+                if let Some(ref bitmap) = spec.bitmap {
+                    if bitmap.field == *name {
+                        for bit in self.read_bitmap(name, field_spec, bitmap, start, position, &mut ranges)? {
+                            let name = bitmap.fields.get(&bit).ok_or_else(|| (Error::BitmapFieldNotDefined(bit), name))?;
+                            let field_spec = spec.field_spec(name).expect("bitmap fields are checked to exist when the spec is built");
+                            self.read_field(name, field_spec, start, position, &mut ranges, &mut nulls)?;
+                        }
+
+                        continue;
+                    }
+                }
+
+                match spec.redefines.get(name) {
+                    None => self.read_field(name, field_spec, start, position, &mut ranges, &mut nulls)?,
+                    Some(redefine) => {
+                        let value = ranges.get(&redefine.discriminator).map(|range| &self.source[start..][range]).unwrap_or(&[]);
+                        let alternative = redefine.alternative(value).ok_or_else(|| (Error::NoAlternativeForDiscriminator(value.to_owned()), name))?;
+
+                        for (name, field_spec) in alternative {
+                            self.read_field(name, field_spec, start, position, &mut ranges, &mut nulls)?;
+                        }
+                    }
+                }
+            }
+
+            let ending = self.take(position, line_ending.len());
+
+            // a record may only leave off its final line ending at the end of the source
+            if ending != line_ending && !(ending.is_empty() && lines.peek().is_none()) {
+                return Err(Error::DataDoesNotMatchLineEnding(line_ending.to_owned(), ending.to_owned()))?;
+            }
+        }
+
+        Ok((ranges, nulls))
+    }
+
+    fn read_field<'b, X>(&self, name: &'b str, field_spec: &'b FieldSpec, start: usize, position: &mut usize, ranges: &mut X, nulls: &mut BTreeSet<String>) -> FieldResult<()>
+        where X: BuildableDataRanges
+    {
+        let range = self.read_raw(field_spec, position).map_err(|e| (e, name))?;
+        let raw = &self.source[range.clone()];
+
+        if field_spec.null.as_ref().map_or(false, |null| null.matches(raw, field_spec)) {
+            nulls.insert(name.to_string());
+            return Ok(validation::validate(&field_spec.validations, None).map_err(|e| (e, name))?);
+        }
+
+        let trimmed = trim_range(raw, field_spec);
+        let value = &raw[trimmed.clone()];
+        ranges.insert(name, range.start - start + trimmed.start..range.start - start + trimmed.end);
+        validation::validate(&field_spec.validations, Some(value)).map_err(|e| (e, name))?;
+
+        if field_spec.constant {
+            let constant = field_spec.default.as_ref().expect("constant fields always have a default");
+
+            if value != &constant[trim_range(&constant[..], field_spec)] {
+                return Err((Error::ConstantMismatch(constant.clone(), value.to_vec()), name).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_raw(&self, field_spec: &FieldSpec, position: &mut usize) -> Result<Range<usize>, Error> {
+        let length = match field_spec.length_prefix {
+            None => field_spec.length,
+            Some(digits) => {
+                let prefix = self.take(position, digits);
+
+                if prefix.len() != digits {
+                    return Err(Error::CouldNotReadEnough(prefix.to_owned()));
+                }
+
+                let length = ::std::str::from_utf8(prefix).ok()
+                    .and_then(|prefix| prefix.parse::<usize>().ok())
+                    .ok_or_else(|| Error::InvalidLengthPrefix(prefix.to_owned()))?
+                ;
+
+                if length > field_spec.length {
+                    return Err(Error::InvalidLengthPrefix(prefix.to_owned()));
+                }
+
+                length
+            }
+        };
+        let start = *position;
+        let mut end = start;
+
+        while let ShouldReadMore::More(amount) = self.read_support.should_read_more(length, &self.source[start..end]) {
+            if end + amount > self.source.len() {
+                *position = self.source.len();
+                return Err(Error::CouldNotReadEnough(self.source[start..].to_owned()));
+            }
+
+            end += amount;
+        }
+
+        if self.read_support.get_length(&self.source[start..end]).length > length {
+            *position = end;
+            return Err(Error::CharacterCrossesFieldBoundary(self.source[start..end].to_owned()));
+        }
+
+        end += self.read_support.get_continuation_length(&self.source[end..]);
+        *position = end;
+        Ok(start..end)
+    }
+
+    fn read_bitmap<'b, X>(&self, name: &'b str, field_spec: &'b FieldSpec, bitmap: &'b BitmapSpec, start: usize, position: &mut usize, ranges: &mut X) -> FieldResult<Vec<usize>>
+        where X: BuildableDataRanges
+    {
+        let bitmap_start = *position;
+
+        if self.take(position, field_spec.length).len() != field_spec.length {
+            return Err((Error::CouldNotReadEnough(self.source[bitmap_start..].to_owned()), name))?;
+        }
+
+        if bitmap.has_secondary(&self.source[bitmap_start..*position]).ok_or_else(|| (Error::InvalidBitmap(self.source[bitmap_start..*position].to_owned()), name))? {
+            if self.take(position, field_spec.length).len() != field_spec.length {
+                return Err((Error::CouldNotReadEnough(self.source[bitmap_start..].to_owned()), name))?;
+            }
+        }
+
+        let data = &self.source[bitmap_start..*position];
+        let bits = bitmap.decode(data).ok_or_else(|| (Error::InvalidBitmap(data.to_owned()), name))?;
+        ranges.insert(name, bitmap_start - start..*position - start);
+
+        Ok(bits.into_iter().filter(|bit| *bit != 1).collect())
+    }
+
+    fn take(&self, position: &mut usize, length: usize) -> &'a [u8] {
+        let start = *position;
+        *position = ::std::cmp::min(start + length, self.source.len());
+        &self.source[start..*position]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use spec::{Builder, SpecBuilder, RecordSpecBuilder, FieldSpecBuilder, BitmapEncoding, NullValue};
+    use spec::stream::VecStream;
+    use spec::resolver::IdFieldResolver;
+    use reader::spec::ResolverSource;
+    use data_type::{BinarySupport, DisplayWidthSupport};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn read_record() {
+        let spec = SpecBuilder::new()
+            .with_record("a")
+                .with_line_ending("\n")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                    .with_default("A")
+                .end()
+                .with_field("amount")
+                    .number()
+                    .with_length(5)
+                .end()
+                .with_field("name")
+                    .string()
+                    .with_length(4)
+                    .with_null(NullValue::Blanks)
+                .end()
+            .end()
+            .with_record("b")
+                .with_line_ending("\n")
+                .with_separator(",")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                    .with_default("B")
+                .end()
+                .with_field("code")
+                    .string()
+                    .with_length(3)
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let source = b"A00012ab  \nB,xy \nA00003    \nB,12";
+        let mut reader = SliceReader::new(&source[..], BinarySupport, ResolverSource::new(IdFieldResolver::new()), &spec.record_specs);
+        let record = reader.read_record::<BTreeMap<_, _>>().unwrap();
+        assert_eq!("a", record.name);
+        assert_eq!(Some(&b"12"[..]), record.data.get_slice("amount"));
+        assert_eq!(Some(&b"ab"[..]), record.data.get_slice("name"));
+        assert_eq!(Some(4..6), record.data.ranges.get("amount").cloned());
+        assert_eq!(source[..11].as_ptr(), record.data.data.as_ptr());
+        let record = reader.read_record::<BTreeMap<_, _>>().unwrap();
+        assert_eq!("b", record.name);
+        assert_eq!(Some(&b"xy"[..]), record.data.get("code"));
+        let record = reader.read_record::<BTreeMap<_, _>>().unwrap();
+        assert!(record.data.is_null("name"));
+        assert_eq!(3, reader.line());
+        assert_result!(
+            Err(PositionalError { error: Error::CouldNotReadEnough(ref data), line: Some(4), .. }) if *data == b"12".to_vec(),
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+        assert_eq!(source.len(), reader.position());
+        assert_result!(Err(PositionalError { error: Error::EndOfFile, .. }), reader.read_record::<BTreeMap<_, _>>());
+    }
+
+    #[test]
+    fn read_record_with_constants() {
+        let spec = SpecBuilder::new()
+            .with_record("a")
+                .with_field("version")
+                    .number()
+                    .with_length(2)
+                    .with_default("02")
+                    .constant()
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut reader = SliceReader::new("0203".as_bytes(), BinarySupport, VecStream::from(vec!["a", "a"]), &spec.record_specs);
+        let record = reader.read_record::<BTreeMap<_, _>>().unwrap();
+        assert_eq!(Some(&b"2"[..]), record.data.get_slice("version"));
+        assert_result!(
+            Err(PositionalError { error: Error::ConstantMismatch(ref expected, ref actual), .. }) if *expected == b"02".to_vec() && *actual == b"3".to_vec(),
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+    }
+
+    #[test]
+    fn read_record_with_display_width() {
+        let spec = SpecBuilder::new()
+            .with_record("a")
+                .with_field("name")
+                    .string()
+                    .with_length(4)
+                .end()
+                .with_field("value")
+                    .string()
+                    .with_length(2)
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let source = "\u{65e5}a ok".as_bytes();
+        let mut reader = SliceReader::new(source, DisplayWidthSupport, VecStream::from(vec!["a"]), &spec.record_specs);
+        let record = reader.read_record::<BTreeMap<_, _>>().unwrap();
+        assert_eq!(Some("\u{65e5}a".as_bytes()), record.data.get_slice("name"));
+        assert_eq!(Some(&b"ok"[..]), record.data.get_slice("value"));
+        let source = "abc\u{65e5}ok".as_bytes();
+        let mut reader = SliceReader::new(source, DisplayWidthSupport, VecStream::from(vec!["a"]), &spec.record_specs);
+        assert_result!(
+            Err(PositionalError { error: Error::CharacterCrossesFieldBoundary(_), .. }),
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+    }
+
+    #[test]
+    fn read_record_with_missing_line_endings() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("field1", FieldSpecBuilder::new().string().with_length(3))
+            .with_segment()
+                .with_line_ending("\n")
+                .add_field("field2", FieldSpecBuilder::new().string().with_length(2))
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let record_specs = [("a".to_string(), record_spec)].iter().cloned().collect::<HashMap<_, _>>();
+        let mut reader = SliceReader::new("abc\nde".as_bytes(), BinarySupport, VecStream::from(vec!["a"]), &record_specs);
+        let record = reader.read_record::<BTreeMap<_, _>>().unwrap();
+        assert_eq!(Some(&b"de"[..]), record.data.get_slice("field2"));
+        let mut reader = SliceReader::new("abcde\n".as_bytes(), BinarySupport, VecStream::from(vec!["a"]), &record_specs);
+        assert_result!(
+            Err(PositionalError { error: Error::DataDoesNotMatchLineEnding(_, _), .. }),
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+        let mut reader = SliceReader::new("abc".as_bytes(), BinarySupport, VecStream::from(vec!["a"]), &record_specs);
+        assert_result!(
+            Err(PositionalError { error: Error::DataDoesNotMatchLineEnding(_, _), .. }),
+            reader.read_record::<BTreeMap<_, _>>()
+        );
+    }
+
+    #[test]
+    fn read_record_with_secondary_bitmap() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("bitmap", FieldSpecBuilder::new().string().with_length(2))
+            .add_field("field1", FieldSpecBuilder::new().string().with_length(2))
+            .add_field("field3", FieldSpecBuilder::new().string().with_length(3))
+            .with_bitmap("bitmap", BitmapEncoding::Hex)
+                .add_field(2, "field1")
+                .add_field(12, "field3")
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let record_specs = [("a".to_string(), record_spec)].iter().cloned().collect::<HashMap<_, _>>();
+        let mut reader = SliceReader::new("C010ab123\n".as_bytes(), BinarySupport, VecStream::from(vec!["a"]), &record_specs);
+        let record = reader.read_record::<BTreeMap<_, _>>().unwrap();
+        assert_eq!(Some(&b"C010"[..]), record.data.get_slice("bitmap"));
+        assert_eq!(Some(&b"ab"[..]), record.data.get_slice("field1"));
+        assert_eq!(Some(&b"123"[..]), record.data.get_slice("field3"));
+    }
+}
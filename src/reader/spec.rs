@@ -2,15 +2,21 @@ use spec::RecordSpec;
 use std::collections::{HashMap};
 use std::io::BufRead;
 use data_type::FieldReadSupport;
-use super::super::BoxedErrorResult as Result;
 use spec::resolver::{IdFieldResolver};
-use spec::stream::{VecStream};
+use spec::compiled::IdIndex;
+use spec::stream::{VecStream, StreamError};
 use std::borrow::Borrow;
 
+type Result<T> = ::std::result::Result<T, StreamError>;
+
 pub trait RequiresBufRead<T: FieldReadSupport> {
     fn get_suggested_buffer_size<'a>(&self, _: &'a HashMap<String, RecordSpec>, _: &'a T) -> Option<usize> {
         None
     }
+
+    fn check_record_specs<'a>(&self, _: &'a HashMap<String, RecordSpec>) -> Result<()> {
+        Ok(())
+    }
 }
 
 pub trait Stream<T: FieldReadSupport>: RequiresBufRead<T> {
@@ -21,12 +27,20 @@ impl<'b, T: RequiresBufRead<U> + 'b, U: FieldReadSupport> RequiresBufRead<U> for
     fn get_suggested_buffer_size<'a>(&self, record_specs: &'a HashMap<String, RecordSpec>, read_support: &'a U) -> Option<usize> {
         RequiresBufRead::get_suggested_buffer_size(*self, record_specs, read_support)
     }
+
+    fn check_record_specs<'a>(&self, record_specs: &'a HashMap<String, RecordSpec>) -> Result<()> {
+        RequiresBufRead::check_record_specs(*self, record_specs)
+    }
 }
 
 impl<'b, T: RequiresBufRead<U> + 'b, U: FieldReadSupport> RequiresBufRead<U> for &'b mut T {
     fn get_suggested_buffer_size<'a>(&self, record_specs: &'a HashMap<String, RecordSpec>, read_support: &'a U) -> Option<usize> {
         RequiresBufRead::get_suggested_buffer_size(*self, record_specs, read_support)
     }
+
+    fn check_record_specs<'a>(&self, record_specs: &'a HashMap<String, RecordSpec>) -> Result<()> {
+        RequiresBufRead::check_record_specs(*self, record_specs)
+    }
 }
 
 impl<'c, T: Stream<U> + 'c, U: FieldReadSupport> Stream<U> for &'c mut T {
@@ -39,6 +53,12 @@ pub trait Resolver<T: FieldReadSupport>: RequiresBufRead<T> {
     fn resolve<'a, 'b, U: BufRead + 'a>(&self, reader: &'a mut U, record_specs: &'b HashMap<String, RecordSpec>, read_support: &'a T) -> Result<Option<&'b str>>;
 }
 
+impl<'c, T: Resolver<U> + 'c, U: FieldReadSupport> Resolver<U> for &'c T {
+    fn resolve<'a, 'b, V: BufRead + 'a>(&self, reader: &'a mut V, record_specs: &'b HashMap<String, RecordSpec>, read_support: &'a U) -> Result<Option<&'b str>> {
+        Resolver::resolve(*self, reader, record_specs, read_support)
+    }
+}
+
 impl<'c, T: Resolver<U> + 'c, U: FieldReadSupport> Resolver<U> for &'c mut T {
     fn resolve<'a, 'b, V: BufRead + 'a>(&self, reader: &'a mut V, record_specs: &'b HashMap<String, RecordSpec>, read_support: &'a U) -> Result<Option<&'b str>> {
         Resolver::resolve(*self, reader, record_specs, read_support)
@@ -57,6 +77,10 @@ impl <'a, T, U> RequiresBufRead<U> for ResolverSource<'a, T, U>
     fn get_suggested_buffer_size<'b>(&self, record_specs: &'b HashMap<String, RecordSpec>, read_support: &'b U) -> Option<usize> {
         self.resolver.get_suggested_buffer_size(record_specs, read_support)
     }
+
+    fn check_record_specs<'b>(&self, record_specs: &'b HashMap<String, RecordSpec>) -> Result<()> {
+        self.resolver.check_record_specs(record_specs)
+    }
 }
 
 impl <'a, T, U> ResolverSource<'a, T, U>
@@ -89,37 +113,72 @@ impl <'a, T, U> Stream<U> for ResolverSource<'a, T, U>
 
 impl<T: FieldReadSupport, U: Borrow<str>> RequiresBufRead<T> for IdFieldResolver<U> {
     fn get_suggested_buffer_size<'a>(&self, record_specs: &'a HashMap<String, RecordSpec>, read_support: &'a T) -> Option<usize> {
-        let min = record_specs.iter().map(|(_, spec)| spec.field_range(self.id_field()).map(|range| range.end).unwrap_or(0)).min().unwrap_or(0);
+        let min = record_specs.iter().filter_map(|(_, spec)| spec.field_range(self.id_field()).map(|range| range.end)).min().unwrap_or(0);
         if min == 0 {
             None
         } else {
             read_support.get_size_hint(min).1
         }
     }
+
+    fn check_record_specs<'a>(&self, record_specs: &'a HashMap<String, RecordSpec>) -> Result<()> {
+        for (name, record_spec) in record_specs {
+            let located = record_spec.field_specs.get(self.id_field())
+                .map_or(true, |field_spec| field_spec.default.is_none() || record_spec.field_range(self.id_field()).is_some())
+            ;
+
+            if !located {
+                return Err(StreamError::IdFieldNotLocatable(name.clone(), self.id_field().to_string()));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl<T: FieldReadSupport, U: Borrow<str>> Resolver<T> for IdFieldResolver<U> {
     fn resolve<'a, 'b, V: BufRead + 'a>(&self, buffer: &'a mut V, record_specs: &'b HashMap<String, RecordSpec>, read_support: &'a T) -> Result<Option<&'b str>> {
+        let mut found: Option<&'b str> = None;
+
         for (name, record_spec) in record_specs.iter() {
             if let Some(ref field_spec) = record_spec.field_specs.get(self.id_field()) {
                 if let Some(ref default) = field_spec.default {
-                    if let Some(field_range) = read_support.get_byte_range(
-                        buffer.fill_buf()?,
-                        record_spec.field_range(self.id_field()).expect("This should never be None")
-                    ) {
+                    let range = record_spec.field_range(self.id_field())
+                        .ok_or_else(|| StreamError::IdFieldNotLocatable(name.clone(), self.id_field().to_string()))?
+                    ;
+
+                    if let Some(field_range) = read_support.get_byte_range(buffer.fill_buf()?, range) {
                         if buffer.fill_buf()?.len() < field_range.end {
                             continue;
                         }
 
                         if &buffer.fill_buf()?[field_range] == &default[..] {
-                            return Ok(Some(name));
+                            found = match found {
+                                None => Some(name),
+                                Some(other) => return Err(StreamError::ambiguous(other, name))
+                            };
                         }
                     }
                 }
             }
         }
 
-        Ok(None)
+        Ok(found)
+    }
+}
+
+impl<T: FieldReadSupport> RequiresBufRead<T> for IdIndex {
+    fn get_suggested_buffer_size<'a>(&self, _: &'a HashMap<String, RecordSpec>, read_support: &'a T) -> Option<usize> {
+        self.ranges().iter().map(|range| range.end).max().and_then(|max| read_support.get_size_hint(max).1)
+    }
+}
+
+impl<T: FieldReadSupport> Resolver<T> for IdIndex {
+    fn resolve<'a, 'b, V: BufRead + 'a>(&self, buffer: &'a mut V, record_specs: &'b HashMap<String, RecordSpec>, read_support: &'a T) -> Result<Option<&'b str>> {
+        let data = buffer.fill_buf()?;
+        let name = self.lookup(|range| read_support.get_byte_range(data, range).and_then(|range| data.get(range)))?;
+
+        Ok(name.and_then(|name| record_specs.get_key_value(&name[..])).map(|(name, _)| &name[..]))
     }
 }
 
@@ -1,13 +1,18 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::collections::btree_map::{Iter as BTreeMapIter, IntoIter as BTreeMapIntoIter};
 use std::collections::hash_map::{Iter as HashMapIter, IntoIter as HashMapIntoIter};
-use std::ops::{Range, Index};
+use std::ops::{Range, Index, Deref};
 use std::iter::FromIterator;
+use std::sync::Arc;
+use spec::{RecordSpec, GROUP_SEPARATOR};
+use spec::compiled::FieldTable;
+use spec::validation::RecordValues;
 
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct Data<T: DataRanges, U> {
     pub ranges: T,
-    pub data: U
+    pub data: U,
+    pub nulls: BTreeSet<String>
 }
 
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -16,6 +21,40 @@ pub struct Record<T: DataRanges, U> {
     pub name: String
 }
 
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Node<T> {
+    Value(T),
+    Group(BTreeMap<String, Node<T>>)
+}
+
+impl<T> Node<T> {
+    pub fn get<'a>(&self, path: &'a str) -> Option<&Node<T>> {
+        let children = match *self {
+            Node::Group(ref children) => children,
+            Node::Value(_) => return None
+        };
+
+        if let Some(node) = children.get(path) {
+            return Some(node);
+        }
+
+        for (index, _) in path.match_indices(GROUP_SEPARATOR) {
+            if let Some(node) = children.get(&path[..index]).and_then(|node| node.get(&path[index + 1..])) {
+                return Some(node);
+            }
+        }
+
+        None
+    }
+
+    pub fn value(&self) -> Option<&T> {
+        match *self {
+            Node::Value(ref value) => Some(value),
+            Node::Group(_) => None
+        }
+    }
+}
+
 pub trait DataRanges {
     fn get<'a>(&self, name: &'a str) -> Option<Range<usize>>;
 }
@@ -23,6 +62,14 @@ pub trait DataRanges {
 pub trait BuildableDataRanges: DataRanges {
     fn new() -> Self;
     fn insert<'a>(&mut self, name: &'a str, range: Range<usize>);
+
+    fn new_with_table(_: &Arc<FieldTable>) -> Self where Self: Sized {
+        Self::new()
+    }
+
+    fn reset(&mut self, table: Option<&Arc<FieldTable>>) where Self: Sized {
+        *self = table.map_or_else(Self::new, Self::new_with_table);
+    }
 }
 
 impl<'a, T> DataRanges for &'a T where T: 'a + DataRanges {
@@ -42,16 +89,67 @@ pub trait IntoIterableDataRanges: DataRanges {
 }
 
 impl<T: DataRanges, U: Index<Range<usize>>> Data<T, U> {
+    // only the full path of a field finds a value, groups have no data of their own so a group's
+    // path returns None, use to_tree to get at a group and its fields
     pub fn get<'a>(&self, name: &'a str) -> Option<&U::Output> {
         self.ranges.get(name).map(|range| &self.data[range])
     }
 }
 
+impl<T: DataRanges, U> Data<T, U> {
+    pub fn is_null<'a>(&self, name: &'a str) -> bool {
+        self.nulls.contains(name)
+    }
+}
+
+impl<T: DataRanges, U: AsRef<[u8]>> RecordValues for Data<T, U> {
+    fn get<'a>(&'a self, name: &'a str) -> Option<&'a [u8]> {
+        if self.is_null(name) {
+            return None;
+        }
+
+        self.ranges.get(name).and_then(|range| self.data.as_ref().get(range))
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct SliceData<'a>(pub &'a [u8]);
+
+impl<'a> Index<Range<usize>> for SliceData<'a> {
+    type Output = [u8];
+
+    fn index(&self, range: Range<usize>) -> &[u8] {
+        &self.0[range]
+    }
+}
+
+impl<'a> AsRef<[u8]> for SliceData<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.0
+    }
+}
+
+impl<'a> Deref for SliceData<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.0
+    }
+}
+
+impl<'a, T: DataRanges> Data<T, SliceData<'a>> {
+    pub fn get_slice<'b>(&self, name: &'b str) -> Option<&'a [u8]> {
+        let data = self.data.0;
+        self.ranges.get(name).map(|range| &data[range])
+    }
+}
+
 impl <T: BuildableDataRanges> Data<T, Vec<u8>> {
     pub fn new() -> Self {
         Data {
             ranges: T::new(),
-            data: Vec::new()
+            data: Vec::new(),
+            nulls: BTreeSet::new()
         }
     }
 
@@ -60,6 +158,10 @@ impl <T: BuildableDataRanges> Data<T, Vec<u8>> {
         self.ranges.insert(name, self.data.len()..self.data.len() + data.len());
         self.data.extend(data);
     }
+
+    pub fn push_null<'a>(&mut self, name: &'a str) {
+        self.nulls.insert(name.to_string());
+    }
 }
 
 pub struct Iter<'a, T: Iterator<Item=(&'a String, &'a Range<usize>)>, U: Index<Range<usize>> + 'a> {
@@ -81,6 +183,43 @@ impl<'a, T: IterableDataRanges<'a>, U: Index<Range<usize>> + 'a> Data<T, U> {
             data: &self.data
         }
     }
+
+    pub fn to_tree<'b>(&'a self, groups: &'b BTreeSet<String>) -> Node<&'a U::Output> {
+        let mut root = BTreeMap::new();
+
+        for (name, value) in self.iter() {
+            let mut node = &mut root;
+            let mut start = 0;
+
+            for (index, _) in name.match_indices(GROUP_SEPARATOR) {
+                if !groups.contains(&name[..index]) {
+                    continue;
+                }
+
+                let child = node.entry(name[start..index].to_string()).or_insert_with(|| Node::Group(BTreeMap::new()));
+
+                if let Node::Value(_) = *child {
+                    *child = Node::Group(BTreeMap::new());
+                }
+
+                node = match *child {
+                    Node::Group(ref mut children) => children,
+                    Node::Value(_) => unreachable!("values are replaced by groups above")
+                };
+                start = index + 1;
+            }
+
+            node.insert(name[start..].to_string(), Node::Value(value));
+        }
+
+        Node::Group(root)
+    }
+}
+
+impl<'a, T: IterableDataRanges<'a>, U: Index<Range<usize>> + 'a> Record<T, U> {
+    pub fn to_tree<'b>(&'a self, spec: &'b RecordSpec) -> Node<&'a U::Output> {
+        self.data.to_tree(&spec.groups)
+    }
 }
 
 pub struct IntoIter<T: Iterator<Item=(String, Range<usize>)>, U: ToOwned, V: Index<Range<usize>, Output=U>> {
@@ -120,7 +259,7 @@ impl<T: BuildableDataRanges> FromIterator<(String, Vec<u8>)> for Data<T, Vec<u8>
             current_index += field.len();
         }
 
-        Data { data: data, ranges: ranges }
+        Data { data: data, ranges: ranges, nulls: BTreeSet::new() }
     }
 }
 
@@ -136,7 +275,7 @@ impl<'a, T: BuildableDataRanges> FromIterator<(&'a str, Vec<u8>)> for Data<T, Ve
             current_index += field.len();
         }
 
-        Data { data: data, ranges: ranges }
+        Data { data: data, ranges: ranges, nulls: BTreeSet::new() }
     }
 }
 
@@ -152,7 +291,7 @@ impl<T: BuildableDataRanges> FromIterator<(String, String)> for Data<T, String>
             current_index += field.len();
         }
 
-        Data { data: data, ranges: ranges }
+        Data { data: data, ranges: ranges, nulls: BTreeSet::new() }
     }
 }
 
@@ -168,7 +307,7 @@ impl<'a, T: BuildableDataRanges> FromIterator<(&'a str, String)> for Data<T, Str
             current_index += field.len();
         }
 
-        Data { data: data, ranges: ranges }
+        Data { data: data, ranges: ranges, nulls: BTreeSet::new() }
     }
 }
 
@@ -216,6 +355,10 @@ impl BuildableDataRanges for HashMap<String, Range<usize>> {
     fn insert<'a>(&mut self, name: &'a str, range: Range<usize>) {
         self.insert(name.to_owned(), range);
     }
+
+    fn reset(&mut self, _: Option<&Arc<FieldTable>>) {
+        self.clear();
+    }
 }
 
 impl<'a> IterableDataRanges<'a> for HashMap<String, Range<usize>> {
@@ -232,6 +375,119 @@ impl IntoIterableDataRanges for HashMap<String, Range<usize>> {
     }
 }
 
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct TableRanges {
+    table: Option<Arc<FieldTable>>,
+    ranges: Vec<Option<Range<usize>>>
+}
+
+impl TableRanges {
+    pub fn table(&self) -> Option<&Arc<FieldTable>> {
+        self.table.as_ref()
+    }
+}
+
+impl DataRanges for TableRanges {
+    fn get<'a>(&self, name: &'a str) -> Option<Range<usize>> {
+        self.table.as_ref()
+            .and_then(|table| table.index(name))
+            .and_then(|index| self.ranges.get(index))
+            .and_then(|range| range.clone())
+    }
+}
+
+impl BuildableDataRanges for TableRanges {
+    fn new() -> Self {
+        TableRanges {
+            table: None,
+            ranges: Vec::new()
+        }
+    }
+
+    fn insert<'a>(&mut self, name: &'a str, range: Range<usize>) {
+        let table = self.table.get_or_insert_with(|| Arc::new(FieldTable::default()));
+        let index = match table.index(name) {
+            Some(index) => index,
+            None => Arc::make_mut(table).push(name, None)
+        };
+
+        if self.ranges.len() <= index {
+            self.ranges.resize(index + 1, None);
+        }
+
+        self.ranges[index] = Some(range);
+    }
+
+    fn new_with_table(table: &Arc<FieldTable>) -> Self {
+        TableRanges {
+            table: Some(table.clone()),
+            ranges: vec![None; table.len()]
+        }
+    }
+
+    fn reset(&mut self, table: Option<&Arc<FieldTable>>) {
+        let same = match (self.table.as_ref(), table) {
+            (Some(current), Some(table)) => Arc::ptr_eq(current, table),
+            (None, None) => true,
+            _ => false
+        };
+
+        if !same {
+            self.table = table.cloned();
+        }
+
+        for range in self.ranges.iter_mut() {
+            *range = None;
+        }
+    }
+}
+
+pub struct TableRangesIter<'a> {
+    table: Option<&'a FieldTable>,
+    ranges: ::std::iter::Enumerate<::std::slice::Iter<'a, Option<Range<usize>>>>
+}
+
+impl<'a> Iterator for TableRangesIter<'a> {
+    type Item = (&'a String, &'a Range<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let table = self.table?;
+
+        while let Some((index, range)) = self.ranges.next() {
+            if let Some(ref range) = *range {
+                return Some((table.name(index).expect("ranges never outgrow the table"), range));
+            }
+        }
+
+        None
+    }
+}
+
+impl<'a> IterableDataRanges<'a> for TableRanges {
+    type Iter = TableRangesIter<'a>;
+    fn range_iter(&'a self) -> TableRangesIter<'a> {
+        TableRangesIter {
+            table: self.table.as_ref().map(|table| &**table),
+            ranges: self.ranges.iter().enumerate()
+        }
+    }
+}
+
+impl IntoIterableDataRanges for TableRanges {
+    type Iter = ::std::vec::IntoIter<(String, Range<usize>)>;
+    fn into_range_iter(self) -> Self::Iter {
+        let table = self.table;
+        self.ranges.into_iter()
+            .enumerate()
+            .filter_map(|(index, range)| range.and_then(|range| table.as_ref()
+                .and_then(|table| table.name(index))
+                .map(|name| (name.clone(), range))
+            ))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
 impl DataRanges for () {
     fn get<'a>(&self, _: &'a str) -> Option<Range<usize>> {
         None
@@ -0,0 +1,262 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+use spec::{Spec, RecordSpec};
+use spec::validation::FileRule;
+use spec::stream::StreamError;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FieldTable {
+    names: Vec<String>,
+    indexes: HashMap<String, usize>,
+    ranges: Vec<Option<Range<usize>>>
+}
+
+impl FieldTable {
+    pub fn new(record_spec: &RecordSpec) -> Self {
+        let mut table = FieldTable {
+            names: Vec::new(),
+            indexes: HashMap::new(),
+            ranges: Vec::new()
+        };
+
+        for (field_specs, _) in record_spec.lines() {
+            for (name, _) in field_specs {
+                table.push(name, record_spec.field_range(name));
+            }
+        }
+
+        for redefine in record_spec.redefines.values() {
+            for name in redefine.alternatives.values().flat_map(|alternative| alternative.keys()) {
+                table.push(name, None);
+            }
+        }
+
+        table
+    }
+
+    pub fn push<'a>(&mut self, name: &'a str, range: Option<Range<usize>>) -> usize {
+        if let Some(index) = self.index(name) {
+            return index;
+        }
+
+        self.names.push(name.to_string());
+        self.indexes.insert(name.to_string(), self.names.len() - 1);
+        self.ranges.push(range);
+        self.names.len() - 1
+    }
+
+    pub fn index<'a>(&self, name: &'a str) -> Option<usize> {
+        self.indexes.get(name).cloned()
+    }
+
+    pub fn name(&self, index: usize) -> Option<&String> {
+        self.names.get(index)
+    }
+
+    pub fn field_range<'a>(&self, name: &'a str) -> Option<Range<usize>> {
+        self.index(name).and_then(|index| self.ranges[index].clone())
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IdIndex {
+    id_field: String,
+    groups: Vec<(Range<usize>, HashMap<Vec<u8>, Vec<String>>)>
+}
+
+impl IdIndex {
+    pub fn new<'a, T: Into<String>>(id_field: T, record_specs: &'a HashMap<String, RecordSpec>) -> Self {
+        let id_field = id_field.into();
+        let mut groups: Vec<(Range<usize>, HashMap<Vec<u8>, Vec<String>>)> = Vec::new();
+
+        for (name, record_spec) in record_specs {
+            let default = match record_spec.field_specs.get(&id_field).and_then(|field_spec| field_spec.default.as_ref()) {
+                Some(default) => default,
+                None => continue
+            };
+            let range = match record_spec.field_range(&id_field) {
+                Some(range) => range,
+                None => continue
+            };
+            let position = match groups.iter().position(|&(ref group_range, _)| *group_range == range) {
+                Some(position) => position,
+                None => {
+                    groups.push((range, HashMap::new()));
+                    groups.len() - 1
+                }
+            };
+            let names = groups[position].1.entry(default.clone()).or_insert_with(Vec::new);
+            names.push(name.clone());
+            names.sort();
+        }
+
+        IdIndex {
+            id_field: id_field,
+            groups: groups
+        }
+    }
+
+    pub fn id_field(&self) -> &str {
+        &self.id_field
+    }
+
+    pub fn ranges(&self) -> Vec<Range<usize>> {
+        self.groups.iter().map(|&(ref range, _)| range.clone()).collect()
+    }
+
+    pub fn lookup<'a, F>(&self, mut id: F) -> Result<Option<&String>, StreamError>
+        where F: FnMut(Range<usize>) -> Option<&'a [u8]>
+    {
+        let mut found: Option<&String> = None;
+
+        for &(ref range, ref ids) in &self.groups {
+            let names = match id(range.clone()).and_then(|id| ids.get(id)) {
+                Some(names) => names,
+                None => continue
+            };
+
+            if names.len() > 1 {
+                return Err(StreamError::AmbiguousRecord(names.clone()));
+            }
+
+            found = match found {
+                None => names.first(),
+                Some(other) => return Err(StreamError::ambiguous(other, &names[0]))
+            };
+        }
+
+        Ok(found)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompiledSpec {
+    pub record_specs: HashMap<String, RecordSpec>,
+    pub rules: Vec<FileRule>,
+    tables: HashMap<String, Arc<FieldTable>>,
+    id_index: Option<IdIndex>
+}
+
+impl CompiledSpec {
+    pub fn new(spec: Spec) -> Self {
+        CompiledSpec {
+            tables: compile_tables(&spec.record_specs),
+            record_specs: spec.record_specs,
+            rules: spec.rules,
+            id_index: None
+        }
+    }
+
+    pub fn with_id_field<T: Into<String>>(mut self, id_field: T) -> Self {
+        self.id_index = Some(IdIndex::new(id_field, &self.record_specs));
+        self
+    }
+
+    pub fn table<'a>(&self, name: &'a str) -> Option<&Arc<FieldTable>> {
+        self.tables.get(name)
+    }
+
+    pub fn tables(&self) -> &HashMap<String, Arc<FieldTable>> {
+        &self.tables
+    }
+
+    pub fn field_range<'a>(&self, record: &'a str, field: &'a str) -> Option<Range<usize>> {
+        self.table(record).and_then(|table| table.field_range(field))
+    }
+
+    pub fn id_index(&self) -> Option<&IdIndex> {
+        self.id_index.as_ref()
+    }
+}
+
+impl From<Spec> for CompiledSpec {
+    fn from(spec: Spec) -> Self {
+        CompiledSpec::new(spec)
+    }
+}
+
+impl Borrow<HashMap<String, RecordSpec>> for CompiledSpec {
+    fn borrow(&self) -> &HashMap<String, RecordSpec> {
+        &self.record_specs
+    }
+}
+
+impl<'a> Borrow<HashMap<String, RecordSpec>> for &'a CompiledSpec {
+    fn borrow(&self) -> &HashMap<String, RecordSpec> {
+        &self.record_specs
+    }
+}
+
+pub fn compile_tables<'a>(record_specs: &'a HashMap<String, RecordSpec>) -> HashMap<String, Arc<FieldTable>> {
+    record_specs.iter()
+        .map(|(name, record_spec)| (name.clone(), Arc::new(FieldTable::new(record_spec))))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use spec::{Builder, SpecBuilder};
+
+    #[test]
+    fn compiled_spec() {
+        let spec = SpecBuilder::new()
+            .with_record("a")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                    .with_default("A")
+                .end()
+                .with_field("value")
+                    .string()
+                    .with_length(2)
+                .end()
+            .end()
+            .with_record("b")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                    .with_default("B")
+                .end()
+                .with_field("other")
+                    .string()
+                    .with_length(3)
+                .end()
+            .end()
+            .with_record("c")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                    .with_default("B")
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let compiled = CompiledSpec::new(spec.clone()).with_id_field("$id");
+        assert_eq!(Some(1..3), compiled.field_range("a", "value"));
+        assert_eq!(None, compiled.field_range("a", "other"));
+        assert_eq!(spec.record_specs["b"].field_range("other"), compiled.field_range("b", "other"));
+        let table = compiled.table("a").unwrap();
+        assert_eq!(2, table.len());
+        assert_eq!(Some(1), table.index("value"));
+        assert_eq!(Some(&"value".to_string()), table.name(1));
+        let index = compiled.id_index().unwrap();
+        assert_eq!(vec![0..1], index.ranges());
+        assert_eq!(Some(&"a".to_string()), index.lookup(|_| Some(&b"A"[..])).unwrap());
+        assert_result!(Ok(None), index.lookup(|_| Some(&b"Z"[..])));
+        assert_result!(Ok(None), index.lookup(|_| None));
+        assert_result!(
+            Err(StreamError::AmbiguousRecord(ref names)) if *names == vec!["b".to_string(), "c".to_string()],
+            index.lookup(|_| Some(&b"B"[..]))
+        );
+        let record_specs: &HashMap<String, RecordSpec> = compiled.borrow();
+        assert_eq!(spec.record_specs, *record_specs);
+    }
+}
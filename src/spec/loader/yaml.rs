@@ -2,7 +2,8 @@ extern crate yaml_rust;
 use self::yaml_rust::{Yaml};
 use std::io::prelude::*;
 use std::collections::BTreeMap;
-use spec::{Builder, FieldSpec, FieldSpecBuilder, RecordSpec, RecordSpecBuilder, Spec, SpecBuilder, PaddingDirection};
+use spec::validation::{CharacterClass, FileRule, RecordRule, Regex, ValidationRule};
+use spec::{Builder, BitmapEncoding, BitmapSpec, BitmapSpecBuilder, FieldSpec, FieldSpecBuilder, GroupSpecBuilder, NullValue, OverflowPolicy, RecordSpec, RecordSpecBuilder, RedefineSpec, RedefineSpecBuilder, SegmentSpec, SegmentSpecBuilder, Spec, SpecBuilder, PaddingDirection, TrimDirection};
 use super::BoxedErrorResult;
 use std::fmt::{Display, Formatter, Error as FmtError};
 
@@ -23,8 +24,9 @@ impl<'a, T: 'a + Read> super::Loader<&'a mut T> for YamlLoader {
 impl YamlLoader {
     fn read_spec(doc: Yaml) -> BoxedErrorResult<Spec> {
         let mut builder = SpecBuilder::new();
+        let mut doc = Self::get_hash(doc, None)?;
 
-        let records = Self::get_hash(Self::get_hash(doc, None)?
+        let records = Self::get_hash(doc
              .remove(&Yaml::String("records".to_string()))
              .ok_or(Error::missing_key("records", None))?, Some(&["records"]))?
         ;
@@ -36,6 +38,14 @@ impl YamlLoader {
             builder = builder.add_record(name, record_spec);
         }
 
+        if let Some(rules) = doc.remove(&Yaml::String("rules".to_string())) {
+            let path = &["rules"];
+
+            for rule_data in Self::get_array(rules, Some(path))? {
+                builder = builder.add_rule(Self::get_file_rule(rule_data, Some(path))?);
+            }
+        }
+
         Ok(builder.build().map_err(Error::BuilderError)?)
     }
 
@@ -69,6 +79,34 @@ impl YamlLoader {
             Some(v) => builder.with_default(Self::get_bytes(v, Some(path))?),
             _ => builder
         };
+        let builder = match field_spec_map.remove(&Yaml::String("length_prefix".to_string())) {
+            Some(v) => builder.with_length_prefix(Self::get_usize(v, Some(path))?),
+            _ => builder
+        };
+        let builder = match field_spec_map.remove(&Yaml::String("trim".to_string())) {
+            Some(v) => builder.with_trim_direction(Self::get_trim_direction(v, Some(path))?),
+            _ => builder
+        };
+        let builder = match field_spec_map.remove(&Yaml::String("trim_set".to_string())) {
+            Some(v) => builder.with_trim_set(Self::get_bytes(v, Some(path))?),
+            _ => builder
+        };
+        let builder = match field_spec_map.remove(&Yaml::String("overflow".to_string())) {
+            Some(v) => builder.with_overflow(Self::get_overflow_policy(v, Some(path))?),
+            _ => builder
+        };
+        let builder = match field_spec_map.remove(&Yaml::String("constant".to_string())) {
+            Some(v) => if Self::get_bool(v, Some(path))? { builder.constant() } else { builder },
+            _ => builder
+        };
+        let builder = match field_spec_map.remove(&Yaml::String("validations".to_string())) {
+            Some(v) => Self::get_validation_rules(v, Some(path))?.into_iter().fold(builder, |builder, rule| builder.with_validation(rule)),
+            _ => builder
+        };
+        let builder = match field_spec_map.remove(&Yaml::String("null_value".to_string())) {
+            Some(v) => builder.with_null(Self::get_null_value(v, Some(path))?),
+            _ => builder
+        };
 
         Ok(builder.build().map_err(Error::BuilderError)?)
     }
@@ -80,6 +118,88 @@ impl YamlLoader {
         let path = &["records", &name, "fields"];
         let fields = Self::get_hash(record_spec_data.remove(&Yaml::String("fields".to_string())).ok_or(Error::missing_key("records", Some(path)))?, Some(path))?;
 
+        for (field_name, field_spec_data) in fields {
+            let field_name = Self::get_string(field_name, Some(path))?;
+
+            if Self::is_group(&field_spec_data) {
+                let group = Self::get_group_spec(field_spec_data, &name, &field_name)?;
+                builder = builder.add_group(field_name, group);
+            } else {
+                let field_spec = Self::get_field_spec(field_spec_data, &name, &field_name)?;
+                builder = builder.add_field(field_name, field_spec);
+            }
+        }
+
+        if let Some(segments) = record_spec_data.remove(&Yaml::String("segments".to_string())) {
+            for segment_data in Self::get_array(segments, Some(&["records", &name, "segments"]))? {
+                builder = builder.add_segment(Self::get_segment_spec(segment_data, &name)?);
+            }
+        }
+
+        if let Some(bitmap) = record_spec_data.remove(&Yaml::String("bitmap".to_string())) {
+            builder = builder.add_bitmap(Self::get_bitmap_spec(bitmap, &name)?);
+        }
+
+        if let Some(redefines) = record_spec_data.remove(&Yaml::String("redefines".to_string())) {
+            let path = &["records", &name, "redefines"];
+
+            for (field_name, redefine_data) in Self::get_hash(redefines, Some(path))? {
+                let field_name = Self::get_string(field_name, Some(path))?;
+                let redefine_spec = Self::get_redefine_spec(redefine_data, &name, &field_name)?;
+                builder = builder.add_redefine(field_name, redefine_spec);
+            }
+        }
+
+        if let Some(rules) = record_spec_data.remove(&Yaml::String("rules".to_string())) {
+            let path = &["records", &name, "rules"];
+
+            for rule_data in Self::get_array(rules, Some(path))? {
+                builder = builder.add_rule(Self::get_record_rule(rule_data, Some(path))?);
+            }
+        }
+
+        Ok(builder
+            .with_line_ending(record_spec_data.remove(&Yaml::String("line_ending".to_string())).map(|v| Self::get_bytes(v, Some(path))).unwrap_or_else(|| Ok(Vec::new()))?)
+            .with_separator(record_spec_data.remove(&Yaml::String("separator".to_string())).map(|v| Self::get_bytes(v, Some(path))).unwrap_or_else(|| Ok(Vec::new()))?)
+            .build().map_err(Error::BuilderError)?
+        )
+    }
+
+    fn is_group(field_spec_data: &Yaml) -> bool {
+        match *field_spec_data {
+            Yaml::Hash(ref hash) => hash.contains_key(&Yaml::String("fields".to_string())),
+            _ => false
+        }
+    }
+
+    fn get_group_spec<'a>(group_spec_data: Yaml, name: &'a str, group_name: &'a str) -> Result<GroupSpecBuilder, Error> {
+        let path = &["records", name, "fields", group_name, "fields"];
+        let fields = Self::get_hash(Self::get_hash(group_spec_data, Some(path))?
+            .remove(&Yaml::String("fields".to_string()))
+            .ok_or(Error::missing_key("fields", Some(path)))?, Some(path))?
+        ;
+        let mut builder = GroupSpecBuilder::new();
+
+        for (field_name, field_spec_data) in fields {
+            let field_name = Self::get_string(field_name, Some(path))?;
+            let full_name = format!("{}.{}", group_name, field_name);
+
+            if Self::is_group(&field_spec_data) {
+                builder = builder.add_group(field_name, Self::get_group_spec(field_spec_data, name, &full_name)?);
+            } else {
+                builder = builder.add_field(field_name, Self::get_field_spec(field_spec_data, name, &full_name)?);
+            }
+        }
+
+        Ok(builder)
+    }
+
+    fn get_segment_spec<'a>(segment_spec_data: Yaml, name: &'a str) -> Result<SegmentSpec, Error> {
+        let path = &["records", &name, "segments"];
+        let mut segment_spec_data = Self::get_hash(segment_spec_data, Some(path))?;
+        let mut builder = SegmentSpecBuilder::new();
+        let fields = Self::get_hash(segment_spec_data.remove(&Yaml::String("fields".to_string())).ok_or(Error::missing_key("fields", Some(path)))?, Some(path))?;
+
         for (field_name, field_spec_data) in fields {
             let field_name = Self::get_string(field_name, Some(path))?;
             let field_spec = Self::get_field_spec(field_spec_data, &name, &field_name)?;
@@ -87,11 +207,70 @@ impl YamlLoader {
         }
 
         Ok(builder
-            .with_line_ending(record_spec_data.remove(&Yaml::String("line_ending".to_string())).map(|v| Self::get_bytes(v, Some(path))).unwrap_or_else(|| Ok(Vec::new()))?)
+            .with_line_ending(segment_spec_data.remove(&Yaml::String("line_ending".to_string())).map(|v| Self::get_bytes(v, Some(path))).unwrap_or_else(|| Ok(Vec::new()))?)
             .build().map_err(Error::BuilderError)?
         )
     }
 
+    fn get_redefine_spec<'a>(redefine_spec_data: Yaml, name: &'a str, field_name: &'a str) -> Result<RedefineSpec, Error> {
+        let path = &["records", name, "redefines", field_name];
+        let mut redefine_spec_data = Self::get_hash(redefine_spec_data, Some(path))?;
+        let mut builder = RedefineSpecBuilder::new(redefine_spec_data
+            .remove(&Yaml::String("discriminator".to_string()))
+            .map(|v| Self::get_string(v, Some(path)))
+            .unwrap_or_else(|| Err(Error::missing_key("discriminator", Some(path))))?
+        );
+        let alternatives = Self::get_hash(redefine_spec_data
+            .remove(&Yaml::String("alternatives".to_string()))
+            .ok_or(Error::missing_key("alternatives", Some(path)))?, Some(path))?
+        ;
+
+        for (value, fields) in alternatives {
+            let value = Self::get_bytes(value, Some(path))?;
+
+            for (alternative_field_name, field_spec_data) in Self::get_hash(fields, Some(path))? {
+                let alternative_field_name = Self::get_string(alternative_field_name, Some(path))?;
+                let field_spec = Self::get_field_spec(field_spec_data, &name, &alternative_field_name)?;
+                builder = builder.add_field(value.clone(), alternative_field_name, field_spec);
+            }
+        }
+
+        Ok(builder.build().map_err(Error::BuilderError)?)
+    }
+
+    fn get_bitmap_spec<'a>(bitmap_spec_data: Yaml, name: &'a str) -> Result<BitmapSpec, Error> {
+        let path = &["records", name, "bitmap"];
+        let mut bitmap_spec_data = Self::get_hash(bitmap_spec_data, Some(path))?;
+        let mut builder = BitmapSpecBuilder::new(
+            bitmap_spec_data
+                .remove(&Yaml::String("field".to_string()))
+                .map(|v| Self::get_string(v, Some(path)))
+                .unwrap_or_else(|| Err(Error::missing_key("field", Some(path))))?,
+            bitmap_spec_data
+                .remove(&Yaml::String("encoding".to_string()))
+                .map(|v| Self::get_bitmap_encoding(v, Some(path)))
+                .unwrap_or_else(|| Ok(BitmapEncoding::Binary))?
+        );
+        let fields = Self::get_hash(bitmap_spec_data
+            .remove(&Yaml::String("fields".to_string()))
+            .ok_or(Error::missing_key("fields", Some(path)))?, Some(path))?
+        ;
+
+        for (bit, field_name) in fields {
+            builder = builder.add_field(Self::get_usize(bit, Some(path))?, Self::get_string(field_name, Some(path))?);
+        }
+
+        Ok(builder.build().map_err(Error::BuilderError)?)
+    }
+
+    fn get_bitmap_encoding<'a, 'b>(value: Yaml, path: Option<&'a [&'b str]>) -> Result<BitmapEncoding, Error> {
+        match value {
+            Yaml::String(ref v) if v == "binary" => Ok(BitmapEncoding::Binary),
+            Yaml::String(ref v) if v == "hex" => Ok(BitmapEncoding::Hex),
+            _ => Err(Error::invalid_type(value, "String: binary, hex", path))
+        }
+    }
+
     fn get_hash<'a, 'b>(value: Yaml, path: Option<&'a [&'b str]>) -> Result<BTreeMap<Yaml, Yaml>, Error> {
         match value {
             Yaml::Hash(v) => Ok(v),
@@ -99,6 +278,13 @@ impl YamlLoader {
         }
     }
 
+    fn get_array<'a, 'b>(value: Yaml, path: Option<&'a [&'b str]>) -> Result<Vec<Yaml>, Error> {
+        match value {
+            Yaml::Array(v) => Ok(v),
+            _ => Err(Error::invalid_type(value, "Array", path))
+        }
+    }
+
     fn get_string<'a, 'b>(value: Yaml, path: Option<&'a [&'b str]>) -> Result<String, Error> {
         match value {
             Yaml::String(v) => Ok(v),
@@ -111,20 +297,179 @@ impl YamlLoader {
         Self::get_string(value, path).map(String::into_bytes)
     }
 
-    fn get_usize<'a, 'b>(value: Yaml, path: Option<&'a [&'a str]>) -> Result<usize, Error> {
+    fn get_usize<'a, 'b>(value: Yaml, path: Option<&'a [&'b str]>) -> Result<usize, Error> {
         match value {
             Yaml::Integer(v) => Ok(v as usize),
             _ => Err(Error::invalid_type(value, "Integer", path))
         }
     }
 
+    fn get_bool<'a, 'b>(value: Yaml, path: Option<&'a [&'b str]>) -> Result<bool, Error> {
+        match value {
+            Yaml::Boolean(v) => Ok(v),
+            _ => Err(Error::invalid_type(value, "Boolean", path))
+        }
+    }
+
+    fn get_validation_rules<'a, 'b>(value: Yaml, path: Option<&'a [&'b str]>) -> Result<Vec<ValidationRule>, Error> {
+        let mut rules = Vec::new();
+
+        for (key, value) in Self::get_hash(value, path)? {
+            let rule = match Self::get_string(key, path)?.as_str() {
+                "required" => if Self::get_bool(value, path)? { ValidationRule::Required } else { continue },
+                "pattern" => {
+                    let pattern = Self::get_string(value, path)?;
+                    ValidationRule::Pattern(Regex::new(&pattern).map_err(|_| Error::invalid_type(Yaml::String(pattern.clone()), "String: regular expression", path))?)
+                },
+                "one_of" => ValidationRule::OneOf(Self::get_array(value, path)?
+                    .into_iter()
+                    .map(|v| Self::get_bytes(v, path))
+                    .collect::<Result<_, _>>()?
+                ),
+                "range" => {
+                    let mut range = Self::get_hash(value, path)?;
+                    ValidationRule::Range {
+                        min: match range.remove(&Yaml::String("min".to_string())) {
+                            Some(v) => Some(Self::get_i64(v, path)?),
+                            None => None
+                        },
+                        max: match range.remove(&Yaml::String("max".to_string())) {
+                            Some(v) => Some(Self::get_i64(v, path)?),
+                            None => None
+                        }
+                    }
+                },
+                "min_length" => ValidationRule::MinLength(Self::get_usize(value, path)?),
+                "max_length" => ValidationRule::MaxLength(Self::get_usize(value, path)?),
+                "character_class" => ValidationRule::CharacterClass(match value {
+                    Yaml::String(ref v) if v == "alphanumeric" => CharacterClass::Alphanumeric,
+                    Yaml::String(ref v) if v == "digits" => CharacterClass::Digits,
+                    Yaml::String(ref v) if v == "printable_ascii" => CharacterClass::PrintableAscii,
+                    _ => return Err(Error::invalid_type(value, "String: alphanumeric, digits, printable_ascii", path))
+                }),
+                key => return Err(Error::invalid_type(Yaml::String(key.to_string()), "String: required, pattern, one_of, range, min_length, max_length, character_class", path))
+            };
+            rules.push(rule);
+        }
+
+        Ok(rules)
+    }
+
+    fn get_record_rule<'a, 'b>(value: Yaml, path: Option<&'a [&'b str]>) -> Result<RecordRule, Error> {
+        let mut rule_data = Self::get_hash(value, path)?;
+
+        if let Some(fields) = rule_data.remove(&Yaml::String("ordered".to_string())) {
+            let fields = Self::get_array(fields, path)?.into_iter().map(|v| Self::get_string(v, path)).collect::<Result<Vec<_>, _>>()?;
+
+            if fields.len() != 2 {
+                return Err(Error::invalid_type(Yaml::Array(fields.into_iter().map(Yaml::String).collect()), "Array: [first, second]", path));
+            }
+
+            return Ok(RecordRule::Ordered { first: fields[0].clone(), second: fields[1].clone() });
+        }
+
+        if let Some(fields) = rule_data.remove(&Yaml::String("any_populated".to_string())) {
+            return Ok(RecordRule::AnyPopulated(Self::get_array(fields, path)?.into_iter().map(|v| Self::get_string(v, path)).collect::<Result<_, _>>()?));
+        }
+
+        if let Some(sign_data) = rule_data.remove(&Yaml::String("sign_matches".to_string())) {
+            let mut sign_data = Self::get_hash(sign_data, path)?;
+            let mut get = |key: &'static str| sign_data.remove(&Yaml::String(key.to_string())).ok_or_else(|| Error::missing_key(key, path));
+
+            return Ok(RecordRule::SignMatches {
+                amount: Self::get_string(get("amount")?, path)?,
+                code: Self::get_string(get("code")?, path)?,
+                negative: Self::get_bytes(get("negative")?, path)?,
+                positive: Self::get_bytes(get("positive")?, path)?
+            });
+        }
+
+        Err(Error::invalid_type(Yaml::Hash(rule_data), "Hash: {ordered: [first, second]}, {any_populated: [fields]} or {sign_matches: {amount, code, negative, positive}}", path))
+    }
+
+    fn get_file_rule<'a, 'b>(value: Yaml, path: Option<&'a [&'b str]>) -> Result<FileRule, Error> {
+        let rule_data = Self::get_hash(value, path)?;
+
+        if rule_data.len() != 1 {
+            return Err(Error::invalid_type(Yaml::Hash(rule_data), "Hash: {unique_key: ..}, {references: ..} or {sequence: ..}", path));
+        }
+
+        let (kind, rule_data) = rule_data.into_iter().next().expect("the length was checked above");
+        let mut rule_data = Self::get_hash(rule_data, path)?;
+        let mut get = |key: &'static str| rule_data.remove(&Yaml::String(key.to_string())).ok_or_else(|| Error::missing_key(key, path));
+
+        match Self::get_string(kind, path)?.as_str() {
+            "unique_key" => Ok(FileRule::UniqueKey {
+                record: Self::get_string(get("record")?, path)?,
+                fields: Self::get_array(get("fields")?, path)?.into_iter().map(|v| Self::get_string(v, path)).collect::<Result<_, _>>()?
+            }),
+            "references" => Ok(FileRule::References {
+                record: Self::get_string(get("record")?, path)?,
+                field: Self::get_string(get("field")?, path)?,
+                target_record: Self::get_string(get("target_record")?, path)?,
+                target_field: Self::get_string(get("target_field")?, path)?
+            }),
+            "sequence" => Ok(FileRule::Sequence {
+                record: Self::get_string(get("record")?, path)?,
+                field: Self::get_string(get("field")?, path)?
+            }),
+            kind => Err(Error::invalid_type(Yaml::String(kind.to_string()), "String: unique_key, references, sequence", path))
+        }
+    }
+
+    fn get_i64<'a, 'b>(value: Yaml, path: Option<&'a [&'b str]>) -> Result<i64, Error> {
+        match value {
+            Yaml::Integer(v) => Ok(v),
+            _ => Err(Error::invalid_type(value, "Integer", path))
+        }
+    }
+
+    fn get_overflow_policy<'a, 'b>(value: Yaml, path: Option<&'a [&'b str]>) -> Result<OverflowPolicy, Error> {
+        match value {
+            Yaml::String(ref v) if v == "error" => Ok(OverflowPolicy::Error),
+            Yaml::String(ref v) if v == "truncate_right" => Ok(OverflowPolicy::TruncateRight),
+            Yaml::String(ref v) if v == "truncate_left" => Ok(OverflowPolicy::TruncateLeft),
+            Yaml::Hash(mut hash) => match hash.remove(&Yaml::String("ellipsis".to_string())) {
+                Some(marker) => Ok(OverflowPolicy::Ellipsis(Self::get_bytes(marker, path)?)),
+                None => Err(Error::invalid_type(Yaml::Hash(hash), "String: error, truncate_right, truncate_left or Hash: {ellipsis: marker}", path))
+            },
+            _ => Err(Error::invalid_type(value, "String: error, truncate_right, truncate_left or Hash: {ellipsis: marker}", path))
+        }
+    }
+
+    fn get_null_value<'a, 'b>(value: Yaml, path: Option<&'a [&'b str]>) -> Result<NullValue, Error> {
+        match value {
+            Yaml::String(ref v) if v == "blanks" => Ok(NullValue::Blanks),
+            Yaml::String(ref v) if v == "zeros" => Ok(NullValue::Zeros),
+            Yaml::String(ref v) if v == "low_values" => Ok(NullValue::LowValues),
+            Yaml::String(ref v) if v == "high_values" => Ok(NullValue::HighValues),
+            Yaml::Hash(mut hash) => match hash.remove(&Yaml::String("sentinel".to_string())) {
+                Some(sentinel) => Ok(NullValue::Sentinel(Self::get_bytes(sentinel, path)?)),
+                None => Err(Error::invalid_type(Yaml::Hash(hash), "String: blanks, zeros, low_values, high_values or Hash: {sentinel: value}", path))
+            },
+            _ => Err(Error::invalid_type(value, "String: blanks, zeros, low_values, high_values or Hash: {sentinel: value}", path))
+        }
+    }
+
     fn get_padding_direction<'a, 'b>(value: Yaml, path: Option<&'a [&'b str]>) -> Result<PaddingDirection, Error> {
         match value {
             Yaml::String(ref v) if v == "right" => Ok(PaddingDirection::Right),
             Yaml::String(ref v) if v == "Right" => Ok(PaddingDirection::Right),
             Yaml::String(ref v) if v == "left" => Ok(PaddingDirection::Left),
             Yaml::String(ref v) if v == "Left" => Ok(PaddingDirection::Left),
-            _ => Err(Error::invalid_type(value, "String: right, Right, left, Left", path))
+            Yaml::String(ref v) if v == "center" => Ok(PaddingDirection::Center),
+            Yaml::String(ref v) if v == "Center" => Ok(PaddingDirection::Center),
+            _ => Err(Error::invalid_type(value, "String: right, Right, left, Left, center, Center", path))
+        }
+    }
+
+    fn get_trim_direction<'a, 'b>(value: Yaml, path: Option<&'a [&'b str]>) -> Result<TrimDirection, Error> {
+        match value {
+            Yaml::String(ref v) if v == "left" => Ok(TrimDirection::Left),
+            Yaml::String(ref v) if v == "right" => Ok(TrimDirection::Right),
+            Yaml::String(ref v) if v == "both" => Ok(TrimDirection::Both),
+            Yaml::String(ref v) if v == "none" => Ok(TrimDirection::None),
+            _ => Err(Error::invalid_type(value, "String: left, right, both, none", path))
         }
     }
 }
@@ -198,7 +543,8 @@ impl Display for Error {
 mod test {
     use super::YamlLoader;
     use spec::loader::Loader;
-    use spec::{RecordSpecBuilder, SpecBuilder, FieldSpecBuilder, PaddingDirection, Builder};
+    use spec::validation::{CharacterClass, FileRule, RecordRule, Regex, ValidationRule};
+    use spec::{BitmapEncoding, RecordSpecBuilder, RedefineSpecBuilder, SegmentSpecBuilder, SpecBuilder, FieldSpecBuilder, GroupSpecBuilder, NullValue, OverflowPolicy, PaddingDirection, TrimDirection, Builder};
     use std::fs::File;
 
     #[test]
@@ -264,4 +610,270 @@ mod test {
         ;
         assert_eq!(spec, loader.load(&mut File::open("src/spec/loader/spec.yml").unwrap()).unwrap());
     }
+
+    #[test]
+    fn read_field_options() {
+        let loader = YamlLoader;
+        let spec = SpecBuilder::new()
+            .add_record(
+                "record1",
+                RecordSpecBuilder::new()
+                    .with_line_ending([92, 110].as_ref())
+                    .add_field(
+                        "$id",
+                        FieldSpecBuilder::new()
+                            .with_length(2)
+                            .with_padding_direction(PaddingDirection::Right)
+                            .with_default([51, 52].as_ref())
+                            .constant()
+                    )
+                    .add_field(
+                        "field1",
+                        FieldSpecBuilder::new()
+                            .with_length(10)
+                            .with_padding_direction(PaddingDirection::Left)
+                            .with_padding([32].as_ref())
+                            .with_default([104, 101, 108, 108, 111].as_ref())
+                            .with_overflow(OverflowPolicy::Ellipsis([46, 46].to_vec()))
+                            .with_validation(ValidationRule::CharacterClass(CharacterClass::PrintableAscii))
+                            .with_validation(ValidationRule::MaxLength(8))
+                            .with_validation(ValidationRule::OneOf(vec![b"bye".to_vec(), b"hello".to_vec()]))
+                            .with_validation(ValidationRule::Pattern(Regex::new("^[a-z]+$").unwrap()))
+                            .with_validation(ValidationRule::Required)
+                    )
+                    .add_field(
+                        "field2",
+                        FieldSpecBuilder::new()
+                            .with_length(23)
+                            .with_padding_direction(PaddingDirection::Right)
+                            .with_default([103, 111, 111, 100, 98, 121, 101].as_ref())
+                    )
+                    .add_field(
+                        "field3",
+                        FieldSpecBuilder::new()
+                            .with_length(12)
+                            .with_padding_direction(PaddingDirection::Center)
+                            .with_trim_direction(TrimDirection::None)
+                            .with_trim_set([32, 0].as_ref())
+                    )
+                    .add_field(
+                        "field4",
+                        FieldSpecBuilder::new()
+                            .with_length(12)
+                            .with_padding_direction(PaddingDirection::Left)
+                            .with_padding([32].as_ref())
+                            .with_null(NullValue::Sentinel([78, 47, 65].to_vec()))
+                    )
+                    .add_field(
+                        "field5",
+                        FieldSpecBuilder::new()
+                            .with_length(19)
+                            .with_padding_direction(PaddingDirection::Right)
+                            .with_length_prefix(2)
+                    )
+                    .add_field(
+                        "field6",
+                        FieldSpecBuilder::new()
+                            .with_length(4)
+                            .with_padding_direction(PaddingDirection::Right)
+                            .with_null(NullValue::Blanks)
+                    )
+            )
+            .build()
+            .unwrap()
+        ;
+        assert_eq!(spec, loader.load(&mut File::open("src/spec/loader/fields.yml").unwrap()).unwrap());
+    }
+
+    #[test]
+    fn read_record_layout() {
+        let loader = YamlLoader;
+        let spec = SpecBuilder::new()
+            .add_record(
+                "record1",
+                RecordSpecBuilder::new()
+                    .with_line_ending([92, 110].as_ref())
+                    .add_field(
+                        "field1",
+                        FieldSpecBuilder::new()
+                            .with_length(10)
+                            .with_padding_direction(PaddingDirection::Left)
+                            .with_padding([32].as_ref())
+                    )
+                    .add_field(
+                        "field2",
+                        FieldSpecBuilder::new()
+                            .with_length(23)
+                            .with_padding_direction(PaddingDirection::Right)
+                    )
+                    .add_group(
+                        "address",
+                        GroupSpecBuilder::new()
+                            .add_field(
+                                "street",
+                                FieldSpecBuilder::new()
+                                    .with_length(4)
+                                    .with_padding_direction(PaddingDirection::Right)
+                                    .with_validation(ValidationRule::MinLength(1))
+                            )
+                            .add_group(
+                                "geo",
+                                GroupSpecBuilder::new()
+                                    .add_field(
+                                        "lat",
+                                        FieldSpecBuilder::new()
+                                            .with_length(2)
+                                            .with_padding_direction(PaddingDirection::Left)
+                                            .with_validation(ValidationRule::CharacterClass(CharacterClass::Digits))
+                                            .with_validation(ValidationRule::Range { min: Some(-90), max: Some(90) })
+                                    )
+                            )
+                    )
+                    .add_redefine(
+                        "field2",
+                        RedefineSpecBuilder::new("field1")
+                            .add_field(
+                                [104, 101, 108, 108, 111].as_ref(),
+                                "field3",
+                                FieldSpecBuilder::new()
+                                    .with_length(20)
+                                    .with_padding_direction(PaddingDirection::Right)
+                            )
+                            .add_field(
+                                [104, 101, 108, 108, 111].as_ref(),
+                                "field4",
+                                FieldSpecBuilder::new()
+                                    .with_length(3)
+                                    .with_padding_direction(PaddingDirection::Left)
+                            )
+                            .add_field(
+                                [98, 121, 101].as_ref(),
+                                "field5",
+                                FieldSpecBuilder::new()
+                                    .with_length(23)
+                                    .with_padding_direction(PaddingDirection::Right)
+                            )
+                    )
+            )
+            .add_record(
+                "record2",
+                RecordSpecBuilder::new()
+                    .with_line_ending([92, 110].as_ref())
+                    .with_separator([124].as_ref())
+                    .with_bitmap("field0", BitmapEncoding::Hex)
+                        .add_field(2, "field4")
+                        .add_field(3, "field5")
+                    .end()
+                    .add_field(
+                        "field0",
+                        FieldSpecBuilder::new()
+                            .with_length(16)
+                            .with_padding_direction(PaddingDirection::Right)
+                    )
+                    .add_field(
+                        "field4",
+                        FieldSpecBuilder::new()
+                            .with_length(19)
+                            .with_padding_direction(PaddingDirection::Right)
+                    )
+                    .add_field(
+                        "field5",
+                        FieldSpecBuilder::new()
+                            .with_length(3)
+                            .with_padding_direction(PaddingDirection::Right)
+                    )
+                    .add_segment(
+                        SegmentSpecBuilder::new()
+                            .with_line_ending([92, 110].as_ref())
+                            .add_field(
+                                "field3",
+                                FieldSpecBuilder::new()
+                                    .with_length(4)
+                                    .with_padding_direction(PaddingDirection::Right)
+                            )
+                    )
+            )
+            .build()
+            .unwrap()
+        ;
+        assert_eq!(spec, loader.load(&mut File::open("src/spec/loader/layout.yml").unwrap()).unwrap());
+    }
+
+    #[test]
+    fn read_rules() {
+        let loader = YamlLoader;
+        let spec = SpecBuilder::new()
+            .add_record(
+                "record1",
+                RecordSpecBuilder::new()
+                    .with_line_ending([92, 110].as_ref())
+                    .add_field(
+                        "$id",
+                        FieldSpecBuilder::new()
+                            .with_length(2)
+                            .with_padding_direction(PaddingDirection::Right)
+                    )
+                    .add_field(
+                        "field1",
+                        FieldSpecBuilder::new()
+                            .with_length(10)
+                            .with_padding_direction(PaddingDirection::Left)
+                    )
+            )
+            .add_record(
+                "record2",
+                RecordSpecBuilder::new()
+                    .with_line_ending([92, 110].as_ref())
+                    .add_field(
+                        "$id",
+                        FieldSpecBuilder::new()
+                            .with_length(5)
+                            .with_padding_direction(PaddingDirection::Right)
+                    )
+                    .add_field(
+                        "field1",
+                        FieldSpecBuilder::new()
+                            .with_length(12)
+                            .with_padding_direction(PaddingDirection::Left)
+                    )
+                    .add_field(
+                        "field2",
+                        FieldSpecBuilder::new()
+                            .with_length(10)
+                            .with_padding_direction(PaddingDirection::Left)
+                    )
+                    .add_field(
+                        "field3",
+                        FieldSpecBuilder::new()
+                            .with_length(4)
+                            .with_padding_direction(PaddingDirection::Right)
+                    )
+                    .add_field(
+                        "field4",
+                        FieldSpecBuilder::new()
+                            .with_length(4)
+                            .with_padding_direction(PaddingDirection::Right)
+                    )
+                    .add_rule(RecordRule::AnyPopulated(vec!["field3".to_string(), "field4".to_string()]))
+                    .add_rule(RecordRule::Ordered { first: "$id".to_string(), second: "field1".to_string() })
+                    .add_rule(RecordRule::SignMatches {
+                        amount: "field2".to_string(),
+                        code: "field1".to_string(),
+                        negative: b"D".to_vec(),
+                        positive: b"C".to_vec()
+                    })
+            )
+            .add_rule(FileRule::UniqueKey { record: "record2".to_string(), fields: vec!["$id".to_string(), "field1".to_string()] })
+            .add_rule(FileRule::References {
+                record: "record2".to_string(),
+                field: "field1".to_string(),
+                target_record: "record1".to_string(),
+                target_field: "field1".to_string()
+            })
+            .add_rule(FileRule::Sequence { record: "record1".to_string(), field: "$id".to_string() })
+            .build()
+            .unwrap()
+        ;
+        assert_eq!(spec, loader.load(&mut File::open("src/spec/loader/rules.yml").unwrap()).unwrap());
+    }
 }
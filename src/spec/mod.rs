@@ -1,14 +1,19 @@
 pub mod resolver;
 pub mod stream;
 pub mod loader;
+pub mod validation;
+pub mod compiled;
 
-use std::collections::{HashMap, BTreeMap};
+use std::collections::{HashMap, BTreeMap, BTreeSet};
 use std::ops::Range;
 use std::iter::repeat;
 use ::std::fmt::{Display, Error as FmtError, Formatter};
+use self::validation::{FileRule, RecordRule, RecordValues, ValidationRule};
 
 type Result<T> = ::std::result::Result<T, Error>;
 
+pub const GROUP_SEPARATOR: char = '.';
+
 pub trait Builder<T> {
     fn build(self) -> Result<T>;
 }
@@ -16,6 +21,7 @@ pub trait Builder<T> {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Spec {
     pub record_specs: HashMap<String, RecordSpec>,
+    pub rules: Vec<FileRule>,
     __no_construct: ()
 }
 
@@ -27,6 +33,7 @@ impl Builder<Spec> for Spec {
 
 pub struct SpecBuilder {
     record_specs: HashMap<String, Result<RecordSpec>>,
+    rules: Vec<FileRule>,
     sub_builder_error: bool
 }
 
@@ -34,6 +41,7 @@ impl SpecBuilder {
     pub fn new() -> Self {
         SpecBuilder {
             record_specs: HashMap::new(),
+            rules: Vec::new(),
             sub_builder_error: false
         }
     }
@@ -48,6 +56,11 @@ impl SpecBuilder {
     pub fn with_record<T: Into<String>>(self, name: T) -> RecordSpecBuilder {
         RecordSpecBuilder::new_with_spec_builder(name, self)
     }
+
+    pub fn add_rule(mut self, rule: FileRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
 }
 
 impl Builder<Spec> for SpecBuilder {
@@ -61,6 +74,7 @@ impl Builder<Spec> for SpecBuilder {
         } else {
             Ok(Spec {
                 record_specs: self.record_specs.into_iter().map(|(name, result)| (name, result.expect("no errors should be in here"))).collect(),
+                rules: self.rules,
                 __no_construct: ()
             })
         }
@@ -71,24 +85,144 @@ impl Builder<Spec> for SpecBuilder {
 pub struct RecordSpec {
     pub line_ending: Vec<u8>,
     pub field_specs: BTreeMap<String, FieldSpec>,
+    pub field_order: Vec<String>,
+    pub segments: Vec<SegmentSpec>,
+    pub separator: Vec<u8>,
+    pub redefines: BTreeMap<String, RedefineSpec>,
+    pub bitmap: Option<BitmapSpec>,
+    pub groups: BTreeSet<String>,
+    pub rules: Vec<RecordRule>,
     __no_construct: ()
 }
 
 impl RecordSpec {
     pub fn field_range<'a>(&self, name: &'a str) -> Option<Range<usize>> {
-        let mut found_field_spec = None;
-        let index = self.field_specs.iter().take_while(|&(field_name, field_spec)| {
-            if field_name == name {
-                found_field_spec = Some(field_spec);
+        let mut offset = 0;
+
+        for (field_specs, line_ending) in self.lines() {
+            let mut first = true;
+
+            for (field_name, field_spec) in field_specs {
+                if self.bitmap.as_ref().map_or(false, |bitmap| bitmap.controls(field_name)) {
+                    continue;
+                }
+
+                if !first {
+                    offset += self.separator.len();
+                }
+
+                first = false;
+
+                if field_spec.length_prefix.is_some() {
+                    return None;
+                }
+
+                if field_name == name {
+                    return Some(offset..offset + field_spec.length);
+                }
+
+                if self.bitmap.as_ref().map_or(false, |bitmap| &bitmap.field == field_name) {
+                    return None;
+                }
+
+                offset += field_spec.length;
             }
-            found_field_spec.is_none()
-        }).fold(0, |length, (_, field_spec)| length + field_spec.length);
 
-        found_field_spec.map(|field_spec| index..index + field_spec.length)
+            offset += line_ending.len();
+        }
+
+        None
+    }
+
+    pub fn field_spec<'a>(&self, name: &'a str) -> Option<&FieldSpec> {
+        self.lines().filter_map(|(field_specs, _)| field_specs.get(name)).next()
     }
 
     pub fn len(&self) -> usize {
-        self.field_specs.iter().fold(0, |length, (_, field_spec)| length + field_spec.length)
+        let length = self.lines().fold(0, |length, (field_specs, line_ending)| {
+            length + field_specs.iter().fold(0, |length, (_, field_spec)| length + field_spec.length)
+                + field_specs.len().saturating_sub(1) * self.separator.len()
+                + line_ending.len()
+        });
+
+        length - self.lines().last().map_or(0, |(_, line_ending)| line_ending.len())
+    }
+
+    pub fn lines(&self) -> Lines {
+        Lines {
+            first: Some((Fields { field_specs: &self.field_specs, order: &self.field_order[..] }, &self.line_ending[..])),
+            segments: self.segments.iter()
+        }
+    }
+}
+
+pub struct Lines<'a> {
+    first: Option<(Fields<'a>, &'a [u8])>,
+    segments: ::std::slice::Iter<'a, SegmentSpec>
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = (Fields<'a>, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.first.take().or_else(|| self.segments.next().map(|segment| (
+            Fields { field_specs: &segment.field_specs, order: &[] },
+            &segment.line_ending[..]
+        )))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Fields<'a> {
+    field_specs: &'a BTreeMap<String, FieldSpec>,
+    order: &'a [String]
+}
+
+impl<'a> Fields<'a> {
+    pub fn get<'b>(&self, name: &'b str) -> Option<&'a FieldSpec> {
+        self.field_specs.get(name)
+    }
+
+    pub fn iter(&self) -> FieldsIter<'a> {
+        if self.order.is_empty() {
+            FieldsIter::Sorted(self.field_specs.iter())
+        } else {
+            FieldsIter::Ordered(self.field_specs, self.order.iter())
+        }
+    }
+}
+
+impl<'a> ::std::ops::Deref for Fields<'a> {
+    type Target = BTreeMap<String, FieldSpec>;
+
+    fn deref(&self) -> &BTreeMap<String, FieldSpec> {
+        self.field_specs
+    }
+}
+
+impl<'a> IntoIterator for Fields<'a> {
+    type Item = (&'a String, &'a FieldSpec);
+    type IntoIter = FieldsIter<'a>;
+
+    fn into_iter(self) -> FieldsIter<'a> {
+        self.iter()
+    }
+}
+
+pub enum FieldsIter<'a> {
+    Sorted(::std::collections::btree_map::Iter<'a, String, FieldSpec>),
+    Ordered(&'a BTreeMap<String, FieldSpec>, ::std::slice::Iter<'a, String>)
+}
+
+impl<'a> Iterator for FieldsIter<'a> {
+    type Item = (&'a String, &'a FieldSpec);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match *self {
+            FieldsIter::Sorted(ref mut fields) => fields.next(),
+            FieldsIter::Ordered(field_specs, ref mut order) => order.next()
+                .map(|name| (name, field_specs.get(name).expect("the field order is checked against the fields when the spec is built")))
+        }
     }
 }
 
@@ -100,7 +234,14 @@ impl Builder<RecordSpec> for RecordSpec {
 
 pub struct RecordSpecBuilder {
     line_ending: Vec<u8>,
+    separator: Vec<u8>,
     field_specs: BTreeMap<String, Result<FieldSpec>>,
+    field_order: Vec<String>,
+    segments: Vec<Result<SegmentSpec>>,
+    redefines: BTreeMap<String, Result<RedefineSpec>>,
+    bitmap: Option<Result<BitmapSpec>>,
+    groups: BTreeSet<String>,
+    rules: Vec<RecordRule>,
     sub_builder_error: bool,
     spec_builder: Option<SpecBuilder>,
     name: Option<String>
@@ -110,7 +251,14 @@ impl RecordSpecBuilder {
     pub fn new() -> Self {
         RecordSpecBuilder {
             line_ending: Vec::new(),
+            separator: Vec::new(),
             field_specs: BTreeMap::new(),
+            field_order: Vec::new(),
+            segments: Vec::new(),
+            redefines: BTreeMap::new(),
+            bitmap: None,
+            groups: BTreeSet::new(),
+            rules: Vec::new(),
             sub_builder_error: false,
             spec_builder: None,
             name: None,
@@ -120,7 +268,14 @@ impl RecordSpecBuilder {
     pub fn new_with_spec_builder<T: Into<String>>(name: T, spec_builder: SpecBuilder) -> Self {
         RecordSpecBuilder {
             line_ending: Vec::new(),
+            separator: Vec::new(),
             field_specs: BTreeMap::new(),
+            field_order: Vec::new(),
+            segments: Vec::new(),
+            redefines: BTreeMap::new(),
+            bitmap: None,
+            groups: BTreeSet::new(),
+            rules: Vec::new(),
             sub_builder_error: false,
             spec_builder: Some(spec_builder),
             name: Some(name.into())
@@ -138,11 +293,87 @@ impl RecordSpecBuilder {
         FieldSpecBuilder::new_with_record_spec_builder(name, self)
     }
 
+    pub fn add_group<T: Into<String>>(mut self, name: T, group: GroupSpecBuilder) -> Self {
+        let name = name.into();
+
+        for (field_name, field) in group.field_specs {
+            self.sub_builder_error = self.sub_builder_error || field.is_err();
+            self.field_specs.insert(format!("{}{}{}", name, GROUP_SEPARATOR, field_name), field);
+        }
+
+        for group_name in group.groups {
+            self.groups.insert(format!("{}{}{}", name, GROUP_SEPARATOR, group_name));
+        }
+
+        self.groups.insert(name);
+        self
+    }
+
+    pub fn with_group<T: Into<String>>(self, name: T) -> GroupSpecBuilder {
+        GroupSpecBuilder::new_with_record_spec_builder(name, self)
+    }
+
     pub fn with_line_ending<T: Into<Vec<u8>>>(mut self, line_ending: T) -> Self {
         self.line_ending = line_ending.into();
         self
     }
 
+    pub fn with_field_order<T: IntoIterator<Item=U>, U: Into<String>>(mut self, field_order: T) -> Self {
+        self.field_order = field_order.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_separator<T: Into<Vec<u8>>>(mut self, separator: T) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    pub fn add_segment<T: Builder<SegmentSpec>>(mut self, segment: T) -> Self {
+        let segment = segment.build();
+        self.sub_builder_error = self.sub_builder_error || segment.is_err();
+        self.segments.push(segment);
+        self
+    }
+
+    pub fn with_segment(self) -> SegmentSpecBuilder {
+        SegmentSpecBuilder::new_with_record_spec_builder(self)
+    }
+
+    pub fn add_redefine<T: Into<String>, U: Builder<RedefineSpec>>(mut self, name: T, redefine: U) -> Self {
+        let redefine = redefine.build();
+        self.sub_builder_error = self.sub_builder_error || redefine.is_err();
+        self.redefines.insert(name.into(), redefine);
+        self
+    }
+
+    pub fn with_redefine<T: Into<String>, U: Into<String>>(self, name: T, discriminator: U) -> RedefineSpecBuilder {
+        RedefineSpecBuilder::new_with_record_spec_builder(name, discriminator, self)
+    }
+
+    pub fn add_bitmap<T: Builder<BitmapSpec>>(mut self, bitmap: T) -> Self {
+        let bitmap = bitmap.build();
+        self.sub_builder_error = self.sub_builder_error || bitmap.is_err();
+        self.bitmap = Some(bitmap);
+        self
+    }
+
+    pub fn with_bitmap<T: Into<String>>(self, field: T, encoding: BitmapEncoding) -> BitmapSpecBuilder {
+        BitmapSpecBuilder::new_with_record_spec_builder(field, encoding, self)
+    }
+
+    pub fn add_rule(mut self, rule: RecordRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn add_rule_fn<T, U, V>(self, name: T, fields: Vec<U>, check: V) -> Self
+        where T: Into<String>,
+              U: Into<String>,
+              V: Fn(&RecordValues) -> bool + Send + Sync + 'static
+    {
+        self.add_rule(RecordRule::custom(name, fields, check))
+    }
+
     pub fn end(mut self) -> SpecBuilder {
         let name = self.name.take().expect("calling end infers that this was created with the name connected");
         self.spec_builder.take()
@@ -153,6 +384,243 @@ impl RecordSpecBuilder {
 
 impl Builder<RecordSpec> for RecordSpecBuilder {
     fn build(self) -> Result<RecordSpec> {
+        if self.sub_builder_error {
+            return Err(Error::SubBuilderErrors(self.field_specs.into_iter()
+                .filter(|&(_, ref result)| result.is_err())
+                .map(|(name, result)| (name, result.unwrap_err()))
+                .chain(self.segments.into_iter()
+                    .enumerate()
+                    .filter(|&(_, ref result)| result.is_err())
+                    .map(|(index, result)| (format!("segment {}", index + 1), result.unwrap_err()))
+                )
+                .chain(self.redefines.into_iter()
+                    .filter(|&(_, ref result)| result.is_err())
+                    .map(|(name, result)| (name, result.unwrap_err()))
+                )
+                .chain(self.bitmap.into_iter()
+                    .filter(|result| result.is_err())
+                    .map(|result| ("bitmap".to_string(), result.unwrap_err()))
+                )
+                .collect()
+            ));
+        }
+
+        let record_spec = RecordSpec {
+            line_ending: self.line_ending,
+            field_specs: self.field_specs.into_iter().map(|(name, result)| (name, result.expect("no errors should be in here"))).collect(),
+            field_order: self.field_order,
+            segments: self.segments.into_iter().map(|result| result.expect("no errors should be in here")).collect(),
+            separator: self.separator,
+            redefines: self.redefines.into_iter().map(|(name, result)| (name, result.expect("no errors should be in here"))).collect(),
+            bitmap: self.bitmap.map(|result| result.expect("no errors should be in here")),
+            groups: self.groups,
+            rules: self.rules,
+            __no_construct: ()
+        };
+
+        for (index, segment) in record_spec.segments.iter().enumerate() {
+            for (name, _) in &segment.field_specs {
+                if record_spec.lines().take(index + 1).any(|(field_specs, _)| field_specs.contains_key(name)) {
+                    return Err(Error::DuplicateField(name.clone()));
+                }
+            }
+        }
+
+        if !record_spec.field_order.is_empty() {
+            let mut ordered = BTreeSet::new();
+
+            for name in &record_spec.field_order {
+                if !record_spec.field_specs.contains_key(name) {
+                    return Err(Error::FieldNotFound(name.clone()));
+                }
+
+                if !ordered.insert(name) {
+                    return Err(Error::DuplicateField(name.clone()));
+                }
+            }
+
+            if let Some(name) = record_spec.field_specs.keys().find(|name| !ordered.contains(name)) {
+                return Err(Error::FieldNotOrdered(name.clone()));
+            }
+        }
+
+        for group in &record_spec.groups {
+            if record_spec.field_spec(group).is_some() {
+                return Err(Error::DuplicateField(group.clone()));
+            }
+        }
+
+        for (name, redefine) in &record_spec.redefines {
+            let range = record_spec.field_range(name).ok_or_else(|| Error::FieldNotFound(name.clone()))?;
+            let discriminator_range = record_spec.field_range(&redefine.discriminator)
+                .ok_or_else(|| Error::FieldNotFound(redefine.discriminator.clone()))?
+            ;
+
+            if discriminator_range.start >= range.start {
+                return Err(Error::DiscriminatorNotBeforeField(name.clone()));
+            }
+
+            for (_, field_specs) in &redefine.alternatives {
+                let length = field_specs.iter().fold(0, |length, (_, field_spec)| length + field_spec.length);
+
+                if length != range.end - range.start {
+                    return Err(Error::AlternativeWrongLength(name.clone(), range.end - range.start, length));
+                }
+
+                for (field_name, _) in field_specs {
+                    if record_spec.field_spec(field_name).is_some() {
+                        return Err(Error::DuplicateField(field_name.clone()));
+                    }
+                }
+            }
+        }
+
+        if let Some(ref bitmap) = record_spec.bitmap {
+            let bits = bitmap.bits_per_block(record_spec.field_spec(&bitmap.field)
+                .ok_or_else(|| Error::FieldNotFound(bitmap.field.clone()))?
+            );
+
+            for (bit, name) in &bitmap.fields {
+                if *bit < 2 || *bit > bits * 2 {
+                    return Err(Error::InvalidBitmapBit(*bit));
+                }
+
+                if record_spec.field_spec(name).is_none() {
+                    return Err(Error::FieldNotFound(name.clone()));
+                }
+            }
+        }
+
+        for rule in &record_spec.rules {
+            for name in rule.fields() {
+                let is_redefined = record_spec.redefines.values()
+                    .any(|redefine| redefine.alternatives.values().any(|field_specs| field_specs.contains_key(&name)))
+                ;
+
+                if record_spec.field_spec(&name).is_none() && !is_redefined {
+                    return Err(Error::FieldNotFound(name));
+                }
+            }
+        }
+
+        Ok(record_spec)
+    }
+}
+
+pub struct GroupSpecBuilder {
+    field_specs: Vec<(String, Result<FieldSpec>)>,
+    groups: Vec<String>,
+    record_spec_builder: Option<RecordSpecBuilder>,
+    name: Option<String>
+}
+
+impl GroupSpecBuilder {
+    pub fn new() -> Self {
+        GroupSpecBuilder {
+            field_specs: Vec::new(),
+            groups: Vec::new(),
+            record_spec_builder: None,
+            name: None
+        }
+    }
+
+    pub fn new_with_record_spec_builder<T: Into<String>>(name: T, record_spec_builder: RecordSpecBuilder) -> Self {
+        GroupSpecBuilder {
+            field_specs: Vec::new(),
+            groups: Vec::new(),
+            record_spec_builder: Some(record_spec_builder),
+            name: Some(name.into())
+        }
+    }
+
+    pub fn add_field<T: Into<String>, U: Builder<FieldSpec>>(mut self, name: T, field: U) -> Self {
+        self.field_specs.push((name.into(), field.build()));
+        self
+    }
+
+    pub fn add_group<T: Into<String>>(mut self, name: T, group: GroupSpecBuilder) -> Self {
+        let name = name.into();
+
+        for (field_name, field) in group.field_specs {
+            self.field_specs.push((format!("{}{}{}", name, GROUP_SEPARATOR, field_name), field));
+        }
+
+        for group_name in group.groups {
+            self.groups.push(format!("{}{}{}", name, GROUP_SEPARATOR, group_name));
+        }
+
+        self.groups.push(name);
+        self
+    }
+
+    pub fn end(mut self) -> RecordSpecBuilder {
+        let name = self.name.take().expect("calling end infers that this was created with the name connected");
+        self.record_spec_builder.take()
+            .expect("calling end infers that this was created with the parent record spec builder connected")
+            .add_group(name, self)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SegmentSpec {
+    pub line_ending: Vec<u8>,
+    pub field_specs: BTreeMap<String, FieldSpec>,
+    __no_construct: ()
+}
+
+impl Builder<SegmentSpec> for SegmentSpec {
+    fn build(self) -> Result<Self> {
+        Ok(self)
+    }
+}
+
+pub struct SegmentSpecBuilder {
+    line_ending: Vec<u8>,
+    field_specs: BTreeMap<String, Result<FieldSpec>>,
+    sub_builder_error: bool,
+    record_spec_builder: Option<RecordSpecBuilder>
+}
+
+impl SegmentSpecBuilder {
+    pub fn new() -> Self {
+        SegmentSpecBuilder {
+            line_ending: Vec::new(),
+            field_specs: BTreeMap::new(),
+            sub_builder_error: false,
+            record_spec_builder: None
+        }
+    }
+
+    pub fn new_with_record_spec_builder(record_spec_builder: RecordSpecBuilder) -> Self {
+        SegmentSpecBuilder {
+            line_ending: Vec::new(),
+            field_specs: BTreeMap::new(),
+            sub_builder_error: false,
+            record_spec_builder: Some(record_spec_builder)
+        }
+    }
+
+    pub fn add_field<T: Into<String>, U: Builder<FieldSpec>>(mut self, name: T, field: U) -> Self {
+        let field = field.build();
+        self.sub_builder_error = self.sub_builder_error || field.is_err();
+        self.field_specs.insert(name.into(), field);
+        self
+    }
+
+    pub fn with_line_ending<T: Into<Vec<u8>>>(mut self, line_ending: T) -> Self {
+        self.line_ending = line_ending.into();
+        self
+    }
+
+    pub fn end(mut self) -> RecordSpecBuilder {
+        self.record_spec_builder.take()
+            .expect("calling end infers that this was created with the parent record spec builder connected")
+            .add_segment(self)
+    }
+}
+
+impl Builder<SegmentSpec> for SegmentSpecBuilder {
+    fn build(self) -> Result<SegmentSpec> {
         if self.sub_builder_error {
             Err(Error::SubBuilderErrors(self.field_specs.into_iter()
                 .filter(|&(_, ref result)| result.is_err())
@@ -160,7 +628,7 @@ impl Builder<RecordSpec> for RecordSpecBuilder {
                 .collect()
             ))
         } else {
-            Ok(RecordSpec {
+            Ok(SegmentSpec {
                 line_ending: self.line_ending,
                 field_specs: self.field_specs.into_iter().map(|(name, result)| (name, result.expect("no errors should be in here"))).collect(),
                 __no_construct: ()
@@ -169,10 +637,310 @@ impl Builder<RecordSpec> for RecordSpecBuilder {
     }
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RedefineSpec {
+    pub discriminator: String,
+    pub alternatives: BTreeMap<Vec<u8>, BTreeMap<String, FieldSpec>>,
+    __no_construct: ()
+}
+
+impl RedefineSpec {
+    pub fn alternative<'a>(&self, discriminator_value: &'a [u8]) -> Option<&BTreeMap<String, FieldSpec>> {
+        self.alternatives.get(discriminator_value)
+    }
+}
+
+impl Builder<RedefineSpec> for RedefineSpec {
+    fn build(self) -> Result<Self> {
+        Ok(self)
+    }
+}
+
+pub struct RedefineSpecBuilder {
+    discriminator: String,
+    alternatives: BTreeMap<Vec<u8>, BTreeMap<String, Result<FieldSpec>>>,
+    sub_builder_error: bool,
+    record_spec_builder: Option<RecordSpecBuilder>,
+    name: Option<String>
+}
+
+impl RedefineSpecBuilder {
+    pub fn new<T: Into<String>>(discriminator: T) -> Self {
+        RedefineSpecBuilder {
+            discriminator: discriminator.into(),
+            alternatives: BTreeMap::new(),
+            sub_builder_error: false,
+            record_spec_builder: None,
+            name: None
+        }
+    }
+
+    pub fn new_with_record_spec_builder<T: Into<String>, U: Into<String>>(name: T, discriminator: U, record_spec_builder: RecordSpecBuilder) -> Self {
+        RedefineSpecBuilder {
+            discriminator: discriminator.into(),
+            alternatives: BTreeMap::new(),
+            sub_builder_error: false,
+            record_spec_builder: Some(record_spec_builder),
+            name: Some(name.into())
+        }
+    }
+
+    pub fn add_field<T: Into<Vec<u8>>, U: Into<String>, V: Builder<FieldSpec>>(mut self, discriminator_value: T, name: U, field: V) -> Self {
+        let field = field.build();
+        self.sub_builder_error = self.sub_builder_error || field.is_err();
+        self.alternatives.entry(discriminator_value.into()).or_insert_with(BTreeMap::new).insert(name.into(), field);
+        self
+    }
+
+    pub fn end(mut self) -> RecordSpecBuilder {
+        let name = self.name.take().expect("calling end infers that this was created with the name connected");
+        self.record_spec_builder.take()
+            .expect("calling end infers that this was created with the parent record spec builder connected")
+            .add_redefine(name, self)
+    }
+}
+
+impl Builder<RedefineSpec> for RedefineSpecBuilder {
+    fn build(self) -> Result<RedefineSpec> {
+        if self.sub_builder_error {
+            Err(Error::SubBuilderErrors(self.alternatives.into_iter()
+                .flat_map(|(_, field_specs)| field_specs.into_iter())
+                .filter(|&(_, ref result)| result.is_err())
+                .map(|(name, result)| (name, result.unwrap_err()))
+                .collect()
+            ))
+        } else {
+            Ok(RedefineSpec {
+                discriminator: self.discriminator,
+                alternatives: self.alternatives.into_iter().map(|(value, field_specs)| (
+                    value,
+                    field_specs.into_iter().map(|(name, result)| (name, result.expect("no errors should be in here"))).collect()
+                )).collect(),
+                __no_construct: ()
+            })
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum BitmapEncoding {
+    Binary,
+    Hex
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BitmapSpec {
+    pub field: String,
+    pub encoding: BitmapEncoding,
+    pub fields: BTreeMap<usize, String>,
+    __no_construct: ()
+}
+
+impl BitmapSpec {
+    pub fn bits_per_block(&self, field_spec: &FieldSpec) -> usize {
+        match self.encoding {
+            BitmapEncoding::Binary => field_spec.length * 8,
+            BitmapEncoding::Hex => field_spec.length * 4
+        }
+    }
+
+    pub fn controls<'a>(&self, name: &'a str) -> bool {
+        self.fields.values().any(|field| field == name)
+    }
+
+    pub fn has_secondary<'a>(&self, primary: &'a [u8]) -> Option<bool> {
+        self.decode(primary).map(|bits| bits.first() == Some(&1))
+    }
+
+    pub fn decode<'a>(&self, data: &'a [u8]) -> Option<Vec<usize>> {
+        let mut bits = Vec::new();
+        let bits_per_byte = match self.encoding {
+            BitmapEncoding::Binary => 8,
+            BitmapEncoding::Hex => 4
+        };
+
+        for (index, byte) in data.iter().enumerate() {
+            let value = match self.encoding {
+                BitmapEncoding::Binary => *byte,
+                BitmapEncoding::Hex => (*byte as char).to_digit(16)? as u8
+            };
+
+            for bit in 0..bits_per_byte {
+                if value & (1 << (bits_per_byte - 1 - bit)) != 0 {
+                    bits.push(index * bits_per_byte + bit + 1);
+                }
+            }
+        }
+
+        Some(bits)
+    }
+
+    pub fn encode<'a>(&self, bits: &'a [usize], field_spec: &'a FieldSpec) -> Vec<u8> {
+        let bits_per_block = self.bits_per_block(field_spec);
+        let secondary = bits.iter().any(|bit| *bit > bits_per_block);
+        let mut values = vec![0u8; if secondary { field_spec.length * 2 } else { field_spec.length }];
+        let bits_per_byte = match self.encoding {
+            BitmapEncoding::Binary => 8,
+            BitmapEncoding::Hex => 4
+        };
+
+        for bit in bits.iter().chain(if secondary { Some(&1) } else { None }) {
+            values[(bit - 1) / bits_per_byte] |= 1 << (bits_per_byte - 1 - (bit - 1) % bits_per_byte);
+        }
+
+        match self.encoding {
+            BitmapEncoding::Binary => values,
+            BitmapEncoding::Hex => values.into_iter()
+                .map(|value| ::std::char::from_digit(value as u32, 16).expect("a nibble is always a hex digit").to_ascii_uppercase() as u8)
+                .collect()
+        }
+    }
+}
+
+impl Builder<BitmapSpec> for BitmapSpec {
+    fn build(self) -> Result<Self> {
+        Ok(self)
+    }
+}
+
+pub struct BitmapSpecBuilder {
+    field: String,
+    encoding: BitmapEncoding,
+    fields: BTreeMap<usize, String>,
+    record_spec_builder: Option<RecordSpecBuilder>
+}
+
+impl BitmapSpecBuilder {
+    pub fn new<T: Into<String>>(field: T, encoding: BitmapEncoding) -> Self {
+        BitmapSpecBuilder {
+            field: field.into(),
+            encoding: encoding,
+            fields: BTreeMap::new(),
+            record_spec_builder: None
+        }
+    }
+
+    pub fn new_with_record_spec_builder<T: Into<String>>(field: T, encoding: BitmapEncoding, record_spec_builder: RecordSpecBuilder) -> Self {
+        BitmapSpecBuilder {
+            field: field.into(),
+            encoding: encoding,
+            fields: BTreeMap::new(),
+            record_spec_builder: Some(record_spec_builder)
+        }
+    }
+
+    pub fn add_field<T: Into<String>>(mut self, bit: usize, name: T) -> Self {
+        self.fields.insert(bit, name.into());
+        self
+    }
+
+    pub fn end(mut self) -> RecordSpecBuilder {
+        self.record_spec_builder.take()
+            .expect("calling end infers that this was created with the parent record spec builder connected")
+            .add_bitmap(self)
+    }
+}
+
+impl Builder<BitmapSpec> for BitmapSpecBuilder {
+    fn build(self) -> Result<BitmapSpec> {
+        Ok(BitmapSpec {
+            field: self.field,
+            encoding: self.encoding,
+            fields: self.fields,
+            __no_construct: ()
+        })
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 pub enum PaddingDirection {
     Left,
-    Right
+    Right,
+    Center
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum TrimDirection {
+    Left,
+    Right,
+    Both,
+    None
+}
+
+impl From<PaddingDirection> for TrimDirection {
+    fn from(padding_direction: PaddingDirection) -> Self {
+        match padding_direction {
+            PaddingDirection::Left => TrimDirection::Left,
+            PaddingDirection::Right => TrimDirection::Right,
+            PaddingDirection::Center => TrimDirection::Both
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum OverflowPolicy {
+    Error,
+    TruncateRight,
+    TruncateLeft,
+    Ellipsis(Vec<u8>)
+}
+
+impl Default for OverflowPolicy {
+    fn default() -> Self {
+        OverflowPolicy::Error
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum NullValue {
+    Blanks,
+    Zeros,
+    LowValues,
+    HighValues,
+    Sentinel(Vec<u8>)
+}
+
+impl NullValue {
+    pub fn to_bytes(&self, field_spec: &FieldSpec) -> Vec<u8> {
+        let fill = match *self {
+            NullValue::Blanks => b' ',
+            NullValue::Zeros => b'0',
+            NullValue::LowValues => 0x00,
+            NullValue::HighValues => 0xFF,
+            NullValue::Sentinel(ref sentinel) => {
+                if field_spec.length_prefix.is_some() || sentinel.len() >= field_spec.length {
+                    return sentinel.clone();
+                }
+
+                let padding_length = field_spec.length - sentinel.len();
+                let left_padding_length = match field_spec.padding_direction {
+                    PaddingDirection::Left => padding_length,
+                    PaddingDirection::Right => 0,
+                    PaddingDirection::Center => padding_length / 2
+                };
+                let mut bytes = Vec::with_capacity(field_spec.length);
+                bytes.extend(field_spec.padding.iter().cycle().take(left_padding_length));
+                bytes.extend_from_slice(&sentinel[..]);
+                bytes.extend(field_spec.padding.iter().cycle().take(padding_length - left_padding_length));
+
+                return bytes;
+            }
+        };
+
+        repeat(fill).take(field_spec.length).collect()
+    }
+
+    pub fn matches<'a>(&self, data: &'a [u8], field_spec: &'a FieldSpec) -> bool {
+        let fill = match *self {
+            NullValue::Blanks => b' ',
+            NullValue::Zeros => b'0',
+            NullValue::LowValues => 0x00,
+            NullValue::HighValues => 0xFF,
+            NullValue::Sentinel(_) => return data == &self.to_bytes(field_spec)[..]
+        };
+
+        !data.is_empty() && data.iter().all(|byte| *byte == fill)
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -180,7 +948,14 @@ pub struct FieldSpec {
     pub length: usize,
     pub padding_direction: PaddingDirection,
     pub padding: Vec<u8>,
+    pub trim_direction: TrimDirection,
+    pub trim_set: Option<Vec<u8>>,
     pub default: Option<Vec<u8>>,
+    pub length_prefix: Option<usize>,
+    pub overflow: OverflowPolicy,
+    pub null: Option<NullValue>,
+    pub constant: bool,
+    pub validations: Vec<ValidationRule>,
     __no_construct: ()
 }
 
@@ -194,7 +969,14 @@ pub struct FieldSpecBuilder {
     length: Option<usize>,
     padding_direction: Option<PaddingDirection>,
     padding: Option<Vec<u8>>,
+    trim_direction: Option<TrimDirection>,
+    trim_set: Option<Vec<u8>>,
     default: Option<Vec<u8>>,
+    length_prefix: Option<usize>,
+    overflow: Option<OverflowPolicy>,
+    null: Option<NullValue>,
+    constant: bool,
+    validations: Vec<ValidationRule>,
     record_spec_builder: Option<RecordSpecBuilder>,
     name: Option<String>
 }
@@ -205,7 +987,14 @@ impl Clone for FieldSpecBuilder {
             length: self.length.clone(),
             padding_direction: self.padding_direction.clone(),
             padding: self.padding.clone(),
+            trim_direction: self.trim_direction.clone(),
+            trim_set: self.trim_set.clone(),
             default: self.default.clone(),
+            length_prefix: self.length_prefix.clone(),
+            overflow: self.overflow.clone(),
+            null: self.null.clone(),
+            constant: self.constant,
+            validations: self.validations.clone(),
             record_spec_builder: None,
             name: None
         }
@@ -218,7 +1007,14 @@ impl FieldSpecBuilder {
             length: None,
             padding_direction: None,
             padding: None,
+            trim_direction: None,
+            trim_set: None,
             default: None,
+            length_prefix: None,
+            overflow: None,
+            null: None,
+            constant: false,
+            validations: Vec::new(),
             record_spec_builder: None,
             name: None,
         }
@@ -229,7 +1025,14 @@ impl FieldSpecBuilder {
             length: None,
             padding_direction: None,
             padding: None,
+            trim_direction: None,
+            trim_set: None,
             default: None,
+            length_prefix: None,
+            overflow: None,
+            null: None,
+            constant: false,
+            validations: Vec::new(),
             record_spec_builder: Some(record_spec_builder),
             name: Some(name.into()),
         }
@@ -247,6 +1050,10 @@ impl FieldSpecBuilder {
         self.with_padding(" ").with_padding_direction(PaddingDirection::Right)
     }
 
+    pub fn centered_string(self) -> Self {
+        self.string().with_padding_direction(PaddingDirection::Center)
+    }
+
     pub fn empty_string(self) -> Self {
         self.string().with_default("")
     }
@@ -272,11 +1079,54 @@ impl FieldSpecBuilder {
         self
     }
 
+    pub fn with_trim_direction(mut self, trim_direction: TrimDirection) -> Self {
+        self.trim_direction = Some(trim_direction);
+        self
+    }
+
+    pub fn with_trim_set<T: Into<Vec<u8>>>(mut self, trim_set: T) -> Self {
+        self.trim_set = Some(trim_set.into());
+        self
+    }
+
     pub fn with_default<T: Into<Vec<u8>>>(mut self, default: T) -> Self {
         self.default = Some(default.into());
         self
     }
 
+    pub fn with_length_prefix(mut self, digits: usize) -> Self {
+        self.length_prefix = Some(digits);
+        self
+    }
+
+    pub fn with_overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.overflow = Some(overflow);
+        self
+    }
+
+    pub fn with_null(mut self, null: NullValue) -> Self {
+        self.null = Some(null);
+        self
+    }
+
+    pub fn constant(mut self) -> Self {
+        self.constant = true;
+        self
+    }
+
+    pub fn with_validation(mut self, rule: ValidationRule) -> Self {
+        self.validations.push(rule);
+        self
+    }
+
+    pub fn llvar(self, max_length: usize) -> Self {
+        self.string().with_length(max_length).with_length_prefix(2)
+    }
+
+    pub fn lllvar(self, max_length: usize) -> Self {
+        self.string().with_length(max_length).with_length_prefix(3)
+    }
+
     pub fn end(mut self) -> RecordSpecBuilder {
         let name = self.name.take().expect("calling end infers that this was created with the name connected");
         self.record_spec_builder.take()
@@ -287,11 +1137,24 @@ impl FieldSpecBuilder {
 
 impl Builder<FieldSpec> for FieldSpecBuilder {
     fn build(self) -> Result<FieldSpec> {
+        let padding_direction = self.padding_direction.ok_or(Error::FieldRequiredToBuild("padding"))?;
+
+        if self.constant && self.default.is_none() {
+            return Err(Error::FieldRequiredToBuild("default"));
+        }
+
         Ok(FieldSpec {
             length: self.length.ok_or(Error::FieldRequiredToBuild("length"))?,
-            padding_direction: self.padding_direction.ok_or(Error::FieldRequiredToBuild("padding"))?,
+            padding_direction: padding_direction,
             padding: self.padding.unwrap_or_default(),
+            trim_direction: self.trim_direction.unwrap_or_else(|| padding_direction.into()),
+            trim_set: self.trim_set,
             default: self.default,
+            length_prefix: self.length_prefix,
+            overflow: self.overflow.unwrap_or_default(),
+            null: self.null,
+            constant: self.constant,
+            validations: self.validations,
             __no_construct: (),
         })
     }
@@ -300,14 +1163,26 @@ impl Builder<FieldSpec> for FieldSpecBuilder {
 #[derive(Debug)]
 pub enum Error {
     FieldRequiredToBuild(&'static str),
-    SubBuilderErrors(HashMap<String, Error>)
+    SubBuilderErrors(HashMap<String, Error>),
+    DuplicateField(String),
+    FieldNotFound(String),
+    DiscriminatorNotBeforeField(String),
+    AlternativeWrongLength(String, usize, usize),
+    InvalidBitmapBit(usize),
+    FieldNotOrdered(String)
 }
 
 impl ::std::error::Error for Error {
     fn description(&self) -> &str {
         match *self {
             Error::FieldRequiredToBuild(_) => "There is a required field missing",
-            Error::SubBuilderErrors(_) => "Some sub builders had errors"
+            Error::SubBuilderErrors(_) => "Some sub builders had errors",
+            Error::DuplicateField(_) => "A field is defined more than once",
+            Error::FieldNotFound(_) => "A referenced field is not defined",
+            Error::DiscriminatorNotBeforeField(_) => "The discriminator of a redefined field must come before it",
+            Error::AlternativeWrongLength(_, _, _) => "An alternative layout doesn't have the same length as the field it redefines",
+            Error::InvalidBitmapBit(_) => "The bitmap bit is out of range",
+            Error::FieldNotOrdered(_) => "A field is missing from the record's field order"
         }
     }
 }
@@ -323,7 +1198,19 @@ impl Display for Error {
                 }
 
                 Ok(())
-            }
+            },
+            Error::DuplicateField(ref name) => write!(f, "The field {} is defined more than once", name),
+            Error::FieldNotFound(ref name) => write!(f, "The referenced field {} is not defined", name),
+            Error::DiscriminatorNotBeforeField(ref name) => write!(f, "The discriminator of the redefined field {} must come before it", name),
+            Error::AlternativeWrongLength(ref name, ref expected, ref actual) => write!(
+                f,
+                "An alternative layout for the field {} is {} long but the field is {} long",
+                name,
+                actual,
+                expected
+            ),
+            Error::InvalidBitmapBit(ref bit) => write!(f, "The bitmap bit {} is out of range", bit),
+            Error::FieldNotOrdered(ref name) => write!(f, "The field {} is missing from the record's field order", name)
         }
     }
 }
@@ -343,26 +1230,54 @@ mod test {
             length: 4,
             padding: "dsasd".as_bytes().to_owned(),
             padding_direction: PaddingDirection::Left,
+            trim_direction: TrimDirection::Left,
+            trim_set: None,
             default: None,
+            length_prefix: None,
+            overflow: OverflowPolicy::Error,
+            null: None,
+            constant: false,
+            validations: Vec::new(),
             __no_construct: ()
         });
         field_specs.insert("field2".to_string(), FieldSpec {
             length: 5,
             padding: " ".as_bytes().to_owned(),
             padding_direction: PaddingDirection::Right,
+            trim_direction: TrimDirection::Right,
+            trim_set: None,
             default: Some("def".as_bytes().to_owned()),
+            length_prefix: None,
+            overflow: OverflowPolicy::Error,
+            null: None,
+            constant: false,
+            validations: Vec::new(),
             __no_construct: ()
         });
         field_specs.insert("field3".to_string(), FieldSpec {
             length: 36,
             padding: "xcvcxv".as_bytes().to_owned(),
             padding_direction: PaddingDirection::Right,
+            trim_direction: TrimDirection::Right,
+            trim_set: None,
             default: None,
+            length_prefix: None,
+            overflow: OverflowPolicy::Error,
+            null: None,
+            constant: false,
+            validations: Vec::new(),
             __no_construct: ()
         });
         record_specs.insert("record1".to_string(), RecordSpec {
             line_ending: "\n".as_bytes().to_owned(),
             field_specs: field_specs,
+            field_order: Vec::new(),
+            segments: Vec::new(),
+            separator: Vec::new(),
+            redefines: BTreeMap::new(),
+            bitmap: None,
+            groups: BTreeSet::new(),
+            rules: Vec::new(),
             __no_construct: ()
         });
         let mut field_specs = BTreeMap::new();
@@ -370,42 +1285,85 @@ mod test {
             length: 3,
             padding: "dsasd".as_bytes().to_owned(),
             padding_direction: PaddingDirection::Left,
+            trim_direction: TrimDirection::Left,
+            trim_set: None,
             default: None,
+            length_prefix: None,
+            overflow: OverflowPolicy::Error,
+            null: None,
+            constant: false,
+            validations: Vec::new(),
             __no_construct: ()
         });
         field_specs.insert("field2".to_string(), FieldSpec {
             length: 4,
             padding: "sdf".as_bytes().to_owned(),
             padding_direction: PaddingDirection::Right,
+            trim_direction: TrimDirection::Right,
+            trim_set: None,
             default: Some("defa".as_bytes().to_owned()),
+            length_prefix: None,
+            overflow: OverflowPolicy::Error,
+            null: None,
+            constant: false,
+            validations: Vec::new(),
             __no_construct: (),
         });
         field_specs.insert("field3".to_string(), FieldSpec {
             length: 27,
             padding: "xcvcxv".as_bytes().to_owned(),
             padding_direction: PaddingDirection::Right,
+            trim_direction: TrimDirection::Right,
+            trim_set: None,
             default: None,
+            length_prefix: None,
+            overflow: OverflowPolicy::Error,
+            null: None,
+            constant: false,
+            validations: Vec::new(),
             __no_construct: (),
         });
         field_specs.insert("field4".to_string(), FieldSpec {
             length: 8,
             padding: "sdfsd".as_bytes().to_owned(),
             padding_direction: PaddingDirection::Left,
+            trim_direction: TrimDirection::Left,
+            trim_set: None,
             default: None,
+            length_prefix: None,
+            overflow: OverflowPolicy::Error,
+            null: None,
+            constant: false,
+            validations: Vec::new(),
             __no_construct: (),
         });
         record_specs.insert("record2".to_string(), RecordSpec {
             line_ending: "\n".as_bytes().to_owned(),
             field_specs: field_specs,
+            field_order: Vec::new(),
+            segments: Vec::new(),
+            separator: Vec::new(),
+            redefines: BTreeMap::new(),
+            bitmap: None,
+            groups: BTreeSet::new(),
+            rules: Vec::new(),
             __no_construct: (),
         });
         record_specs.insert("record3".to_string(), RecordSpec {
             line_ending: "\n".as_bytes().to_owned(),
             field_specs: BTreeMap::new(),
+            field_order: Vec::new(),
+            segments: Vec::new(),
+            separator: Vec::new(),
+            redefines: BTreeMap::new(),
+            bitmap: None,
+            groups: BTreeSet::new(),
+            rules: Vec::new(),
             __no_construct: (),
         });
         assert_eq!(Spec {
             record_specs: record_specs,
+            rules: Vec::new(),
             __no_construct: (),
         }, spec);
         assert_eq!(FieldSpecBuilder::new()
@@ -441,4 +1399,227 @@ mod test {
         assert_eq!(42, spec.record_specs.get("record2").unwrap().len());
         assert_eq!(0, spec.record_specs.get("record3").unwrap().len());
     }
+    #[test]
+    fn segments() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\r\n")
+            .with_field("field1")
+                .string()
+                .with_length(3)
+            .end()
+            .with_segment()
+                .with_line_ending("\n")
+                .add_field("field2", FieldSpecBuilder::new().string().with_length(4))
+                .add_field("field3", FieldSpecBuilder::new().string().with_length(2))
+            .end()
+            .build()
+            .unwrap()
+        ;
+        assert_eq!(Some(0..3), record_spec.field_range("field1"));
+        assert_eq!(Some(5..9), record_spec.field_range("field2"));
+        assert_eq!(Some(9..11), record_spec.field_range("field3"));
+        assert_eq!(None, record_spec.field_range("field4"));
+        assert_eq!(Some(4), record_spec.field_spec("field2").map(|field_spec| field_spec.length));
+        assert_eq!(11, record_spec.len());
+        assert_eq!(record_spec.field_range("field3").map(|range| range.end), Some(record_spec.len()));
+        assert_eq!(vec![&b"\r\n"[..], &b"\n"[..]], record_spec.lines().map(|(_, line_ending)| line_ending).collect::<Vec<_>>());
+        assert_result!(
+            Err(Error::DuplicateField(ref name)) if name == "field1",
+            RecordSpecBuilder::new()
+                .add_field("field1", FieldSpecBuilder::new().string().with_length(3))
+                .add_segment(SegmentSpecBuilder::new().add_field("field1", FieldSpecBuilder::new().string().with_length(3)))
+                .build()
+        );
+        assert_result!(
+            Err(Error::SubBuilderErrors(ref errors)) if errors.contains_key("segment 1"),
+            RecordSpecBuilder::new()
+                .add_segment(SegmentSpecBuilder::new().add_field("field1", FieldSpecBuilder::new().string()))
+                .build()
+        );
+    }
+
+    #[test]
+    fn separator() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .with_separator("|")
+            .add_field("field1", FieldSpecBuilder::new().string().with_length(3))
+            .add_field("field2", FieldSpecBuilder::new().string().with_length(4))
+            .add_field("field3", FieldSpecBuilder::new().string().with_length(2))
+            .with_segment()
+                .with_line_ending("\n")
+                .add_field("field4", FieldSpecBuilder::new().string().with_length(2))
+                .add_field("field5", FieldSpecBuilder::new().string().with_length(2))
+            .end()
+            .build()
+            .unwrap()
+        ;
+        assert_eq!(Some(0..3), record_spec.field_range("field1"));
+        assert_eq!(Some(4..8), record_spec.field_range("field2"));
+        assert_eq!(Some(9..11), record_spec.field_range("field3"));
+        assert_eq!(Some(12..14), record_spec.field_range("field4"));
+        assert_eq!(Some(15..17), record_spec.field_range("field5"));
+        assert_eq!(17, record_spec.len());
+    }
+
+    #[test]
+    fn redefines() {
+        let record_spec = RecordSpecBuilder::new()
+            .add_field("field1", FieldSpecBuilder::new().string().with_length(1))
+            .add_field("field2", FieldSpecBuilder::new().string().with_length(5))
+            .with_redefine("field2", "field1")
+                .add_field("A", "field3", FieldSpecBuilder::new().string().with_length(5))
+                .add_field("B", "field4", FieldSpecBuilder::new().string().with_length(2))
+                .add_field("B", "field5", FieldSpecBuilder::new().number().with_length(3))
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let redefine = record_spec.redefines.get("field2").unwrap();
+        assert_eq!("field1", redefine.discriminator);
+        assert_eq!(Some(vec!["field3"]), redefine.alternative(b"A").map(|field_specs| field_specs.keys().map(|v| &v[..]).collect::<Vec<_>>()));
+        assert_eq!(Some(vec!["field4", "field5"]), redefine.alternative(b"B").map(|field_specs| field_specs.keys().map(|v| &v[..]).collect::<Vec<_>>()));
+        assert_eq!(None, redefine.alternative(b"C"));
+        let builder = RecordSpecBuilder::new()
+            .add_field("field1", FieldSpecBuilder::new().string().with_length(1))
+            .add_field("field2", FieldSpecBuilder::new().string().with_length(5))
+        ;
+        assert_result!(
+            Err(Error::FieldNotFound(ref name)) if name == "field3",
+            RecordSpecBuilder::new()
+                .add_field("field1", FieldSpecBuilder::new().string().with_length(1))
+                .add_redefine("field3", RedefineSpecBuilder::new("field1"))
+                .build()
+        );
+        assert_result!(
+            Err(Error::DiscriminatorNotBeforeField(ref name)) if name == "field1",
+            RecordSpecBuilder::new()
+                .add_field("field1", FieldSpecBuilder::new().string().with_length(1))
+                .add_field("field2", FieldSpecBuilder::new().string().with_length(5))
+                .add_redefine("field1", RedefineSpecBuilder::new("field2"))
+                .build()
+        );
+        assert_result!(
+            Err(Error::AlternativeWrongLength(ref name, 5, 4)) if name == "field2",
+            builder
+                .add_redefine("field2", RedefineSpecBuilder::new("field1")
+                    .add_field("A", "field3", FieldSpecBuilder::new().string().with_length(4))
+                )
+                .build()
+        );
+        assert_result!(
+            Err(Error::DuplicateField(ref name)) if name == "field1",
+            RecordSpecBuilder::new()
+                .add_field("field1", FieldSpecBuilder::new().string().with_length(1))
+                .add_field("field2", FieldSpecBuilder::new().string().with_length(1))
+                .add_redefine("field2", RedefineSpecBuilder::new("field1")
+                    .add_field("A", "field1", FieldSpecBuilder::new().string().with_length(1))
+                )
+                .build()
+        );
+    }
+
+    #[test]
+    fn bitmap() {
+        let record_spec = RecordSpecBuilder::new()
+            .add_field("bitmap", FieldSpecBuilder::new().string().with_length(2))
+            .add_field("field1", FieldSpecBuilder::new().string().with_length(2))
+            .add_field("field2", FieldSpecBuilder::new().llvar(10))
+            .add_field("field3", FieldSpecBuilder::new().string().with_length(3))
+            .add_field("a_field", FieldSpecBuilder::new().string().with_length(1))
+            .with_bitmap("bitmap", BitmapEncoding::Hex)
+                .add_field(2, "field1")
+                .add_field(3, "field2")
+                .add_field(12, "field3")
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let bitmap = record_spec.bitmap.as_ref().unwrap();
+        let field_spec = record_spec.field_spec("bitmap").unwrap();
+        assert_eq!(8, bitmap.bits_per_block(field_spec));
+        assert!(bitmap.controls("field2"));
+        assert!(!bitmap.controls("a_field"));
+        assert_eq!(b"60".to_vec(), bitmap.encode(&[2, 3], field_spec));
+        assert_eq!(b"C010".to_vec(), bitmap.encode(&[2, 12], field_spec));
+        assert_eq!(Some(vec![1, 2, 12]), bitmap.decode(b"C010"));
+        assert_eq!(Some(true), bitmap.has_secondary(b"C0"));
+        assert_eq!(Some(false), bitmap.has_secondary(b"60"));
+        assert_eq!(None, bitmap.decode(b"G0"));
+        assert_eq!(Some(vec![1, 8, 9]), BitmapSpecBuilder::new("bitmap", BitmapEncoding::Binary).build().unwrap().decode(&[0x81, 0x80]));
+        assert_eq!(Some(0..1), record_spec.field_range("a_field"));
+        assert_eq!(Some(1..3), record_spec.field_range("bitmap"));
+        assert_eq!(None, record_spec.field_range("field1"));
+        assert_result!(
+            Err(Error::InvalidBitmapBit(17)),
+            RecordSpecBuilder::new()
+                .add_field("bitmap", FieldSpecBuilder::new().string().with_length(2))
+                .add_field("field1", FieldSpecBuilder::new().string().with_length(2))
+                .with_bitmap("bitmap", BitmapEncoding::Hex)
+                    .add_field(17, "field1")
+                .end()
+                .build()
+        );
+        assert_result!(
+            Err(Error::FieldNotFound(ref name)) if name == "field1",
+            RecordSpecBuilder::new()
+                .add_field("bitmap", FieldSpecBuilder::new().string().with_length(2))
+                .with_bitmap("bitmap", BitmapEncoding::Hex)
+                    .add_field(2, "field1")
+                .end()
+                .build()
+        );
+    }
+
+    #[test]
+    fn groups() {
+        let record_spec = RecordSpecBuilder::new()
+            .add_field("id", FieldSpecBuilder::new().string().with_length(2))
+            .with_group("address")
+                .add_field("street", FieldSpecBuilder::new().string().with_length(4))
+                .add_group("geo", GroupSpecBuilder::new()
+                    .add_field("lat", FieldSpecBuilder::new().string().with_length(2))
+                )
+            .end()
+            .build()
+            .unwrap()
+        ;
+        assert_eq!(vec!["address".to_string(), "address.geo".to_string()], record_spec.groups.iter().cloned().collect::<Vec<_>>());
+        assert_eq!(Some(0..2), record_spec.field_range("address.geo.lat"));
+        assert_eq!(Some(2..6), record_spec.field_range("address.street"));
+        assert_result!(
+            Err(Error::DuplicateField(ref name)) if name == "address",
+            RecordSpecBuilder::new()
+                .add_field("address", FieldSpecBuilder::new().string().with_length(2))
+                .with_group("address")
+                    .add_field("street", FieldSpecBuilder::new().string().with_length(4))
+                .end()
+                .build()
+        );
+        assert_result!(
+            Err(Error::SubBuilderErrors(ref errors)) if errors.contains_key("address.street"),
+            RecordSpecBuilder::new()
+                .with_group("address")
+                    .add_field("street", FieldSpecBuilder::new().string())
+                .end()
+                .build()
+        );
+    }
+
+    #[test]
+    fn rules() {
+        let builder = || RecordSpecBuilder::new()
+            .add_field("start", FieldSpecBuilder::new().string().with_length(8))
+            .add_field("end", FieldSpecBuilder::new().string().with_length(8))
+        ;
+        assert!(builder().add_rule(RecordRule::Ordered { first: "start".to_string(), second: "end".to_string() }).build().is_ok());
+        assert_result!(
+            Err(Error::FieldNotFound(ref name)) if name == "finish",
+            builder().add_rule(RecordRule::Ordered { first: "start".to_string(), second: "finish".to_string() }).build()
+        );
+        assert_result!(
+            Err(Error::FieldNotFound(ref name)) if name == "email",
+            builder().add_rule(RecordRule::AnyPopulated(vec!["email".to_string()])).build()
+        );
+    }
 }
@@ -1,7 +1,72 @@
 use std::borrow::Borrow;
-use ::BoxedErrorResult as Result;
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Error as FmtError};
+use std::io::Error as IoError;
 use spec::RecordSpec;
+use error::BoxedError;
+
+type Result<T> = ::std::result::Result<T, StreamError>;
+
+#[derive(Debug)]
+pub enum StreamError {
+    UnknownRecord(String),
+    AmbiguousRecord(Vec<String>),
+    IdFieldNotLocatable(String, String),
+    IoError(IoError),
+    Other(BoxedError)
+}
+
+impl StreamError {
+    pub fn ambiguous(first: &str, second: &str) -> Self {
+        let mut names = vec![first.to_string(), second.to_string()];
+        names.sort();
+        StreamError::AmbiguousRecord(names)
+    }
+}
+
+impl ::std::error::Error for StreamError {
+    fn description(&self) -> &str {
+        match *self {
+            StreamError::UnknownRecord(_) => "The stream returned a record name for which no record spec exists",
+            StreamError::AmbiguousRecord(_) => "More than one record spec matches the record",
+            StreamError::IdFieldNotLocatable(_, _) => "The id field of a record spec has no fixed position",
+            StreamError::IoError(_) => "An IO error occurred while trying to determine the record spec",
+            StreamError::Other(_) => "The record spec stream encountered an error"
+        }
+    }
+
+    fn cause(&self) -> Option<&::std::error::Error> {
+        match *self {
+            StreamError::IoError(ref e) => Some(e),
+            StreamError::Other(ref e) => Some(&**e),
+            _ => None
+        }
+    }
+}
+
+impl Display for StreamError {
+    fn fmt(&self, f: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+        match *self {
+            StreamError::UnknownRecord(ref name) => write!(f, "There is no record spec named {}", name),
+            StreamError::AmbiguousRecord(ref names) => write!(f, "The record matches the record specs {}", names.join(", ")),
+            StreamError::IdFieldNotLocatable(ref record, ref field) => write!(f, "The id field {} of the record spec {} has no fixed position", field, record),
+            StreamError::IoError(ref e) => write!(f, "An IO error occurred while trying to determine the record spec: {}", e),
+            StreamError::Other(ref e) => write!(f, "{}", e)
+        }
+    }
+}
+
+impl From<IoError> for StreamError {
+    fn from(error: IoError) -> Self {
+        StreamError::IoError(error)
+    }
+}
+
+impl From<BoxedError> for StreamError {
+    fn from(error: BoxedError) -> Self {
+        StreamError::Other(error)
+    }
+}
 
 pub struct VecStream<T: Borrow<str>> {
     vec: Vec<T>,
@@ -23,14 +88,10 @@ impl<T: Borrow<str>> VecStream<T> {
 
         Ok(match self.vec.get(self.position - 1).map(|v| v.borrow()) {
             None => None,
-            Some(v) => {
-                for (name, _) in record_specs.iter() {
-                    if name == v {
-                        return Ok(Some(name));
-                    }
-                }
-                return Err("The nes".into())
-            }
+            Some(v) => Some(record_specs.keys()
+                .find(|name| *name == v)
+                .ok_or_else(|| StreamError::UnknownRecord(v.to_string()))?
+            )
         })
     }
 }
@@ -54,5 +115,8 @@ mod test {
         ;
         assert_result!(Ok(Some("record1")), stream.next(&specs));
         assert_result!(Ok(Some("record2")), stream.next(&specs));
+        assert_result!(Ok(None), stream.next(&specs));
+        let mut stream = VecStream::from(vec!["record3"]);
+        assert_result!(Err(StreamError::UnknownRecord(ref name)) if name == "record3", stream.next(&specs));
     }
 }
\ No newline at end of file
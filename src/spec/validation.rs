@@ -0,0 +1,482 @@
+extern crate regex;
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter, Error as FmtError};
+use std::sync::Arc;
+use error::Error;
+pub use self::regex::bytes::Regex;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CharacterClass {
+    Alphanumeric,
+    Digits,
+    PrintableAscii
+}
+
+impl CharacterClass {
+    pub fn contains(&self, byte: u8) -> bool {
+        match *self {
+            CharacterClass::Alphanumeric => (byte as char).is_ascii_alphanumeric(),
+            CharacterClass::Digits => (byte as char).is_ascii_digit(),
+            CharacterClass::PrintableAscii => byte >= 0x20 && byte < 0x7F
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ValidationRule {
+    Required,
+    Pattern(Regex),
+    OneOf(Vec<Vec<u8>>),
+    Range { min: Option<i64>, max: Option<i64> },
+    MinLength(usize),
+    MaxLength(usize),
+    CharacterClass(CharacterClass)
+}
+
+impl ValidationRule {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ValidationRule::Required => "required",
+            ValidationRule::Pattern(_) => "pattern",
+            ValidationRule::OneOf(_) => "one_of",
+            ValidationRule::Range { .. } => "range",
+            ValidationRule::MinLength(_) => "min_length",
+            ValidationRule::MaxLength(_) => "max_length",
+            ValidationRule::CharacterClass(_) => "character_class"
+        }
+    }
+
+    // Every rule is checked against the value with leading and trailing ASCII whitespace removed, so
+    // the outcome is the same whether or not the field parser already removed the padding. This
+    // changes the value rules see: " ab " passes `OneOf(["ab"])` and has a `MaxLength` of 2.
+    pub fn is_valid<'a>(&self, value: Option<&'a [u8]>) -> bool {
+        let value = match (value, self) {
+            (Some(value), _) => trim(value),
+            (None, &ValidationRule::Required) => return false,
+            (None, _) => return true
+        };
+
+        match *self {
+            ValidationRule::Required => !value.is_empty(),
+            ValidationRule::Pattern(ref regex) => regex.is_match(value),
+            ValidationRule::OneOf(ref values) => values.iter().any(|allowed| &allowed[..] == value),
+            ValidationRule::Range { min, max } => parse_number(value)
+                .map_or(false, |number| min.map_or(true, |min| number >= min) && max.map_or(true, |max| number <= max)),
+            ValidationRule::MinLength(length) => value.len() >= length,
+            ValidationRule::MaxLength(length) => value.len() <= length,
+            ValidationRule::CharacterClass(class) => value.iter().all(|byte| class.contains(*byte))
+        }
+    }
+}
+
+impl PartialEq for ValidationRule {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (&ValidationRule::Required, &ValidationRule::Required) => true,
+            (&ValidationRule::Pattern(ref left), &ValidationRule::Pattern(ref right)) => left.as_str() == right.as_str(),
+            (&ValidationRule::OneOf(ref left), &ValidationRule::OneOf(ref right)) => left == right,
+            (&ValidationRule::Range { min: left_min, max: left_max }, &ValidationRule::Range { min: right_min, max: right_max }) => left_min == right_min && left_max == right_max,
+            (&ValidationRule::MinLength(left), &ValidationRule::MinLength(right)) => left == right,
+            (&ValidationRule::MaxLength(left), &ValidationRule::MaxLength(right)) => left == right,
+            (&ValidationRule::CharacterClass(left), &ValidationRule::CharacterClass(right)) => left == right,
+            _ => false
+        }
+    }
+}
+
+impl Eq for ValidationRule {}
+
+impl Display for ValidationRule {
+    fn fmt(&self, f: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+        match *self {
+            ValidationRule::Pattern(ref regex) => write!(f, "{} {}", self.name(), regex.as_str()),
+            ValidationRule::Range { min, max } => write!(
+                f,
+                "{} {}..{}",
+                self.name(),
+                min.map(|min| min.to_string()).unwrap_or_default(),
+                max.map(|max| max.to_string()).unwrap_or_default()
+            ),
+            ValidationRule::MinLength(length) | ValidationRule::MaxLength(length) => write!(f, "{} {}", self.name(), length),
+            ValidationRule::CharacterClass(class) => write!(f, "{} {:?}", self.name(), class),
+            _ => write!(f, "{}", self.name())
+        }
+    }
+}
+
+pub fn validate<'a>(rules: &'a [ValidationRule], value: Option<&'a [u8]>) -> Result<(), Error> {
+    match rules.iter().find(|rule| !rule.is_valid(value)) {
+        Some(rule) => Err(Error::ValidationFailed(rule.clone(), value.map(|value| value.to_vec()))),
+        None => Ok(())
+    }
+}
+
+pub trait RecordValues {
+    fn get<'a>(&'a self, name: &'a str) -> Option<&'a [u8]>;
+}
+
+#[derive(Clone)]
+pub enum RecordRule {
+    Ordered { first: String, second: String },
+    SignMatches { amount: String, code: String, negative: Vec<u8>, positive: Vec<u8> },
+    AnyPopulated(Vec<String>),
+    Custom { name: String, fields: Vec<String>, check: Arc<Fn(&RecordValues) -> bool + Send + Sync> }
+}
+
+impl RecordRule {
+    pub fn custom<T, U, V>(name: T, fields: Vec<U>, check: V) -> Self
+        where T: Into<String>,
+              U: Into<String>,
+              V: Fn(&RecordValues) -> bool + Send + Sync + 'static
+    {
+        RecordRule::Custom {
+            name: name.into(),
+            fields: fields.into_iter().map(Into::into).collect(),
+            check: Arc::new(check)
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match *self {
+            RecordRule::Ordered { .. } => "ordered",
+            RecordRule::SignMatches { .. } => "sign_matches",
+            RecordRule::AnyPopulated(_) => "any_populated",
+            RecordRule::Custom { ref name, .. } => name
+        }
+    }
+
+    pub fn fields(&self) -> Vec<String> {
+        match *self {
+            RecordRule::Ordered { ref first, ref second } => vec![first.clone(), second.clone()],
+            RecordRule::SignMatches { ref amount, ref code, .. } => vec![amount.clone(), code.clone()],
+            RecordRule::AnyPopulated(ref fields) | RecordRule::Custom { ref fields, .. } => fields.clone()
+        }
+    }
+
+    pub fn is_valid<'a>(&self, values: &'a RecordValues) -> bool {
+        match *self {
+            RecordRule::Ordered { ref first, ref second } => match (values.get(first).map(trim), values.get(second).map(trim)) {
+                (Some(first), Some(second)) => match (parse_number(first), parse_number(second)) {
+                    (Some(first), Some(second)) => first <= second,
+                    _ => first <= second
+                },
+                _ => true
+            },
+            RecordRule::SignMatches { ref amount, ref code, ref negative, ref positive } => match (values.get(amount).map(trim), values.get(code).map(trim)) {
+                (Some(amount), Some(code)) => {
+                    let is_negative = amount.first() == Some(&b'-') || amount.last() == Some(&b'-');
+
+                    (code != &negative[..] || is_negative) && (code != &positive[..] || !is_negative)
+                },
+                _ => true
+            },
+            RecordRule::AnyPopulated(ref fields) => fields.iter().any(|field| values.get(field).map_or(false, |value| !trim(value).is_empty())),
+            RecordRule::Custom { ref check, .. } => check(values)
+        }
+    }
+}
+
+impl Debug for RecordRule {
+    fn fmt(&self, f: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+        write!(f, "RecordRule {{ name: {:?}, fields: {:?} }}", self.name(), self.fields())
+    }
+}
+
+impl PartialEq for RecordRule {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                &RecordRule::Ordered { first: ref left_first, second: ref left_second },
+                &RecordRule::Ordered { first: ref right_first, second: ref right_second }
+            ) => left_first == right_first && left_second == right_second,
+            (
+                &RecordRule::SignMatches { amount: ref left_amount, code: ref left_code, negative: ref left_negative, positive: ref left_positive },
+                &RecordRule::SignMatches { amount: ref right_amount, code: ref right_code, negative: ref right_negative, positive: ref right_positive }
+            ) => left_amount == right_amount && left_code == right_code && left_negative == right_negative && left_positive == right_positive,
+            (&RecordRule::AnyPopulated(ref left), &RecordRule::AnyPopulated(ref right)) => left == right,
+            (&RecordRule::Custom { check: ref left, .. }, &RecordRule::Custom { check: ref right, .. }) => Arc::ptr_eq(left, right),
+            _ => false
+        }
+    }
+}
+
+impl Eq for RecordRule {}
+
+pub fn check_record<'a>(rules: &'a [RecordRule], values: &'a RecordValues) -> Result<(), Error> {
+    match rules.iter().find(|rule| !rule.is_valid(values)) {
+        Some(rule) => Err(Error::RecordRuleFailed(rule.name().to_string(), rule.fields())),
+        None => Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum FileRule {
+    UniqueKey { record: String, fields: Vec<String> },
+    References { record: String, field: String, target_record: String, target_field: String },
+    Sequence { record: String, field: String }
+}
+
+impl FileRule {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            FileRule::UniqueKey { .. } => "unique_key",
+            FileRule::References { .. } => "references",
+            FileRule::Sequence { .. } => "sequence"
+        }
+    }
+
+    pub fn fields(&self) -> Vec<String> {
+        match *self {
+            FileRule::UniqueKey { ref fields, .. } => fields.clone(),
+            FileRule::References { ref field, .. } | FileRule::Sequence { ref field, .. } => vec![field.clone()]
+        }
+    }
+
+    pub fn record_fields(&self) -> Vec<(String, String)> {
+        match *self {
+            FileRule::UniqueKey { ref record, ref fields } => fields.iter().map(|field| (record.clone(), field.clone())).collect(),
+            FileRule::References { ref record, ref field, ref target_record, ref target_field } => vec![
+                (record.clone(), field.clone()),
+                (target_record.clone(), target_field.clone())
+            ],
+            FileRule::Sequence { ref record, ref field } => vec![(record.clone(), field.clone())]
+        }
+    }
+
+    fn error(&self, other: Option<(String, usize)>) -> Error {
+        Error::FileRuleFailed(self.name().to_string(), self.fields(), other)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct FileRuleState {
+    seen: HashMap<Vec<Vec<u8>>, usize>,
+    last: Option<(i64, usize)>,
+    last_target: Option<usize>
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FileValidator {
+    pub rules: Vec<FileRule>,
+    states: Vec<FileRuleState>
+}
+
+impl FileValidator {
+    pub fn new(rules: Vec<FileRule>) -> Self {
+        FileValidator {
+            states: rules.iter().map(|_| FileRuleState::default()).collect(),
+            rules: rules
+        }
+    }
+
+    pub fn check<'a>(&mut self, record: &'a str, values: &'a RecordValues, line: usize) -> Result<(), Vec<Error>> {
+        let errors: Vec<Error> = self.rules.iter()
+            .zip(self.states.iter_mut())
+            .filter_map(|(rule, state)| state.check(rule, record, values, line).err())
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl FileRuleState {
+    fn check<'a>(&mut self, rule: &'a FileRule, record: &'a str, values: &'a RecordValues, line: usize) -> Result<(), Error> {
+        match *rule {
+            FileRule::UniqueKey { record: ref rule_record, ref fields } if rule_record == record => {
+                let key: Vec<Vec<u8>> = match fields.iter().map(|field| values.get(field).map(|value| value.to_vec())).collect() {
+                    Some(key) => key,
+                    None => return Ok(())
+                };
+
+                if let Some(first_line) = self.seen.get(&key) {
+                    return Err(rule.error(Some((record.to_string(), *first_line))));
+                }
+
+                self.seen.insert(key, line);
+            },
+            FileRule::References { record: ref rule_record, ref field, ref target_record, ref target_field } => {
+                if target_record == record {
+                    self.last_target = Some(line);
+
+                    if let Some(value) = values.get(target_field) {
+                        self.seen.insert(vec![value.to_vec()], line);
+                    }
+                }
+
+                if rule_record == record {
+                    if let Some(value) = values.get(field) {
+                        if !self.seen.contains_key(&vec![value.to_vec()]) {
+                            return Err(rule.error(self.last_target.map(|last_target| (target_record.clone(), last_target))));
+                        }
+                    }
+                }
+            },
+            FileRule::Sequence { record: ref rule_record, ref field } if rule_record == record => {
+                let value = values.get(field).map(trim).and_then(parse_number);
+                let last = self.last.take();
+                self.last = value.map(|value| (value, line));
+
+                match (value, last) {
+                    (None, _) => return Err(rule.error(None)),
+                    (Some(value), Some((last, last_line))) if value != last + 1 => return Err(rule.error(Some((record.to_string(), last_line)))),
+                    _ => ()
+                }
+            },
+            _ => ()
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_number(value: &[u8]) -> Option<i64> {
+    ::std::str::from_utf8(value).ok().and_then(|number| number.parse().ok())
+}
+
+fn trim(value: &[u8]) -> &[u8] {
+    let start = value.iter().position(|byte| !byte.is_ascii_whitespace()).unwrap_or(value.len());
+    let end = value.iter().rposition(|byte| !byte.is_ascii_whitespace()).map_or(start, |index| index + 1);
+
+    &value[start..end]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use error::Error;
+    use record::Data;
+    use std::collections::BTreeMap;
+    use std::ops::Range;
+
+    #[test]
+    fn validation_rules() {
+        assert!(!ValidationRule::Required.is_valid(None));
+        assert!(!ValidationRule::Required.is_valid(Some(b"  ")));
+        assert!(ValidationRule::Required.is_valid(Some(b" a ")));
+        assert!(ValidationRule::MaxLength(3).is_valid(None));
+        assert!(ValidationRule::Pattern(Regex::new("^[A-Z]{2}$").unwrap()).is_valid(Some(b"AB")));
+        assert!(!ValidationRule::Pattern(Regex::new("^[A-Z]{2}$").unwrap()).is_valid(Some(b"Ab")));
+        assert!(ValidationRule::OneOf(vec![b"A".to_vec(), b"B".to_vec()]).is_valid(Some(b"B")));
+        assert!(!ValidationRule::OneOf(vec![b"A".to_vec(), b"B".to_vec()]).is_valid(Some(b"C")));
+        assert!(ValidationRule::Range { min: Some(-5), max: Some(10) }.is_valid(Some(b" -5")));
+        assert!(!ValidationRule::Range { min: Some(-5), max: Some(10) }.is_valid(Some(b"11")));
+        assert!(!ValidationRule::Range { min: None, max: None }.is_valid(Some(b"1.5")));
+        assert!(ValidationRule::MinLength(2).is_valid(Some(b"ab  ")));
+        assert!(!ValidationRule::MinLength(3).is_valid(Some(b"ab  ")));
+        assert!(!ValidationRule::MaxLength(1).is_valid(Some(b"ab  ")));
+        assert!(ValidationRule::CharacterClass(CharacterClass::Alphanumeric).is_valid(Some(b"ab12")));
+        assert!(!ValidationRule::CharacterClass(CharacterClass::Alphanumeric).is_valid(Some(b"ab 12")));
+        assert!(!ValidationRule::CharacterClass(CharacterClass::Digits).is_valid(Some(b"12a")));
+        assert!(!ValidationRule::CharacterClass(CharacterClass::PrintableAscii).is_valid(Some(b"a\tb")));
+        assert!(ValidationRule::Pattern(Regex::new("^[A-Z]{2}$").unwrap()).is_valid(Some(b"AB  ")));
+        assert!(ValidationRule::OneOf(vec![b"A".to_vec(), b"B".to_vec()]).is_valid(Some(b"  B")));
+        assert!(ValidationRule::CharacterClass(CharacterClass::Digits).is_valid(Some(b"12  ")));
+        assert!(ValidationRule::OneOf(vec!["\u{e0}".into()]).is_valid(Some("\u{e0}".as_bytes())));
+        assert!(ValidationRule::MinLength(3).is_valid(Some(" \u{3060} ".as_bytes())));
+        assert!(ValidationRule::Pattern(Regex::new("^\u{3060}$").unwrap()).is_valid(Some("\u{3060}".as_bytes())));
+        assert_eq!(ValidationRule::Pattern(Regex::new("a+").unwrap()), ValidationRule::Pattern(Regex::new("a+").unwrap()));
+        assert_result!(
+            Err(Error::ValidationFailed(ValidationRule::MaxLength(1), Some(ref value))) if *value == b"ab".to_vec(),
+            validate(&[ValidationRule::Required, ValidationRule::MaxLength(1)], Some(b"ab"))
+        );
+        assert_result!(Ok(()), validate(&[ValidationRule::Required, ValidationRule::MaxLength(2)], Some(b"ab")));
+    }
+
+    #[test]
+    fn record_rules() {
+        let values = |pairs: &[(&str, &str)]| pairs.iter().fold(Data::<BTreeMap<String, Range<usize>>, Vec<u8>>::new(), |mut data, &(name, value)| {
+            data.push(name, value);
+            data
+        });
+        let ordered = RecordRule::Ordered { first: "start".to_string(), second: "end".to_string() };
+        assert!(ordered.is_valid(&values(&[("start", "20200101"), ("end", "20200131")])));
+        assert!(!ordered.is_valid(&values(&[("start", "20200201"), ("end", "20200131")])));
+        assert!(ordered.is_valid(&values(&[("start", " 9"), ("end", "10")])));
+        assert!(ordered.is_valid(&values(&[("start", "20200201")])));
+        let sign = RecordRule::SignMatches { amount: "amount".to_string(), code: "code".to_string(), negative: b"D".to_vec(), positive: b"C".to_vec() };
+        assert!(sign.is_valid(&values(&[("amount", "-12"), ("code", "D")])));
+        assert!(sign.is_valid(&values(&[("amount", "12-"), ("code", "D")])));
+        assert!(!sign.is_valid(&values(&[("amount", "12"), ("code", "D")])));
+        assert!(!sign.is_valid(&values(&[("amount", "-12"), ("code", "C")])));
+        let populated = RecordRule::AnyPopulated(vec!["email".to_string(), "phone".to_string()]);
+        assert!(populated.is_valid(&values(&[("email", "   "), ("phone", "555")])));
+        assert!(!populated.is_valid(&values(&[("email", "   ")])));
+        let custom = RecordRule::custom("same_length", vec!["a", "b"], |values: &RecordValues| values.get("a").map(|v| v.len()) == values.get("b").map(|v| v.len()));
+        assert!(custom.is_valid(&values(&[("a", "12"), ("b", "34")])));
+        assert_eq!(custom, custom.clone());
+        assert_result!(
+            Err(Error::RecordRuleFailed(ref name, ref fields)) if name == "same_length" && *fields == vec!["a".to_string(), "b".to_string()],
+            check_record(&[ordered, custom], &values(&[("a", "1"), ("b", "34")]))
+        );
+    }
+
+    #[test]
+    fn file_rules() {
+        let values = |pairs: &[(&str, &str)]| pairs.iter().fold(Data::<BTreeMap<String, Range<usize>>, Vec<u8>>::new(), |mut data, &(name, value)| {
+            data.push(name, value);
+            data
+        });
+        let single = |result: Result<(), Vec<Error>>| result.map_err(|mut errors| {
+            assert_eq!(1, errors.len());
+            errors.remove(0)
+        });
+        let mut validator = FileValidator::new(vec![
+            FileRule::UniqueKey { record: "detail".to_string(), fields: vec!["batch".to_string(), "key".to_string()] },
+            FileRule::References { record: "detail".to_string(), field: "batch".to_string(), target_record: "batch".to_string(), target_field: "batch".to_string() },
+            FileRule::Sequence { record: "detail".to_string(), field: "seq".to_string() }
+        ]);
+        assert_result!(
+            Err(Error::FileRuleFailed(ref rule, _, None)) if rule == "references",
+            single(validator.check("detail", &values(&[("batch", "1"), ("key", "a"), ("seq", "1")]), 1))
+        );
+        assert_result!(Ok(()), validator.check("batch", &values(&[("batch", "1")]), 2));
+        assert_result!(Ok(()), validator.check("detail", &values(&[("batch", "1"), ("key", "b"), ("seq", "2")]), 3));
+        assert_result!(Ok(()), validator.check("detail", &values(&[("batch", "1"), ("key", "c"), ("seq", "3")]), 4));
+        assert_result!(
+            Err(Error::FileRuleFailed(ref rule, ref fields, Some((ref record, 3)))) if rule == "unique_key" && record == "detail" && *fields == vec!["batch".to_string(), "key".to_string()],
+            single(validator.check("detail", &values(&[("batch", "1"), ("key", "b"), ("seq", "4")]), 5))
+        );
+        assert_result!(
+            Err(Error::FileRuleFailed(ref rule, _, Some((_, 5)))) if rule == "sequence",
+            single(validator.check("detail", &values(&[("batch", "1"), ("key", "d"), ("seq", "6")]), 6))
+        );
+        assert_result!(
+            Err(Error::FileRuleFailed(ref rule, _, None)) if rule == "sequence",
+            single(validator.check("detail", &values(&[("batch", "1"), ("key", "e"), ("seq", "x")]), 7))
+        );
+        assert_result!(
+            Err(Error::FileRuleFailed(ref rule, _, Some((ref record, 2)))) if rule == "references" && record == "batch",
+            single(validator.check("detail", &values(&[("batch", "2"), ("key", "f"), ("seq", "8")]), 8))
+        );
+        let mut data = values(&[("batch", "1"), ("seq", "9")]);
+        data.push_null("key");
+        assert_result!(Ok(()), validator.check("detail", &data, 9));
+        let mut data = values(&[("batch", "1"), ("seq", "10")]);
+        data.push_null("key");
+        assert_result!(Ok(()), validator.check("detail", &data, 10));
+    }
+
+    #[test]
+    fn file_rules_report_every_failure() {
+        let mut data = Data::<BTreeMap<String, Range<usize>>, Vec<u8>>::new();
+        data.push("batch", "1");
+        data.push("seq", "x");
+        let mut validator = FileValidator::new(vec![
+            FileRule::References { record: "detail".to_string(), field: "batch".to_string(), target_record: "batch".to_string(), target_field: "batch".to_string() },
+            FileRule::Sequence { record: "detail".to_string(), field: "seq".to_string() }
+        ]);
+        let errors = validator.check("detail", &data, 1).unwrap_err();
+        assert_eq!(
+            vec!["references", "sequence"],
+            errors.iter().map(|e| match *e {
+                Error::FileRuleFailed(ref rule, _, None) => rule.as_str(),
+                ref e => panic!("unexpected error {:?}", e)
+            }).collect::<Vec<_>>()
+        );
+    }
+}
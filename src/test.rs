@@ -3,10 +3,11 @@ use std::collections::HashMap;
 use std::io::BufRead;
 use record::{Data, DataRanges};
 use data_type::{FieldReadSupport, WriteSupport};
-use writer::formatter::FieldFormatter;
-use reader::parser::FieldParser;
+use writer::formatter::{FieldFormatter, FormatError};
+use reader::parser::{FieldParser, ParseError};
 use reader::spec::{Resolver as ReaderResolver, RequiresBufRead};
 use writer::spec::Resolver as WriterResolver;
+use spec::stream::StreamError;
 use super::BoxedErrorResult as Result;
 
 #[derive(Debug)]
@@ -37,7 +38,7 @@ impl<'a> MockResolver<'a> {
 impl<'a, T: FieldReadSupport> RequiresBufRead<T> for MockResolver<'a> {}
 
 impl<'a, U: FieldReadSupport> ReaderResolver<U> for MockResolver<'a> {
-    fn resolve<'b, 'c, V: BufRead + 'b>(&self, _: &'b mut V, record_specs: &'c HashMap<String, RecordSpec>, _: &'b U) -> Result<Option<&'c str>> {
+    fn resolve<'b, 'c, V: BufRead + 'b>(&self, _: &'b mut V, record_specs: &'c HashMap<String, RecordSpec>, _: &'b U) -> ::std::result::Result<Option<&'c str>, StreamError> {
         for &(ref expected_record_specs, ref return_value) in &self.line_recognize_calls {
             if *expected_record_specs as *const HashMap<String, RecordSpec> == record_specs as *const HashMap<String, RecordSpec>
             {
@@ -52,7 +53,7 @@ impl<'a, U: FieldReadSupport> ReaderResolver<U> for MockResolver<'a> {
                         panic!("key {:?} not found in {:?}");
                     },
                     Ok(None) => Ok(None),
-                    Err(_) => Err("".into())
+                    Err(_) => Err(StreamError::Other("".into()))
                 }
             }
         }
@@ -62,7 +63,7 @@ impl<'a, U: FieldReadSupport> ReaderResolver<U> for MockResolver<'a> {
 }
 
 impl<'a, V: WriteSupport> WriterResolver<V> for MockResolver<'a> {
-    fn resolve<'b, 'c, W: DataRanges + 'b>(&self, _: &'b Data<W, V::DataHolder>, record_specs: &'c HashMap<String, RecordSpec>, _: &'b V) -> Result<Option<&'c str>> {
+    fn resolve<'b, 'c, W: DataRanges + 'b>(&self, _: &'b Data<W, V::DataHolder>, record_specs: &'c HashMap<String, RecordSpec>, _: &'b V) -> ::std::result::Result<Option<&'c str>, StreamError> {
         for &(ref expected_record_specs, ref return_value) in &self.data_recognize_calls {
             if *expected_record_specs as *const HashMap<String, RecordSpec> == record_specs as *const HashMap<String, RecordSpec>
                 {
@@ -77,7 +78,7 @@ impl<'a, V: WriteSupport> WriterResolver<V> for MockResolver<'a> {
                             panic!("key {:?} not found in {:?}");
                         },
                         Ok(None) => Ok(None),
-                        Err(_) => Err("".into())
+                        Err(_) => Err(StreamError::Other("".into()))
                     }
                 }
         }
@@ -105,7 +106,7 @@ impl MockFormatter {
 }
 
 impl<T: WriteSupport> FieldFormatter<T> for MockFormatter {
-    fn format<'a>(&self, data: &'a [u8], field_spec: &'a FieldSpec, destination: &'a mut Vec<u8>, _: &'a T) -> Result<()> {
+    fn format<'a>(&self, data: &'a [u8], field_spec: &'a FieldSpec, destination: &'a mut Vec<u8>, _: &'a T) -> ::std::result::Result<(), FormatError> {
         for &(ref expected_data, ref expected_field_spec, ref return_value) in &self.format_calls {
             if *expected_data == data && expected_field_spec == field_spec {
                 return match *return_value {
@@ -113,7 +114,7 @@ impl<T: WriteSupport> FieldFormatter<T> for MockFormatter {
                         destination.extend(value.iter());
                         Ok(())
                     },
-                    Err(_) => Err("".into())
+                    Err(_) => Err(FormatError::Other("".into()))
                 };
             }
         }
@@ -141,7 +142,7 @@ impl MockParser {
 }
 
 impl<T: FieldReadSupport> FieldParser<T> for MockParser {
-    fn parse<'a>(&self, data: &'a [u8], field_spec: &'a FieldSpec, destination: &'a mut Vec<u8>, _: &'a T) -> Result<()> {
+    fn parse<'a>(&self, data: &'a [u8], field_spec: &'a FieldSpec, destination: &'a mut Vec<u8>, _: &'a T) -> ::std::result::Result<(), ParseError> {
         for &(ref expected_data, ref expected_field_spec, ref return_value) in &self.parse_calls {
             if *expected_data == data
                 && expected_field_spec == field_spec {
@@ -150,7 +151,7 @@ impl<T: FieldReadSupport> FieldParser<T> for MockParser {
                         destination.extend(value.iter());
                         Ok(())
                     },
-                    Err(_) => Err("".into())
+                    Err(_) => Err(ParseError::Other("".into()))
                 };
             }
         }
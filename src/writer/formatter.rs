@@ -1,8 +1,11 @@
-use spec::PaddingDirection;
+use spec::{PaddingDirection, OverflowPolicy};
 use std::fmt::{Display, Formatter, Error as FmtError};
-use data_type::{WriteSupport, BinarySupport};
+use data_type::{WriteSupport, BinarySupport, DisplayWidthSupport};
 use spec::FieldSpec;
-use super::super::BoxedErrorResult as Result;
+use std::str::Utf8Error;
+use error::BoxedError;
+
+type Result<T> = ::std::result::Result<T, FormatError>;
 
 pub trait FieldFormatter<T: WriteSupport> {
     fn format<'a>(&self, data: &'a [u8], field_spec: &'a FieldSpec, destination: &'a mut Vec<u8>, write_support: &'a T) -> Result<()>;
@@ -17,14 +20,28 @@ impl<'a, T, U: WriteSupport> FieldFormatter<U> for &'a T where T: FieldFormatter
 #[derive(Debug)]
 pub enum FormatError {
     DataSplitNotOnCharBoundary(usize),
-    PaddingSplitNotOnCharBoundary(usize)
+    PaddingSplitNotOnCharBoundary(usize),
+    ValueTooLong(usize, Vec<u8>),
+    InvalidUtf8(Utf8Error),
+    Other(BoxedError)
 }
 
 impl ::std::error::Error for FormatError {
     fn description(&self) -> &str {
         match *self {
             FormatError::DataSplitNotOnCharBoundary(_) => "The index needed for splitting the data is not on a char boundary",
-            FormatError::PaddingSplitNotOnCharBoundary(_) => "The index needed for splitting the padding is not on a char boundary"
+            FormatError::PaddingSplitNotOnCharBoundary(_) => "The index needed for splitting the padding is not on a char boundary",
+            FormatError::ValueTooLong(_, _) => "The value is longer than the field allows",
+            FormatError::InvalidUtf8(_) => "The value or padding is not valid utf8",
+            FormatError::Other(_) => "The field formatter encountered an error"
+        }
+    }
+
+    fn cause(&self) -> Option<&::std::error::Error> {
+        match *self {
+            FormatError::InvalidUtf8(ref e) => Some(e),
+            FormatError::Other(ref e) => Some(&**e),
+            _ => None
         }
     }
 }
@@ -41,31 +58,160 @@ impl Display for FormatError {
                 f,
                 "The index {} needed for splitting the padding is not on a char boundary",
                 index
-            )
+            ),
+            FormatError::ValueTooLong(length, ref value) => write!(
+                f,
+                "The value {:?} is {} long but the field allows at most {}",
+                String::from_utf8_lossy(value),
+                value.len(),
+                length
+            ),
+            FormatError::InvalidUtf8(ref e) => write!(f, "The value or padding is not valid utf8: {}", e),
+            FormatError::Other(ref e) => write!(f, "{}", e)
         }
     }
 }
 
+impl From<Utf8Error> for FormatError {
+    fn from(error: Utf8Error) -> Self {
+        FormatError::InvalidUtf8(error)
+    }
+}
+
+impl From<BoxedError> for FormatError {
+    fn from(error: BoxedError) -> Self {
+        FormatError::Other(error)
+    }
+}
+
 pub struct DefaultFormatter;
 
 impl FieldFormatter<BinarySupport> for DefaultFormatter {
     fn format<'a>(&self, data: &'a [u8], field_spec: &'a FieldSpec, destination: &'a mut Vec<u8>, _: &'a BinarySupport) -> Result<()> {
-        if data.len() >= field_spec.length {
-            destination.extend_from_slice(&data[..field_spec.length]);
-            return Ok(());
+        if data.len() > field_spec.length {
+            return overflow(data, field_spec, destination);
         }
 
-        let padding_iter = field_spec.padding.iter().cycle().take(field_spec.length - data.len());
+        let padding_length = field_spec.length - data.len();
+        let left_padding_length = match field_spec.padding_direction {
+            PaddingDirection::Left => padding_length,
+            PaddingDirection::Right => 0,
+            PaddingDirection::Center => padding_length / 2
+        };
 
-        if field_spec.padding_direction == PaddingDirection::Left {
-            destination.extend(padding_iter.chain(data.iter()));
-        } else {
-            destination.extend(data.iter().chain(padding_iter));
-        }
+        destination.extend(field_spec.padding.iter().cycle().take(left_padding_length));
+        destination.extend_from_slice(data);
+        destination.extend(field_spec.padding.iter().cycle().take(padding_length - left_padding_length));
         Ok(())
     }
 }
 
+impl FieldFormatter<DisplayWidthSupport> for DefaultFormatter {
+    fn format<'a>(&self, data: &'a [u8], field_spec: &'a FieldSpec, destination: &'a mut Vec<u8>, support: &'a DisplayWidthSupport) -> Result<()> {
+        let string = ::std::str::from_utf8(data)?;
+        let width = support.get_width(string);
+
+        if width > field_spec.length {
+            return overflow_width(string, field_spec, destination, support);
+        }
+
+        let padding_width = field_spec.length - width;
+        let left_padding_width = match field_spec.padding_direction {
+            PaddingDirection::Left => padding_width,
+            PaddingDirection::Right => 0,
+            PaddingDirection::Center => padding_width / 2
+        };
+
+        pad_width(left_padding_width, field_spec, destination, support)?;
+        destination.extend_from_slice(data);
+        pad_width(padding_width - left_padding_width, field_spec, destination, support)
+    }
+}
+
+fn pad_width<'a>(width: usize, field_spec: &'a FieldSpec, destination: &'a mut Vec<u8>, support: &'a DisplayWidthSupport) -> Result<()> {
+    let mut remaining = width;
+
+    for character in ::std::str::from_utf8(&field_spec.padding[..])?.chars().cycle() {
+        let character_width = support.get_char_width(character);
+
+        if remaining == 0 || character_width == 0 || character_width > remaining {
+            break;
+        }
+
+        let mut buffer = [0; 4];
+        destination.extend_from_slice(character.encode_utf8(&mut buffer).as_bytes());
+        remaining -= character_width;
+    }
+
+    Ok(())
+}
+
+fn take_width<'a, T: Iterator<Item = (usize, char)>>(characters: T, max_width: usize, support: &'a DisplayWidthSupport) -> (usize, usize) {
+    let mut width = 0;
+    let mut length = 0;
+
+    for (index, character) in characters {
+        let character_width = support.get_char_width(character);
+
+        if width + character_width > max_width {
+            break;
+        }
+
+        width += character_width;
+        length = index + character.len_utf8();
+    }
+
+    (length, width)
+}
+
+fn overflow_width<'a>(string: &'a str, field_spec: &'a FieldSpec, destination: &'a mut Vec<u8>, support: &'a DisplayWidthSupport) -> Result<()> {
+    let length = field_spec.length;
+
+    match field_spec.overflow {
+        OverflowPolicy::Error => return Err(FormatError::ValueTooLong(length, string.as_bytes().to_vec())),
+        OverflowPolicy::TruncateRight => {
+            let (end, width) = take_width(string.char_indices(), length, support);
+            destination.extend_from_slice(string[..end].as_bytes());
+            pad_width(length - width, field_spec, destination, support)?;
+        },
+        OverflowPolicy::TruncateLeft => {
+            let (suffix_length, width) = take_width(string.chars().rev().scan(0, |length, character| {
+                *length += character.len_utf8();
+                Some((*length - character.len_utf8(), character))
+            }), length, support);
+            pad_width(length - width, field_spec, destination, support)?;
+            destination.extend_from_slice(string[string.len() - suffix_length..].as_bytes());
+        },
+        OverflowPolicy::Ellipsis(ref marker) => {
+            let marker = ::std::str::from_utf8(&marker[..])?;
+            let (marker_end, marker_width) = take_width(marker.char_indices(), length, support);
+            let (end, width) = take_width(string.char_indices(), length - marker_width, support);
+            destination.extend_from_slice(string[..end].as_bytes());
+            pad_width(length - marker_width - width, field_spec, destination, support)?;
+            destination.extend_from_slice(marker[..marker_end].as_bytes());
+        }
+    }
+
+    Ok(())
+}
+
+fn overflow<'a>(data: &'a [u8], field_spec: &'a FieldSpec, destination: &'a mut Vec<u8>) -> Result<()> {
+    let length = field_spec.length;
+
+    match field_spec.overflow {
+        OverflowPolicy::Error => return Err(FormatError::ValueTooLong(length, data.to_vec())),
+        OverflowPolicy::TruncateRight => destination.extend_from_slice(&data[..length]),
+        OverflowPolicy::TruncateLeft => destination.extend_from_slice(&data[data.len() - length..]),
+        OverflowPolicy::Ellipsis(ref marker) => {
+            let marker = &marker[..::std::cmp::min(marker.len(), length)];
+            destination.extend_from_slice(&data[..length - marker.len()]);
+            destination.extend_from_slice(marker);
+        }
+    }
+
+    Ok(())
+}
+
 pub struct IdentityFormatter;
 
 impl<T: WriteSupport> FieldFormatter<T> for IdentityFormatter {
@@ -109,6 +255,58 @@ mod test {
         assert_result!(Ok(()), padder.format(data, &left_field_spec, &mut destination, &data_type));
         assert_eq!("333333qwer".as_bytes().to_owned(), destination);
         destination.clear();
+        let center_field_spec = FieldSpecBuilder::new().centered_string().with_length(9).build().unwrap();
+        assert_result!(Ok(()), padder.format(data, &center_field_spec, &mut destination, &data_type));
+        assert_eq!("  qwer   ".as_bytes().to_owned(), destination);
+        destination.clear();
+    }
+
+    #[test]
+    fn default_formatter_overflow() {
+        let padder = DefaultFormatter;
+        let data = "1234567".as_bytes();
+        let mut destination = Vec::new();
+        let field_spec_builder = FieldSpecBuilder::new().number().with_length(5);
+        assert_result!(
+            Err(FormatError::ValueTooLong(5, ref value)) if *value == data.to_vec(),
+            padder.format(data, &field_spec_builder.clone().build().unwrap(), &mut destination, &BinarySupport)
+        );
+        assert_eq!(Vec::<u8>::new(), destination);
+        assert_result!(Ok(()), padder.format(data, &field_spec_builder.clone().with_overflow(OverflowPolicy::TruncateRight).build().unwrap(), &mut destination, &BinarySupport));
+        assert_eq!("12345".as_bytes().to_owned(), destination);
+        destination.clear();
+        assert_result!(Ok(()), padder.format(data, &field_spec_builder.clone().with_overflow(OverflowPolicy::TruncateLeft).build().unwrap(), &mut destination, &BinarySupport));
+        assert_eq!("34567".as_bytes().to_owned(), destination);
+        destination.clear();
+        assert_result!(Ok(()), padder.format(data, &field_spec_builder.clone().with_overflow(OverflowPolicy::Ellipsis("..".into())).build().unwrap(), &mut destination, &BinarySupport));
+        assert_eq!("123..".as_bytes().to_owned(), destination);
+        destination.clear();
+        assert_result!(Ok(()), padder.format(data, &field_spec_builder.with_length(1).with_overflow(OverflowPolicy::Ellipsis("..".into())).build().unwrap(), &mut destination, &BinarySupport));
+        assert_eq!(".".as_bytes().to_owned(), destination);
+    }
+
+    #[test]
+    fn display_width_formatter() {
+        let padder = DefaultFormatter;
+        let support = DisplayWidthSupport;
+        let mut destination = Vec::new();
+        let field_spec_builder = FieldSpecBuilder::new().string().with_length(7);
+        assert_result!(Ok(()), padder.format("a\u{65e5}\u{672c}".as_bytes(), &field_spec_builder.clone().build().unwrap(), &mut destination, &support));
+        assert_eq!("a\u{65e5}\u{672c}  ".as_bytes().to_owned(), destination);
+        destination.clear();
+        assert_result!(Ok(()), padder.format("a\u{65e5}".as_bytes(), &field_spec_builder.clone().centered_string().build().unwrap(), &mut destination, &support));
+        assert_eq!("  a\u{65e5}  ".as_bytes().to_owned(), destination);
+        destination.clear();
+        let data = "\u{65e5}\u{672c}\u{8a9e}\u{3067}".as_bytes();
+        assert_result!(Err(_), padder.format(data, &field_spec_builder.clone().build().unwrap(), &mut destination, &support));
+        assert_result!(Ok(()), padder.format(data, &field_spec_builder.clone().with_overflow(OverflowPolicy::TruncateRight).build().unwrap(), &mut destination, &support));
+        assert_eq!("\u{65e5}\u{672c}\u{8a9e} ".as_bytes().to_owned(), destination);
+        destination.clear();
+        assert_result!(Ok(()), padder.format(data, &field_spec_builder.clone().with_overflow(OverflowPolicy::TruncateLeft).build().unwrap(), &mut destination, &support));
+        assert_eq!(" \u{672c}\u{8a9e}\u{3067}".as_bytes().to_owned(), destination);
+        destination.clear();
+        assert_result!(Ok(()), padder.format(data, &field_spec_builder.with_overflow(OverflowPolicy::Ellipsis("..".into())).build().unwrap(), &mut destination, &support));
+        assert_eq!("\u{65e5}\u{672c} ..".as_bytes().to_owned(), destination);
     }
 
     #[test]
@@ -2,16 +2,23 @@ pub mod formatter;
 pub mod spec;
 
 use spec::{RecordSpec, FieldSpec};
+use spec::compiled::{CompiledSpec, IdIndex};
+use spec::validation::{self, RecordValues};
 use std::collections::{HashMap};
+use std::ops::Range;
 use std::io::Write;
 use std::borrow::Borrow;
-use error::Error;
+use error::{Error, FieldError};
 use super::{Result, PositionalResult, FieldResult};
 use record::{Data, DataRanges};
 use data_type::WriteSupport;
 use self::formatter::{FieldFormatter, IdentityFormatter};
 use std::borrow::BorrowMut;
-use self::spec::Stream as SpecSource;
+use self::spec::{Stream as SpecSource, ResolverSource};
+use framing::{self, Framing, DESCRIPTOR_LENGTH};
+use header::{self, Header};
+use context::{Capture, ErrorContext};
+use error::PositionalError;
 
 pub struct FieldWriter<'a, T: FieldFormatter<U> + 'a, U: WriteSupport> {
     formatter: T,
@@ -38,6 +45,25 @@ impl <'a, T: FieldFormatter<U> + 'a, U: WriteSupport> FieldWriter<'a, T, U> {
         where V: Write + 'b
     {
         buffer.clear();
+
+        if let Some(digits) = spec.length_prefix {
+            let mut variable_spec = spec.clone();
+            variable_spec.length = ::std::cmp::min(self.write_support.get_length(data).length, spec.length);
+            self.formatter.format(data, &variable_spec, buffer, &self.write_support).map_err(Error::FormatterFailure)?;
+
+            let length = self.write_support.get_length(&buffer[..]);
+            let prefix = format!("{:01$}", length.length, digits);
+
+            if length.length > spec.length || length.remainder > 0 || prefix.len() != digits {
+                return Err(Error::FormattedValueWrongLength(spec.length, buffer.clone()));
+            }
+
+            writer.write_all(prefix.as_bytes())?;
+            writer.write_all(&buffer[..])?;
+
+            return Ok(prefix.len() + buffer.len());
+        }
+
         self.formatter.format(data, spec, buffer, &self.write_support).map_err(Error::FormatterFailure)?;
 
         let length = self.write_support.get_length(&buffer[..]);
@@ -73,19 +99,166 @@ impl <'a, T: FieldFormatter<U> + 'a, U: WriteSupport> RecordWriter<'a, T, U> {
         where V: Write + 'b,
               W: DataRanges + 'b
     {
+        self.write_collecting_into(writer, spec, data, buffer, &mut None, &mut None)
+    }
+
+    pub fn check<'b, W>(&self, spec: &'b RecordSpec, data: &'b Data<W, U::DataHolder>) -> Vec<FieldError>
+        where W: DataRanges + 'b
+    {
+        let mut errors = Vec::new();
+
+        if let Err(e) = self.write_collecting_into(&mut ::std::io::sink(), spec, data, &mut Vec::new(), &mut Some(&mut errors), &mut None) {
+            errors.push(e);
+        }
+
+        errors
+    }
+
+    // when given, written_fields gets the byte range of the record each field was written to
+    fn write_collecting_into<'b, V, W>(&self, writer: &'b mut V, spec: &'b RecordSpec, data: &'b Data<W, U::DataHolder>, buffer: &mut Vec<u8>, errors: &'b mut Option<&mut Vec<FieldError>>, written_fields: &'b mut Option<&mut Vec<(String, Range<usize>)>>) -> FieldResult<usize>
+        where V: Write + 'b,
+              W: DataRanges + 'b
+    {
+        let record_result = validation::check_record(&spec.rules, &WriteValues { support: self.write_support(), spec: spec, data: data });
+        FieldError::collect(record_result.map_err(FieldError::from), errors)?;
+
         let mut amount_written = 0;
 
-        for (name, field_spec) in &spec.field_specs {
-            let field_data = self.field_writer.write_support().get_data_by_name(name, data)
-                .or_else(|| field_spec.default.as_ref().map(|v| &v[..]))
-                .ok_or_else(|| (Error::FieldValueRequired, name))?
-            ;
-            amount_written += self.field_writer.write(writer, field_spec, field_data, buffer).map_err(|e| (e, name))?;
+        for (field_specs, line_ending) in spec.lines() {
+            let mut first = true;
+
+            for (name, field_spec) in field_specs {
+                if spec.bitmap.as_ref().map_or(false, |bitmap| bitmap.controls(name)) {
+                    continue;
+                }
+
+                if !first {
+                    writer.write_all(&spec.separator[..]).map_err(|e| (e.into(), name))?;
+                    amount_written += spec.separator.len();
+                }
+
+                first = false;
+
+                if let Some(ref bitmap) = spec.bitmap {
+                    if bitmap.field == *name {
+                        let bits = bitmap.fields.iter()
+                            .filter(|&(_, name)| self.field_writer.write_support().get_data_by_name(name, data).is_some())
+                            .map(|(bit, _)| *bit)
+                            .collect::<Vec<_>>()
+                        ;
+                        let encoded = bitmap.encode(&bits[..], field_spec);
+                        writer.write_all(&encoded[..]).map_err(|e| (e.into(), name))?;
+                        track(written_fields, name, amount_written, &Ok(encoded.len()));
+                        amount_written += encoded.len();
+
+                        for bit in bits {
+                            let name = &bitmap.fields[&bit];
+                            let field_spec = spec.field_spec(name).expect("bitmap fields are checked to exist when the spec is built");
+                            let written = self.write_field(writer, name, field_spec, data, buffer);
+                            track(written_fields, name, amount_written, &written);
+                            amount_written += FieldError::collect(written, errors)?;
+                        }
+
+                        continue;
+                    }
+                }
+
+                match spec.redefines.get(name) {
+                    None => {
+                        let written = self.write_field(writer, name, field_spec, data, buffer);
+                        track(written_fields, name, amount_written, &written);
+                        amount_written += FieldError::collect(written, errors)?;
+                    },
+                    Some(redefine) => {
+                        let value = self.field_writer.write_support().get_data_by_name(&redefine.discriminator, data)
+                            .or_else(|| spec.field_spec(&redefine.discriminator).and_then(|field_spec| field_spec.default.as_ref().map(|v| &v[..])))
+                            .unwrap_or(&[])
+                        ;
+                        let alternative = redefine.alternative(value)
+                            .ok_or_else(|| (Error::NoAlternativeForDiscriminator(value.to_owned()), name))?
+                        ;
+
+                        for (name, field_spec) in alternative {
+                            let written = self.write_field(writer, name, field_spec, data, buffer);
+                            track(written_fields, name, amount_written, &written);
+                            amount_written += FieldError::collect(written, errors)?;
+                        }
+                    }
+                }
+            }
+
+            writer.write_all(line_ending)?;
+            amount_written += line_ending.len();
         }
 
-        writer.write_all(&spec.line_ending[..])?;
+        Ok(amount_written)
+    }
+
+    fn write_field<'b, V, W>(&self, writer: &'b mut V, name: &'b str, field_spec: &'b FieldSpec, data: &'b Data<W, U::DataHolder>, buffer: &mut Vec<u8>) -> FieldResult<usize>
+        where V: Write + 'b,
+              W: DataRanges + 'b
+    {
+        let is_null = data.is_null(name);
+        let field_data = if is_null {
+            None
+        } else {
+            self.field_writer.write_support().get_data_by_name(name, data)
+        };
+
+        if field_spec.constant {
+            let constant = field_spec.default.as_ref().expect("constant fields always have a default");
 
-        Ok(amount_written + spec.line_ending.len())
+            if let Some(field_data) = field_data {
+                if field_data != &constant[..] {
+                    return Err((Error::ConstantMismatch(constant.clone(), field_data.to_vec()), name).into());
+                }
+            }
+
+            return Ok(self.field_writer.write(writer, field_spec, &constant[..], buffer).map_err(|e| (e, name))?);
+        }
+
+        let field_data = if is_null {
+            None
+        } else {
+            field_data.or_else(|| field_spec.default.as_ref().map(|v| &v[..]))
+        };
+
+        validation::validate(&field_spec.validations, field_data).map_err(|e| (e, name))?;
+
+        let field_data = match (field_data, field_spec.null.as_ref()) {
+            (Some(field_data), _) => field_data,
+            (None, Some(null)) => {
+                let null = null.to_bytes(field_spec);
+                writer.write_all(&null[..]).map_err(|e| (e.into(), name))?;
+                return Ok(null.len());
+            },
+            (None, None) => return Err((Error::FieldValueRequired, name).into())
+        };
+
+        Ok(self.field_writer.write(writer, field_spec, field_data, buffer).map_err(|e| (e, name))?)
+    }
+}
+
+fn track<'a>(written_fields: &'a mut Option<&mut Vec<(String, Range<usize>)>>, name: &'a str, start: usize, written: &'a FieldResult<usize>) {
+    if let Some(ref mut written_fields) = *written_fields {
+        written_fields.push((name.to_string(), start..start + written.as_ref().map_or(0, |length| *length)));
+    }
+}
+
+struct WriteValues<'a, U: WriteSupport + 'a, W: DataRanges + 'a> {
+    support: &'a U,
+    spec: &'a RecordSpec,
+    data: &'a Data<W, U::DataHolder>
+}
+
+impl<'a, U: WriteSupport + 'a, W: DataRanges + 'a> RecordValues for WriteValues<'a, U, W> {
+    fn get<'b>(&'b self, name: &'b str) -> Option<&'b [u8]> {
+        if self.data.is_null(name) {
+            return None;
+        }
+
+        self.support.get_data_by_name(name, self.data)
+            .or_else(|| self.spec.field_spec(name).and_then(|field_spec| field_spec.default.as_ref()).map(|v| &v[..]))
     }
 }
 
@@ -104,6 +277,14 @@ pub struct Writer<
     spec_source: U,
     record_specs: W,
     buffer: Y,
+    framing: Framing,
+    record_buffer: Vec<u8>,
+    block: Vec<u8>,
+    header: Option<Header>,
+    header_written: bool,
+    last_record: Option<Vec<u8>>,
+    last_fields: Vec<(String, Range<usize>)>,
+    flushed: usize,
     destination_type: ::std::marker::PhantomData<&'a R>
 }
 
@@ -115,23 +296,136 @@ impl<'a, R, T, U, V, W, X, Y> Writer<'a, R, T, U, V, W, X, Y>
           W: Borrow<HashMap<String, RecordSpec>> + 'a,
           X: BorrowMut<R> + 'a,
           Y: BorrowMut<Vec<u8>> + 'a {
+    // returns the framed length of the record, its descriptor word included, every time. With
+    // blocked framing the record only joins the pending block, the blocks reaching the destination
+    // are reported by flush and flushed instead
     pub fn write_record<'b, A: DataRanges + 'b>(&mut self, data: &'b Data<A, V::DataHolder>) -> PositionalResult<usize> {
         let spec_name = self.spec_source.next(data, self.record_specs.borrow(), self.writer.write_support())
             .map_err(Error::SpecStreamError)?
             .ok_or(Error::SpecStreamReturnedNone)?
         ;
-        self.writer
-            .write(
-                self.destination.borrow_mut(),
-                self.record_specs.borrow().get(spec_name).ok_or_else(|| Error::RecordSpecNotFound(spec_name.to_string()))?,
-                data,
-                self.buffer.borrow_mut()
-            )
-            .map_err(|e| (e, spec_name).into())
+        let record_spec = self.record_specs.borrow().get(spec_name).ok_or_else(|| Error::RecordSpecNotFound(spec_name.to_string()))?;
+        let mut header_length = 0;
+
+        if let Some(ref header) = self.header {
+            if !self.header_written {
+                self.record_buffer.clear();
+                header::write_header(record_spec, header, &mut self.record_buffer).map_err(|e| (FieldError::from(e), spec_name))?;
+                self.destination.borrow_mut().write_all(&self.record_buffer[..]).map_err(Error::from)?;
+                self.header_written = true;
+                header_length = self.record_buffer.len();
+            }
+        }
+
+        let mut last_record = self.last_record.as_mut().map(|last_record| {
+            last_record.clear();
+            last_record
+        });
+        self.last_fields.clear();
+        let mut last_fields = if last_record.is_some() { Some(&mut self.last_fields) } else { None };
+
+        if let Framing::None = self.framing {
+            return self.writer
+                .write_collecting_into(&mut Capture::new(self.destination.borrow_mut(), last_record), record_spec, data, self.buffer.borrow_mut(), &mut None, &mut last_fields)
+                .map(|length| length + header_length)
+                .map_err(|e| (e, spec_name).into())
+            ;
+        }
+
+        self.record_buffer.clear();
+        let result = self.writer.write_collecting_into(&mut self.record_buffer, record_spec, data, self.buffer.borrow_mut(), &mut None, &mut last_fields);
+
+        if let Some(ref mut last_record) = last_record {
+            last_record.extend_from_slice(&self.record_buffer[..]);
+        }
+
+        result.map_err(|e| (e, spec_name))?;
+        Self::write_framed(
+            self.destination.borrow_mut(),
+            self.framing,
+            &self.record_buffer[..],
+            &mut self.block,
+            &mut self.flushed
+        )
+            .map(|length| length + header_length)
+            .map_err(|e| (FieldError::from(e), spec_name).into())
     }
 
-    pub fn into_inner(self) -> RecordWriter<'a, T, V> {
-        self.writer
+    pub fn flush(&mut self) -> Result<usize> {
+        let written = Self::write_block(self.destination.borrow_mut(), &mut self.block)?;
+        self.flushed += written;
+        Ok(written)
+    }
+
+    // the bytes of all the blocks written to the destination so far, block descriptor words included
+    pub fn flushed(&self) -> usize {
+        self.flushed
+    }
+
+    pub fn pending(&self) -> &[u8] {
+        &self.block[..]
+    }
+
+    pub fn finish(mut self) -> Result<X> {
+        self.flush()?;
+        Ok(self.destination)
+    }
+
+    fn write_framed(destination: &mut R, framing: Framing, record: &[u8], block: &mut Vec<u8>, flushed: &mut usize) -> Result<usize> {
+        let mut descriptor = Vec::with_capacity(DESCRIPTOR_LENGTH);
+        framing::write_descriptor(record.len(), &mut descriptor)?;
+
+        if let Framing::Blocked(max_block_length) = framing {
+            let framed_length = record.len() + DESCRIPTOR_LENGTH * 2;
+
+            if framed_length > max_block_length {
+                return Err(Error::RecordTooLong(record.len(), max_block_length.saturating_sub(DESCRIPTOR_LENGTH * 2)));
+            }
+
+            if block.len() + framed_length > max_block_length {
+                *flushed += Self::write_block(destination, block)?;
+            }
+
+            block.extend_from_slice(&descriptor[..]);
+            block.extend_from_slice(record);
+
+            return Ok(record.len() + DESCRIPTOR_LENGTH);
+        }
+
+        destination.write_all(&descriptor[..])?;
+        destination.write_all(record)?;
+
+        Ok(record.len() + DESCRIPTOR_LENGTH)
+    }
+
+    fn write_block(destination: &mut R, block: &mut Vec<u8>) -> Result<usize> {
+        if block.is_empty() {
+            return Ok(0);
+        }
+
+        let mut descriptor = Vec::with_capacity(DESCRIPTOR_LENGTH);
+        framing::write_descriptor(block.len(), &mut descriptor)?;
+        destination.write_all(&descriptor[..])?;
+        destination.write_all(&block[..])?;
+        let written = block.len() + DESCRIPTOR_LENGTH;
+        block.clear();
+
+        Ok(written)
+    }
+
+    pub fn last_record(&self) -> Option<&[u8]> {
+        self.last_record.as_ref().map(|last_record| &last_record[..])
+    }
+
+    pub fn error_context<'b>(&'b self, error: &'b PositionalError) -> Option<ErrorContext<'b>> {
+        let record_spec = self.record_specs.borrow().get(&error.position.as_ref()?.record)?;
+
+        Some(ErrorContext::new(error, self.last_record()?, record_spec, &self.last_fields[..]))
+    }
+
+    pub fn into_inner(mut self) -> Result<RecordWriter<'a, T, V>> {
+        self.flush()?;
+        Ok(self.writer)
     }
 }
 
@@ -151,6 +445,9 @@ pub struct WriterBuilder<
     spec_source: Option<U>,
     record_specs: Option<W>,
     buffer: Y,
+    framing: Framing,
+    header: Option<Header>,
+    error_context: bool,
     destination_type: ::std::marker::PhantomData<&'a WR>
 }
 
@@ -164,6 +461,32 @@ impl<'a, V> WriterBuilder<'a, &'a mut [u8], IdentityFormatter, (), V, HashMap<St
             spec_source: None,
             record_specs: None,
             buffer: Vec::new(),
+            framing: Framing::None,
+            header: None,
+            error_context: false,
+            destination_type: ::std::marker::PhantomData
+        }
+    }
+}
+
+impl<'a, WR, T, V, W, X, Y> WriterBuilder<'a, WR, T, (), V, W, X, Y>
+    where WR: Write + 'a,
+          T: FieldFormatter<V> + 'a,
+          V: WriteSupport,
+          W: Borrow<HashMap<String, RecordSpec>> + 'a,
+          X: BorrowMut<WR> + 'a,
+          Y: BorrowMut<Vec<u8>> + 'a {
+    pub fn with_compiled_spec(self, compiled: &'a CompiledSpec) -> WriterBuilder<'a, WR, T, ResolverSource<'a, &'a IdIndex, V>, V, &'a CompiledSpec, X, Y> {
+        WriterBuilder {
+            destination: self.destination,
+            write_support: self.write_support,
+            field_formatter: self.field_formatter,
+            spec_source: compiled.id_index().map(ResolverSource::new),
+            record_specs: Some(compiled),
+            buffer: self.buffer,
+            framing: self.framing,
+            header: self.header,
+            error_context: self.error_context,
             destination_type: ::std::marker::PhantomData
         }
     }
@@ -185,6 +508,9 @@ impl<'a, WR, T, U, V, W, X, Y> WriterBuilder<'a, WR, T, U, V, W, X, Y>
             spec_source: self.spec_source,
             record_specs: self.record_specs,
             buffer: self.buffer,
+            framing: self.framing,
+            header: self.header,
+            error_context: self.error_context,
             destination_type: ::std::marker::PhantomData
         }
     }
@@ -197,6 +523,9 @@ impl<'a, WR, T, U, V, W, X, Y> WriterBuilder<'a, WR, T, U, V, W, X, Y>
             spec_source: self.spec_source,
             record_specs: self.record_specs,
             buffer: self.buffer,
+            framing: self.framing,
+            header: self.header,
+            error_context: self.error_context,
             destination_type: ::std::marker::PhantomData
         }
     }
@@ -209,6 +538,9 @@ impl<'a, WR, T, U, V, W, X, Y> WriterBuilder<'a, WR, T, U, V, W, X, Y>
             spec_source: Some(spec_source),
             record_specs: self.record_specs,
             buffer: self.buffer,
+            framing: self.framing,
+            header: self.header,
+            error_context: self.error_context,
             destination_type: ::std::marker::PhantomData
         }
     }
@@ -221,6 +553,9 @@ impl<'a, WR, T, U, V, W, X, Y> WriterBuilder<'a, WR, T, U, V, W, X, Y>
             spec_source: self.spec_source,
             record_specs: Some(record_specs),
             buffer: self.buffer,
+            framing: self.framing,
+            header: self.header,
+            error_context: self.error_context,
             destination_type: ::std::marker::PhantomData
         }
     }
@@ -233,10 +568,28 @@ impl<'a, WR, T, U, V, W, X, Y> WriterBuilder<'a, WR, T, U, V, W, X, Y>
             spec_source: self.spec_source,
             record_specs: self.record_specs,
             buffer: buffer,
+            framing: self.framing,
+            header: self.header,
+            error_context: self.error_context,
             destination_type: ::std::marker::PhantomData
         }
     }
 
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    pub fn with_header(mut self, header: Header) -> Self {
+        self.header = Some(header);
+        self
+    }
+
+    pub fn with_error_context(mut self) -> Self {
+        self.error_context = true;
+        self
+    }
+
     pub fn build(self) -> Result<Writer<'a, WR, T, U, V, W, X, Y>> {
         Ok(Writer {
             destination: self.destination.ok_or(Error::FieldRequiredToBuild("source needs to be defined in order to build"))?,
@@ -247,6 +600,14 @@ impl<'a, WR, T, U, V, W, X, Y> WriterBuilder<'a, WR, T, U, V, W, X, Y>
             spec_source: self.spec_source.ok_or(Error::FieldRequiredToBuild("spec_source needs to be defined in order to build"))?,
             record_specs: self.record_specs.ok_or(Error::FieldRequiredToBuild("record_specs needs to be defined in order to build"))?,
             buffer: self.buffer,
+            framing: self.framing,
+            record_buffer: Vec::new(),
+            block: Vec::new(),
+            header: self.header,
+            header_written: false,
+            last_record: if self.error_context { Some(Vec::new()) } else { None },
+            last_fields: Vec::new(),
+            flushed: 0,
             destination_type: ::std::marker::PhantomData
         })
     }
@@ -256,10 +617,15 @@ impl<'a, WR, T, U, V, W, X, Y> WriterBuilder<'a, WR, T, U, V, W, X, Y>
 mod test {
     use super::*;
     use record::Data;
-    use error::{Error, FieldError};
+    use error::{Error, FieldError, PositionalError};
     use test::*;
+    use spec::{SpecBuilder, RecordSpecBuilder, FieldSpecBuilder, BitmapEncoding, NullValue, OverflowPolicy, Builder};
     use std::collections::{HashMap, BTreeMap};
     use std::io::Cursor;
+    use std::ops::Range;
+    use spec::validation::{RecordRule, ValidationRule};
+    use spec::stream::StreamError;
+    use writer::formatter::FormatError;
     use data_type::BinarySupport;
 
     #[test]
@@ -440,4 +806,549 @@ mod test {
         }
         assert_eq!(string, String::from_utf8(buf.into_inner()).unwrap());
     }
-}
\ No newline at end of file
+    #[test]
+    fn write_with_header() {
+        let spec = SpecBuilder::new()
+            .with_record("record1")
+                .with_line_ending("\n")
+                .with_field("amount")
+                    .string()
+                    .with_length(7)
+                .end()
+                .with_field("code")
+                    .string()
+                    .with_length(4)
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new(Vec::new());
+        {
+            let mut writer = WriterBuilder::new(BinarySupport)
+                .with_source::<Cursor<_>, _>(&mut buf)
+                .with_field_formatter(IdentityFormatter)
+                .with_spec_source(::spec::stream::VecStream::from(vec!["record1", "record1"]))
+                .with_record_specs(&spec.record_specs)
+                .with_header(Header::new("record1", "\n", true))
+                .build()
+                .unwrap()
+            ;
+            let record = Data::from([("amount".to_string(), "12.50  ".as_bytes().to_owned()), ("code".to_string(), "ab  ".as_bytes().to_owned())]
+                .iter().cloned().collect::<BTreeMap<_, _>>());
+            assert_result!(Ok(36), writer.write_record(&record));
+            assert_result!(Ok(12), writer.write_record(&record));
+        }
+        assert_eq!(
+            "amount code\n------ --- \n12.50  ab  \n12.50  ab  \n".to_string(),
+            String::from_utf8(buf.into_inner()).unwrap()
+        );
+    }
+
+    #[test]
+    fn write_with_framing() {
+        let spec = SpecBuilder::new()
+            .with_record("record1")
+                .with_field("field1")
+                    .string()
+                    .with_length(3)
+                .end()
+                .with_field("field2")
+                    .string()
+                    .with_length(2)
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let records = vec!["abcde", "fghij", "klmno"].into_iter()
+            .map(|v| Data::from([("field1".to_string(), v[..3].as_bytes().to_owned()), ("field2".to_string(), v[3..].as_bytes().to_owned())]
+                .iter().cloned().collect::<BTreeMap<_, _>>()))
+            .collect::<Vec<_>>()
+        ;
+        let mut buf = Cursor::new(Vec::new());
+        {
+            let mut writer = WriterBuilder::new(BinarySupport)
+                .with_source::<Cursor<_>, _>(&mut buf)
+                .with_field_formatter(IdentityFormatter)
+                .with_spec_source(::spec::stream::VecStream::from(vec!["record1", "record1"]))
+                .with_record_specs(&spec.record_specs)
+                .with_framing(Framing::Rdw)
+                .build()
+                .unwrap()
+            ;
+            assert_result!(Ok(9), writer.write_record(&records[0]));
+            assert_result!(Ok(9), writer.write_record(&records[1]));
+        }
+        let mut expected = vec![0, 9, 0, 0];
+        expected.extend("abcde".as_bytes());
+        expected.extend(&[0, 9, 0, 0]);
+        expected.extend("fghij".as_bytes());
+        assert_eq!(expected, buf.into_inner());
+
+        let mut buf = Cursor::new(Vec::new());
+        {
+            let mut writer = WriterBuilder::new(BinarySupport)
+                .with_source::<Cursor<_>, _>(&mut buf)
+                .with_field_formatter(IdentityFormatter)
+                .with_spec_source(::spec::stream::VecStream::from(vec!["record1", "record1", "record1"]))
+                .with_record_specs(&spec.record_specs)
+                .with_framing(Framing::Blocked(22))
+                .build()
+                .unwrap()
+            ;
+            assert_result!(Ok(9), writer.write_record(&records[0]));
+            assert_result!(Ok(9), writer.write_record(&records[1]));
+            assert_eq!(0, writer.flushed());
+            assert_result!(Ok(9), writer.write_record(&records[2]));
+            assert_eq!(22, writer.flushed());
+            assert_eq!(9, writer.pending().len());
+            assert_result!(Ok(13), writer.flush());
+            assert_eq!(35, writer.flushed());
+            writer.finish().unwrap();
+        }
+        let mut expected = vec![0, 22, 0, 0, 0, 9, 0, 0];
+        expected.extend("abcde".as_bytes());
+        expected.extend(&[0, 9, 0, 0]);
+        expected.extend("fghij".as_bytes());
+        expected.extend(&[0, 13, 0, 0, 0, 9, 0, 0]);
+        expected.extend("klmno".as_bytes());
+        assert_eq!(expected, buf.into_inner());
+
+        let mut buf = Cursor::new(Vec::new());
+        {
+            let mut writer = WriterBuilder::new(BinarySupport)
+                .with_source::<Cursor<_>, _>(&mut buf)
+                .with_field_formatter(IdentityFormatter)
+                .with_spec_source(::spec::stream::VecStream::from(vec!["record1"]))
+                .with_record_specs(&spec.record_specs)
+                .with_framing(Framing::Blocked(22))
+                .build()
+                .unwrap()
+            ;
+            assert_result!(Ok(9), writer.write_record(&records[0]));
+            writer.into_inner().unwrap();
+        }
+        assert_eq!(vec![0, 13, 0, 0, 0, 9, 0, 0], buf.into_inner()[..8].to_vec());
+
+        let mut buf = Cursor::new(Vec::new());
+        let mut writer = WriterBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_formatter(IdentityFormatter)
+            .with_spec_source(::spec::stream::VecStream::from(vec!["record1"]))
+            .with_record_specs(&spec.record_specs)
+            .with_framing(Framing::Blocked(12))
+            .build()
+            .unwrap()
+        ;
+        assert_result!(
+            Err(PositionalError { error: Error::RecordTooLong(5, 4), .. }),
+            writer.write_record(&records[0])
+        );
+    }
+
+    #[test]
+    fn write_record_with_segments() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("field1", FieldSpecBuilder::new().string().with_length(3))
+            .with_segment()
+                .with_line_ending("\r\n")
+                .add_field("field2", FieldSpecBuilder::new().string().with_length(2).with_default("+"))
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new(Vec::new());
+        let writer = RecordWriter::new(FieldWriter::new(::writer::formatter::DefaultFormatter, BinarySupport));
+        assert_result!(
+            Ok(8),
+            writer.write(&mut buf, &record_spec, &Data::from([("field1".to_string(), "ab".as_bytes().to_owned())]
+                .iter().cloned().collect::<BTreeMap<_, _>>()), &mut Vec::new())
+        );
+        assert_eq!("ab \n+ \r\n".to_string(), String::from_utf8(buf.into_inner()).unwrap());
+    }
+
+    #[test]
+    fn write_record_with_separator() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .with_separator(" | ")
+            .add_field("field1", FieldSpecBuilder::new().string().with_length(3))
+            .add_field("field2", FieldSpecBuilder::new().number().with_length(2))
+            .add_field("field3", FieldSpecBuilder::new().string().with_length(1))
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new(Vec::new());
+        let writer = RecordWriter::new(FieldWriter::new(::writer::formatter::DefaultFormatter, BinarySupport));
+        assert_result!(
+            Ok(13),
+            writer.write(&mut buf, &record_spec, &Data::from([
+                ("field1".to_string(), "ab".as_bytes().to_owned()),
+                ("field2".to_string(), "1".as_bytes().to_owned()),
+                ("field3".to_string(), "c".as_bytes().to_owned())
+            ].iter().cloned().collect::<BTreeMap<_, _>>()), &mut Vec::new())
+        );
+        assert_eq!("ab  | 01 | c\n".to_string(), String::from_utf8(buf.into_inner()).unwrap());
+    }
+
+    #[test]
+    fn write_record_with_redefine() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("field1", FieldSpecBuilder::new().string().with_length(1).with_default("A"))
+            .add_field("field2", FieldSpecBuilder::new().string().with_length(5))
+            .with_redefine("field2", "field1")
+                .add_field("A", "field3", FieldSpecBuilder::new().string().with_length(5))
+                .add_field("B", "field4", FieldSpecBuilder::new().string().with_length(2))
+                .add_field("B", "field5", FieldSpecBuilder::new().number().with_length(3))
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new(Vec::new());
+        let writer = RecordWriter::new(FieldWriter::new(::writer::formatter::DefaultFormatter, BinarySupport));
+        writer.write(&mut buf, &record_spec, &Data::from([("field3".to_string(), "ab".as_bytes().to_owned())]
+            .iter().cloned().collect::<BTreeMap<_, _>>()), &mut Vec::new()).unwrap();
+        writer.write(&mut buf, &record_spec, &Data::from([
+            ("field1".to_string(), "B".as_bytes().to_owned()),
+            ("field4".to_string(), "cd".as_bytes().to_owned()),
+            ("field5".to_string(), "7".as_bytes().to_owned())
+        ].iter().cloned().collect::<BTreeMap<_, _>>()), &mut Vec::new()).unwrap();
+        assert_eq!("Aab   \nBcd007\n".to_string(), String::from_utf8(buf.into_inner()).unwrap());
+        assert_result!(
+            Err(FieldError {
+                error: Error::NoAlternativeForDiscriminator(ref value),
+                field: Some(ref field)
+            }) if *value == b"C".to_vec() && field == "field2",
+            writer.write(&mut Cursor::new(Vec::new()), &record_spec, &Data::from([("field1".to_string(), "C".as_bytes().to_owned())]
+                .iter().cloned().collect::<BTreeMap<_, _>>()), &mut Vec::new())
+        );
+    }
+
+    #[test]
+    fn write_record_with_bitmap() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("bitmap", FieldSpecBuilder::new().string().with_length(1))
+            .add_field("field1", FieldSpecBuilder::new().string().with_length(2))
+            .add_field("field2", FieldSpecBuilder::new().lllvar(10))
+            .add_field("field3", FieldSpecBuilder::new().string().with_length(3))
+            .add_field("mti", FieldSpecBuilder::new().string().with_length(1))
+            .with_bitmap("bitmap", BitmapEncoding::Binary)
+                .add_field(2, "field1")
+                .add_field(3, "field2")
+                .add_field(12, "field3")
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new(Vec::new());
+        let writer = RecordWriter::new(FieldWriter::new(::writer::formatter::DefaultFormatter, BinarySupport));
+        assert_result!(Ok(14), writer.write(&mut buf, &record_spec, &Data::from([
+            ("mti".to_string(), "1".as_bytes().to_owned()),
+            ("field2".to_string(), "abcd".as_bytes().to_owned()),
+            ("field3".to_string(), "e".as_bytes().to_owned())
+        ].iter().cloned().collect::<BTreeMap<_, _>>()), &mut Vec::new()));
+        let mut expected = vec![0xA0, 0x10];
+        expected.extend("004abcde  1\n".as_bytes());
+        assert_eq!(expected, buf.into_inner());
+        assert_result!(
+            Err(FieldError {
+                error: Error::FormatterFailure(FormatError::ValueTooLong(10, _)),
+                field: Some(ref field)
+            }) if field == "field2",
+            writer.write(&mut Cursor::new(Vec::new()), &record_spec, &Data::from([
+                ("mti".to_string(), "1".as_bytes().to_owned()),
+                ("field2".to_string(), "abcdefghijk".as_bytes().to_owned())
+            ].iter().cloned().collect::<BTreeMap<_, _>>()), &mut Vec::new())
+        );
+        let record_spec = RecordSpecBuilder::new()
+            .add_field("field1", FieldSpecBuilder::new().llvar(5).with_overflow(OverflowPolicy::Ellipsis(b"~".to_vec())))
+            .build()
+            .unwrap()
+        ;
+        let mut buf = Cursor::new(Vec::new());
+        assert_result!(Ok(7), writer.write(&mut buf, &record_spec, &Data::from([
+            ("field1".to_string(), "abcdefgh".as_bytes().to_owned())
+        ].iter().cloned().collect::<BTreeMap<_, _>>()), &mut Vec::new()));
+        assert_eq!("05abcd~".to_string(), String::from_utf8(buf.into_inner()).unwrap());
+    }
+
+    #[test]
+    fn write_record_with_nulls() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("amount", FieldSpecBuilder::new().number().with_length(4).with_null(NullValue::Zeros))
+            .add_field("code", FieldSpecBuilder::new().string().with_length(4).with_null(NullValue::Sentinel(b"NA".to_vec())))
+            .add_field("name", FieldSpecBuilder::new().string().with_length(3).with_default("abc").with_null(NullValue::Blanks))
+            .build()
+            .unwrap()
+        ;
+        let writer = RecordWriter::new(FieldWriter::new(IdentityFormatter, BinarySupport));
+        let mut data: Data<BTreeMap<String, Range<usize>>, Vec<u8>> = Data::new();
+        data.push("amount", "1234");
+        data.push_null("amount");
+        data.push_null("name");
+        let mut buf = Cursor::new(Vec::new());
+        assert_result!(Ok(12), writer.write(&mut buf, &record_spec, &data, &mut Vec::new()));
+        assert_eq!("0000NA     \n".to_string(), String::from_utf8(buf.into_inner()).unwrap());
+        let mut data: Data<BTreeMap<String, Range<usize>>, Vec<u8>> = Data::new();
+        data.push("amount", "1234");
+        let mut buf = Cursor::new(Vec::new());
+        assert_result!(Ok(12), writer.write(&mut buf, &record_spec, &data, &mut Vec::new()));
+        assert_eq!("1234NA  abc\n".to_string(), String::from_utf8(buf.into_inner()).unwrap());
+        let record_spec = RecordSpecBuilder::new()
+            .add_field("code", FieldSpecBuilder::new().string().with_length(4))
+            .build()
+            .unwrap()
+        ;
+        assert_result!(
+            Err(FieldError { error: Error::FieldValueRequired, field: Some(ref field) }) if field == "code",
+            writer.write(&mut Cursor::new(Vec::new()), &record_spec, &data, &mut Vec::new())
+        );
+    }
+
+    #[test]
+    fn write_record_with_constants() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("$type", FieldSpecBuilder::new().string().with_length(3).with_default("HDR").constant())
+            .add_field("version", FieldSpecBuilder::new().string().with_length(2).with_default("02").constant())
+            .build()
+            .unwrap()
+        ;
+        let writer = RecordWriter::new(FieldWriter::new(IdentityFormatter, BinarySupport));
+        let mut data: Data<BTreeMap<String, Range<usize>>, Vec<u8>> = Data::new();
+        data.push("$type", "HDR");
+        let mut buf = Cursor::new(Vec::new());
+        assert_result!(Ok(6), writer.write(&mut buf, &record_spec, &data, &mut Vec::new()));
+        assert_eq!("HDR02\n".to_string(), String::from_utf8(buf.into_inner()).unwrap());
+        data.push("version", "03");
+        assert_result!(
+            Err(FieldError { error: Error::ConstantMismatch(ref expected, ref actual), field: Some(ref field) })
+                if *expected == b"02".to_vec() && *actual == b"03".to_vec() && field == "version",
+            writer.write(&mut Cursor::new(Vec::new()), &record_spec, &data, &mut Vec::new())
+        );
+    }
+
+    #[test]
+    fn write_record_with_validations() {
+        let record_spec = RecordSpecBuilder::new()
+            .add_field("amount", FieldSpecBuilder::new().number().with_length(3).with_validation(ValidationRule::Range { min: Some(1), max: Some(100) }))
+            .add_field("code", FieldSpecBuilder::new().string().with_length(2).with_default("AB").with_validation(ValidationRule::OneOf(vec![b"AB".to_vec(), b"CD".to_vec()])))
+            .build()
+            .unwrap()
+        ;
+        let writer = RecordWriter::new(FieldWriter::new(IdentityFormatter, BinarySupport));
+        let mut data: Data<BTreeMap<String, Range<usize>>, Vec<u8>> = Data::new();
+        data.push("amount", "100");
+        let mut buf = Cursor::new(Vec::new());
+        assert_result!(Ok(5), writer.write(&mut buf, &record_spec, &data, &mut Vec::new()));
+        assert_eq!("100AB".to_string(), String::from_utf8(buf.into_inner()).unwrap());
+        data.push("code", "EF");
+        assert_result!(
+            Err(FieldError { error: Error::ValidationFailed(ValidationRule::OneOf(_), Some(ref value)), field: Some(ref field) })
+                if *value == b"EF".to_vec() && field == "code",
+            writer.write(&mut Cursor::new(Vec::new()), &record_spec, &data, &mut Vec::new())
+        );
+        let mut data: Data<BTreeMap<String, Range<usize>>, Vec<u8>> = Data::new();
+        data.push("amount", "101");
+        assert_result!(
+            Err(FieldError { error: Error::ValidationFailed(ValidationRule::Range { .. }, _), field: Some(ref field) }) if field == "amount",
+            writer.write(&mut Cursor::new(Vec::new()), &record_spec, &data, &mut Vec::new())
+        );
+    }
+
+    #[test]
+    fn write_record_with_record_rules() {
+        let record_spec = RecordSpecBuilder::new()
+            .add_field("email", FieldSpecBuilder::new().string().with_length(3).with_null(NullValue::Blanks))
+            .add_field("phone", FieldSpecBuilder::new().string().with_length(3).with_null(NullValue::Blanks))
+            .add_rule(RecordRule::AnyPopulated(vec!["email".to_string(), "phone".to_string()]))
+            .build()
+            .unwrap()
+        ;
+        let writer = RecordWriter::new(FieldWriter::new(IdentityFormatter, BinarySupport));
+        let mut data: Data<BTreeMap<String, Range<usize>>, Vec<u8>> = Data::new();
+        data.push("phone", "555");
+        let mut buf = Cursor::new(Vec::new());
+        assert_result!(Ok(6), writer.write(&mut buf, &record_spec, &data, &mut Vec::new()));
+        assert_eq!("   555".to_string(), String::from_utf8(buf.into_inner()).unwrap());
+        let mut buf = Cursor::new(Vec::new());
+        assert_result!(
+            Err(FieldError { error: Error::RecordRuleFailed(ref rule, ref fields), field: None })
+                if rule == "any_populated" && *fields == vec!["email".to_string(), "phone".to_string()],
+            writer.write(&mut buf, &record_spec, &Data::<BTreeMap<String, Range<usize>>, Vec<u8>>::new(), &mut Vec::new())
+        );
+        assert!(buf.into_inner().is_empty());
+    }
+
+    #[test]
+    fn check_record() {
+        let record_spec = RecordSpecBuilder::new()
+            .with_line_ending("\n")
+            .add_field("amount", FieldSpecBuilder::new().number().with_length(3).with_validation(ValidationRule::Range { min: Some(1), max: Some(100) }))
+            .add_field("code", FieldSpecBuilder::new().string().with_length(2))
+            .add_field("name", FieldSpecBuilder::new().string().with_length(3).with_validation(ValidationRule::Required))
+            .add_rule(RecordRule::AnyPopulated(vec!["code".to_string()]))
+            .build()
+            .unwrap()
+        ;
+        let writer = RecordWriter::new(FieldWriter::new(IdentityFormatter, BinarySupport));
+        let mut data: Data<BTreeMap<String, Range<usize>>, Vec<u8>> = Data::new();
+        data.push("amount", "101");
+        data.push("name", "   ");
+        let errors = writer.check(&record_spec, &data);
+        assert_eq!(
+            vec![None, Some("amount".to_string()), Some("code".to_string()), Some("name".to_string())],
+            errors.iter().map(|e| e.field.clone()).collect::<Vec<_>>()
+        );
+        match errors[2].error {
+            Error::FieldValueRequired => (),
+            ref e => panic!("unexpected error {:?}", e)
+        }
+        data.push("code", "AB");
+        data.push("amount", "100");
+        data.push("name", "abc");
+        assert!(writer.check(&record_spec, &data).is_empty());
+    }
+
+    #[test]
+    fn write_record_with_error_context() {
+        let spec = SpecBuilder::new()
+            .with_record("record1")
+                .with_line_ending("\n")
+                .with_field("code")
+                    .string()
+                    .with_length(2)
+                .end()
+                .with_field("name")
+                    .string()
+                    .with_length(3)
+                    .with_validation(ValidationRule::MinLength(2))
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut data: Data<BTreeMap<String, Range<usize>>, Vec<u8>> = Data::new();
+        data.push("code", "12");
+        data.push("name", "a  ");
+        let mut buf = Cursor::new(Vec::new());
+        let mut writer = WriterBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_formatter(IdentityFormatter)
+            .with_spec_source(::spec::stream::VecStream::from(vec!["record1"]))
+            .with_record_specs(&spec.record_specs)
+            .with_error_context()
+            .build()
+            .unwrap()
+        ;
+        let error = writer.write_record(&data).unwrap_err();
+        assert_eq!(Some(&b"12"[..]), writer.last_record());
+        assert_eq!(
+            format!("{}\n |   \n |123\n |12\n || |\n |  ^", error),
+            writer.error_context(&error).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn write_record_with_typed_errors() {
+        let spec = SpecBuilder::new()
+            .with_record("a")
+                .with_line_ending("\n")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                    .with_default("A")
+                .end()
+                .with_field("value")
+                    .string()
+                    .with_length(2)
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let mut data: Data<BTreeMap<String, Range<usize>>, Vec<u8>> = Data::new();
+        data.push("$id", "A");
+        data.push("value", "123");
+        let mut buf = Cursor::new(Vec::new());
+        let mut writer = WriterBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_formatter(::writer::formatter::DefaultFormatter)
+            .with_spec_source(::writer::spec::ResolverSource::new(::spec::resolver::IdFieldResolver::new()))
+            .with_record_specs(&spec.record_specs)
+            .build()
+            .unwrap()
+        ;
+        assert_result!(
+            Err(PositionalError { error: Error::FormatterFailure(FormatError::ValueTooLong(2, _)), .. }),
+            writer.write_record(&data)
+        );
+
+        let mut record_specs = spec.record_specs.clone();
+        record_specs.insert("b".to_string(), spec.record_specs["a"].clone());
+        let mut buf = Cursor::new(Vec::new());
+        let mut writer = WriterBuilder::new(BinarySupport)
+            .with_source::<Cursor<_>, _>(&mut buf)
+            .with_field_formatter(::writer::formatter::DefaultFormatter)
+            .with_spec_source(::writer::spec::ResolverSource::new(::spec::resolver::IdFieldResolver::new()))
+            .with_record_specs(&record_specs)
+            .build()
+            .unwrap()
+        ;
+        assert_result!(
+            Err(PositionalError { error: Error::SpecStreamError(StreamError::AmbiguousRecord(ref names)), .. }) if *names == vec!["a".to_string(), "b".to_string()],
+            writer.write_record(&data)
+        );
+    }
+
+    #[test]
+    fn write_record_with_compiled_spec() {
+        let spec = SpecBuilder::new()
+            .with_record("a")
+                .with_line_ending("\n")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                    .with_default("A")
+                .end()
+                .with_field("value")
+                    .string()
+                    .with_length(3)
+                .end()
+            .end()
+            .with_record("b")
+                .with_line_ending("\n")
+                .with_field("$id")
+                    .string()
+                    .with_length(1)
+                    .with_default("B")
+                .end()
+            .end()
+            .build()
+            .unwrap()
+        ;
+        let compiled = ::spec::compiled::CompiledSpec::new(spec).with_id_field("$id");
+        let mut data: Data<BTreeMap<String, Range<usize>>, Vec<u8>> = Data::new();
+        data.push("$id", "A");
+        data.push("value", "12");
+        let mut buf = Cursor::new(Vec::new());
+        {
+            let mut writer = WriterBuilder::new(BinarySupport)
+                .with_source::<Cursor<_>, _>(&mut buf)
+                .with_field_formatter(::writer::formatter::DefaultFormatter)
+                .with_compiled_spec(&compiled)
+                .build()
+                .unwrap()
+            ;
+            assert_result!(Ok(5), writer.write_record(&data));
+            data.push("$id", "Z");
+            assert_result!(Err(PositionalError { error: Error::SpecStreamReturnedNone, .. }), writer.write_record(&data));
+        }
+        assert_eq!(b"A12 \n".to_vec(), buf.into_inner());
+    }
+}
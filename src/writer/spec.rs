@@ -2,11 +2,13 @@ use spec::RecordSpec;
 use std::collections::HashMap;
 use record::{Data, DataRanges};
 use data_type::{WriteSupport};
-use super::super::BoxedErrorResult as Result;
 use spec::resolver::IdFieldResolver;
-use spec::stream::VecStream;
+use spec::compiled::IdIndex;
+use spec::stream::{VecStream, StreamError};
 use std::borrow::Borrow;
 
+type Result<T> = ::std::result::Result<T, StreamError>;
+
 pub trait Stream<T: WriteSupport> {
     fn next<'a, 'b, U: DataRanges + 'a>(&mut self, data: &'a Data<U, T::DataHolder>, record_specs: &'b HashMap<String, RecordSpec>, write_support: &'a T) -> Result<Option<&'b str>>;
 }
@@ -21,6 +23,12 @@ pub trait Resolver<T: WriteSupport> {
     fn resolve<'a, 'b, U: DataRanges + 'a>(&self, data: &'a Data<U, T::DataHolder>, record_specs: &'b HashMap<String, RecordSpec>, write_support: &'a T) -> Result<Option<&'b str>>;
 }
 
+impl<'c, T: Resolver<U> + 'c, U: WriteSupport> Resolver<U> for &'c T {
+    fn resolve<'a, 'b, V: DataRanges + 'a>(&self, data: &'a Data<V, U::DataHolder>, record_specs: &'b HashMap<String, RecordSpec>, write_support: &'a U) -> Result<Option<&'b str>> {
+        Resolver::resolve(*self, data, record_specs, write_support)
+    }
+}
+
 impl<'c, T: Resolver<U> + 'c, U: WriteSupport> Resolver<U> for &'c mut T {
     fn resolve<'a, 'b, V: DataRanges + 'a>(&self, data: &'a Data<V, U::DataHolder>, record_specs: &'b HashMap<String, RecordSpec>, write_support: &'a U) -> Result<Option<&'b str>> {
         Resolver::resolve(*self, data, record_specs, write_support)
@@ -55,19 +63,33 @@ impl <'a, T, U> Stream<U> for ResolverSource<'a, T, U>
 
 impl<'a, T: WriteSupport, U: Borrow<str>> Resolver<T> for IdFieldResolver<U> {
     fn resolve<'b, 'c, V: DataRanges + 'b>(&self, data: &'b Data<V, T::DataHolder>, record_specs: &'c HashMap<String, RecordSpec>, write_support: &'b T) -> Result<Option<&'c str>> {
+        let mut found: Option<&'c str> = None;
+
         for (name, record_spec) in record_specs.iter() {
             if let Some(ref field_spec) = record_spec.field_specs.get(self.id_field()) {
                 if let Some(ref default) = field_spec.default {
                     if let Some(data) = write_support.get_data_by_name(&self.id_field(), data) {
                         if data == &default[..] {
-                            return Ok(Some(name));
+                            found = match found {
+                                None => Some(name),
+                                Some(other) => return Err(StreamError::ambiguous(other, name))
+                            };
                         }
                     }
                 }
             }
         }
 
-        Ok(None)
+        Ok(found)
+    }
+}
+
+impl<T: WriteSupport> Resolver<T> for IdIndex {
+    fn resolve<'a, 'b, U: DataRanges + 'a>(&self, data: &'a Data<U, T::DataHolder>, record_specs: &'b HashMap<String, RecordSpec>, write_support: &'a T) -> Result<Option<&'b str>> {
+        let id = write_support.get_data_by_name(self.id_field(), data);
+        let name = self.lookup(|_| id)?;
+
+        Ok(name.and_then(|name| record_specs.get_key_value(&name[..])).map(|(name, _)| &name[..]))
     }
 }
 